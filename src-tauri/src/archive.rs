@@ -0,0 +1,197 @@
+//! Zip archive operations module
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tauri::{command, Emitter, Window};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Progress event payload emitted as `zip_progress`
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct ZipProgress {
+    operation: String,
+    archive_path: String,
+    processed: usize,
+    total: usize,
+    current_path: String,
+}
+
+fn emit_progress(window: &Window, operation: &str, archive_path: &str, processed: usize, total: usize, current_path: &str) {
+    let _ = window.emit("zip_progress", ZipProgress {
+        operation: operation.to_string(),
+        archive_path: archive_path.to_string(),
+        processed,
+        total,
+        current_path: current_path.to_string(),
+    });
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn file_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    let _ = fs::set_permissions(path, fs::Permissions::from_mode(mode));
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) {}
+
+/// Create a zip archive from a file or directory
+///
+/// # Arguments
+/// * `source_path` - The file or directory to archive
+/// * `output_zip` - The path of the zip file to create
+/// * `window` - Window that receives `zip_progress` events
+///
+/// # Returns
+/// Result indicating success or error message
+#[command]
+pub async fn create_zip(source_path: String, output_zip: String, window: Window) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || create_zip_blocking(&source_path, &output_zip, &window))
+        .await
+        .map_err(|e| format!("Failed to run zip task: {}", e))?
+}
+
+fn create_zip_blocking(source_path: &str, output_zip: &str, window: &Window) -> Result<(), String> {
+    let source = Path::new(source_path);
+    if !source.exists() {
+        return Err(format!("Source path does not exist: {}", source_path));
+    }
+
+    // Entries are named relative to the source's parent so the archive keeps its top-level folder
+    let base = source.parent().unwrap_or_else(|| Path::new(""));
+    let output = Path::new(output_zip);
+
+    // Symlinks inside the source are skipped: their targets may dangle or lie outside the archived tree
+    let entries: Vec<walkdir::DirEntry> = WalkDir::new(source)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_type().is_symlink() && e.path() != output)
+        .collect();
+
+    let file = fs::File::create(output)
+        .map_err(|e| format!("Failed to create zip file: {}", e))?;
+    let mut writer = ZipWriter::new(file);
+    let total = entries.len();
+
+    for (index, entry) in entries.iter().enumerate() {
+        let path = entry.path();
+        let relative = path.strip_prefix(base).unwrap_or(path);
+        let name = relative.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let metadata = entry.metadata()
+            .map_err(|e| format!("Failed to read metadata: {}", e))?;
+
+        let mut options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        if let Some(mode) = file_mode(&metadata) {
+            options = options.unix_permissions(mode);
+        }
+
+        if metadata.is_dir() {
+            writer.add_directory(format!("{}/", name), options)
+                .map_err(|e| format!("Failed to add directory to zip: {}", e))?;
+        } else {
+            writer.start_file(name.as_str(), options)
+                .map_err(|e| format!("Failed to add file to zip: {}", e))?;
+            let mut input = fs::File::open(path)
+                .map_err(|e| format!("Failed to open file: {}", e))?;
+            io::copy(&mut input, &mut writer)
+                .map_err(|e| format!("Failed to write file to zip: {}", e))?;
+        }
+
+        emit_progress(window, "create", output_zip, index + 1, total, &name);
+    }
+
+    writer.finish()
+        .map_err(|e| format!("Failed to finish zip file: {}", e))?;
+
+    Ok(())
+}
+
+/// Extract a zip archive into a directory
+///
+/// # Arguments
+/// * `zip_path` - The zip file to extract
+/// * `destination` - The directory to extract into
+/// * `window` - Window that receives `zip_progress` events
+///
+/// # Returns
+/// Result indicating success or error message. Entries that would escape the
+/// destination (e.g. `../evil`) are rejected.
+#[command]
+pub async fn extract_zip(zip_path: String, destination: String, window: Window) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || extract_zip_blocking(&zip_path, &destination, &window))
+        .await
+        .map_err(|e| format!("Failed to run unzip task: {}", e))?
+}
+
+fn extract_zip_blocking(zip_path: &str, destination: &str, window: &Window) -> Result<(), String> {
+    let file = fs::File::open(zip_path)
+        .map_err(|e| format!("Failed to open zip file: {}", e))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read zip file: {}", e))?;
+
+    let destination = Path::new(destination);
+    fs::create_dir_all(destination)
+        .map_err(|e| format!("Failed to create destination directory: {}", e))?;
+
+    let total = archive.len();
+    // Directory modes are applied once extraction is done, so a read-only directory still receives its contents
+    let mut directory_modes: Vec<(PathBuf, u32)> = Vec::new();
+
+    for index in 0..total {
+        let mut entry = archive.by_index(index)
+            .map_err(|e| format!("Failed to read zip entry: {}", e))?;
+
+        // enclosed_name rejects absolute paths and `..` components (zip-slip)
+        let relative = entry.enclosed_name()
+            .ok_or_else(|| format!("Refusing to extract unsafe zip entry: {}", entry.name()))?;
+        let target = destination.join(&relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        } else {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+            }
+            let mut output = fs::File::create(&target)
+                .map_err(|e| format!("Failed to create file: {}", e))?;
+            io::copy(&mut entry, &mut output)
+                .map_err(|e| format!("Failed to extract file: {}", e))?;
+        }
+
+        if let Some(mode) = entry.unix_mode() {
+            if entry.is_dir() {
+                directory_modes.push((target, mode));
+            } else {
+                set_mode(&target, mode);
+            }
+        }
+
+        emit_progress(window, "extract", zip_path, index + 1, total, &relative.to_string_lossy());
+    }
+
+    // Deepest first, so a parent never loses write access before its subdirectories are done
+    directory_modes.sort_by_key(|(path, _)| std::cmp::Reverse(path.components().count()));
+    for (path, mode) in directory_modes {
+        set_mode(&path, mode);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Bookmarks module for persisting pinned files per project
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, Manager};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+/// Resolves the bookmarks file of a project inside the app data directory
+///
+/// Projects are keyed by a hash of their canonical path so bookmarks
+/// never leak between workspaces that share a folder name.
+fn bookmarks_file(app: &AppHandle, project_path: &str) -> Result<PathBuf, String> {
+    let app_dir = app.path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+
+    let canonical = fs::canonicalize(project_path)
+        .unwrap_or_else(|_| PathBuf::from(project_path));
+    let key = format!("{:x}", Sha256::digest(canonical.to_string_lossy().as_bytes()));
+
+    Ok(app_dir.join("bookmarks").join(format!("{}.json", key)))
+}
+
+/// Saves the bookmarked files of a project
+///
+/// # Arguments
+/// * `project_path` - Root path of the project the bookmarks belong to
+/// * `bookmarks` - Paths of the bookmarked files, in display order
+/// * `app` - The Tauri application handle
+///
+/// # Returns
+/// A Result indicating success or failure
+#[command]
+pub async fn save_bookmarks(project_path: String, bookmarks: Vec<String>, app: AppHandle) -> Result<(), String> {
+    let bookmarks_file = bookmarks_file(&app, &project_path)?;
+
+    if let Some(parent) = bookmarks_file.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let bookmarks_json = json!({
+        "project_path": project_path,
+        "bookmarks": bookmarks,
+        "timestamp": chrono::Local::now().to_rfc3339()
+    });
+
+    fs::write(bookmarks_file, bookmarks_json.to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Loads the bookmarked files of a project
+///
+/// # Arguments
+/// * `project_path` - Root path of the project
+/// * `app` - The Tauri application handle
+///
+/// # Returns
+/// A Result containing the bookmarks that still exist on disk, or an empty
+/// vector if none were saved. Dead entries are pruned from the stored file.
+#[command]
+pub async fn load_bookmarks(project_path: String, app: AppHandle) -> Result<Vec<String>, String> {
+    let bookmarks_file = bookmarks_file(&app, &project_path)?;
+
+    if !bookmarks_file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&bookmarks_file)
+        .map_err(|e| e.to_string())?;
+
+    let stored: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| e.to_string())?;
+
+    let bookmarks: Vec<String> = stored["bookmarks"]
+        .as_array()
+        .ok_or_else(|| "Invalid bookmarks format".to_string())?
+        .iter()
+        .filter_map(|v| v.as_str().map(|s| s.to_string()))
+        .collect();
+
+    let valid: Vec<String> = bookmarks.iter()
+        .filter(|path| Path::new(path).exists())
+        .cloned()
+        .collect();
+
+    if valid.len() != bookmarks.len() {
+        save_bookmarks(project_path, valid.clone(), app).await?;
+    }
+
+    Ok(valid)
+}
@@ -0,0 +1,492 @@
+//! Diff module shared by every diff view, so hunks, binary detection and
+//! renames are reported the same way whatever is being compared
+use git2::{Diff, DiffFindOptions, DiffOptions, Repository};
+use serde::Deserialize;
+use similar::{ChangeTag, TextDiff};
+use tauri::command;
+
+use crate::git::{DiffHunk, DiffLine, FileDiff};
+
+/// Lines of unchanged context around each hunk, like git
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, thiserror::Error)]
+pub enum DiffError {
+    #[error("{0}")]
+    Git(#[from] git2::Error),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// What to compare, old side first
+///
+/// Serialized with a `kind` tag, e.g. `{ "kind": "commit_to_commit", "from": null, "to": "HEAD" }`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiffTarget {
+    /// Unstaged changes, including untracked files
+    WorkdirToIndex,
+    /// Staged changes
+    IndexToHead,
+    /// All uncommitted changes
+    WorkdirToHead,
+    /// Changes between two revisions; without `from`, the changes made by `to` itself
+    CommitToCommit { from: Option<String>, to: String },
+    /// Two files on disk, which don't need to be in a repository
+    FileToFile { left_path: String, right_path: String },
+}
+
+/// Options used for every git diff, optionally limited to one repository-relative path.
+/// Hunk staging relies on these producing the same hunks as `compute_diff`
+pub(crate) fn diff_options(file_path: Option<&str>) -> DiffOptions {
+    let mut options = DiffOptions::new();
+    options.context_lines(CONTEXT_LINES as u32);
+    options.include_untracked(true);
+    options.recurse_untracked_dirs(true);
+    options.show_untracked_content(true);
+    if let Some(file_path) = file_path {
+        options.pathspec(file_path);
+        options.disable_pathspec_match(true);
+    }
+    options
+}
+
+/// Computes the differences of `target`, optionally limited to one repository-relative path
+///
+/// # Arguments
+/// * `repo_path` - The repository, required for every target but `FileToFile`
+/// * `target` - What to compare
+/// * `file_path` - Only diff this path (ignored for `FileToFile`)
+///
+/// # Returns
+/// One `FileDiff` per changed file; always exactly one for `FileToFile`
+pub fn compute_diff(repo_path: Option<&str>, target: &DiffTarget, file_path: Option<&str>) -> Result<Vec<FileDiff>, DiffError> {
+    if let DiffTarget::FileToFile { left_path, right_path } = target {
+        return Ok(vec![diff_file_pair(left_path, right_path)?]);
+    }
+
+    let repo_path = repo_path
+        .ok_or_else(|| DiffError::Invalid("A repository is required for this diff".to_string()))?;
+    let repo = Repository::open(repo_path)?;
+
+    let mut diff = git_diff(&repo, target, &mut diff_options(file_path))?;
+
+    // Pairs deletions with additions of similar content, so moved files show as renames
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+
+    collect_file_diffs(&diff)
+}
+
+fn git_diff<'r>(repo: &'r Repository, target: &DiffTarget, options: &mut DiffOptions) -> Result<Diff<'r>, DiffError> {
+    // An unborn HEAD diffs against the empty tree
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree()?),
+        Err(_) => None,
+    };
+
+    let diff = match target {
+        DiffTarget::WorkdirToIndex => repo.diff_index_to_workdir(None, Some(options))?,
+        DiffTarget::IndexToHead => repo.diff_tree_to_index(head_tree.as_ref(), None, Some(options))?,
+        DiffTarget::WorkdirToHead => repo.diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(options))?,
+        DiffTarget::CommitToCommit { from, to } => {
+            let to_commit = resolve_commit(repo, to)?;
+            let from_tree = match from {
+                Some(from) => Some(resolve_commit(repo, from)?.tree()?),
+                // A root commit is compared with the empty tree
+                None => match to_commit.parent(0) {
+                    Ok(parent) => Some(parent.tree()?),
+                    Err(_) => None,
+                },
+            };
+
+            repo.diff_tree_to_tree(from_tree.as_ref(), Some(&to_commit.tree()?), Some(options))?
+        },
+        DiffTarget::FileToFile { .. } => {
+            return Err(DiffError::Invalid("Files are not diffed through the repository".to_string()));
+        },
+    };
+
+    Ok(diff)
+}
+
+// Accepts full ids, short ids and other revspecs like HEAD~1
+fn resolve_commit<'r>(repo: &'r Repository, revision: &str) -> Result<git2::Commit<'r>, DiffError> {
+    repo.revparse_single(revision)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| DiffError::Invalid(format!("Commit '{}' not found: {}", revision, e)))
+}
+
+fn delta_status_to_string(status: git2::Delta) -> String {
+    match status {
+        git2::Delta::Added | git2::Delta::Untracked => "added",
+        git2::Delta::Deleted => "deleted",
+        git2::Delta::Renamed => "renamed",
+        git2::Delta::Copied => "copied",
+        git2::Delta::Typechange => "typechange",
+        _ => "modified",
+    }.to_string()
+}
+
+/// Converts every delta of a git diff to the shared `FileDiff` structure
+pub(crate) fn collect_file_diffs(diff: &Diff) -> Result<Vec<FileDiff>, DiffError> {
+    let mut files = Vec::new();
+
+    for (index, delta) in diff.deltas().enumerate() {
+        let new_path = delta.new_file().path().map(|p| p.to_string_lossy().to_string());
+        let old_path = delta.old_file().path().map(|p| p.to_string_lossy().to_string());
+        let path = new_path.clone().or_else(|| old_path.clone()).unwrap_or_default();
+
+        let patch = git2::Patch::from_diff(diff, index)?;
+        let is_binary = delta.flags().is_binary() || patch.is_none();
+
+        let mut hunks = Vec::new();
+        if let Some(patch) = patch.filter(|_| !is_binary) {
+            for hunk_index in 0..patch.num_hunks() {
+                let (hunk, line_count) = patch.hunk(hunk_index)?;
+
+                let mut lines = Vec::with_capacity(line_count);
+                for line_index in 0..line_count {
+                    let line = patch.line_in_hunk(hunk_index, line_index)?;
+
+                    // Skip "\ No newline at end of file" markers
+                    if !matches!(line.origin(), '+' | '-' | ' ') {
+                        continue;
+                    }
+
+                    lines.push(DiffLine {
+                        origin: line.origin(),
+                        content: String::from_utf8_lossy(line.content()).trim_end_matches(['\r', '\n']).to_string(),
+                        old_line: line.old_lineno(),
+                        new_line: line.new_lineno(),
+                    });
+                }
+
+                hunks.push(DiffHunk {
+                    header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                    old_start: hunk.old_start(),
+                    old_lines: hunk.old_lines(),
+                    new_start: hunk.new_start(),
+                    new_lines: hunk.new_lines(),
+                    lines,
+                });
+            }
+        }
+
+        files.push(FileDiff {
+            path,
+            old_path: old_path.filter(|old| Some(old) != new_path.as_ref()),
+            status: delta_status_to_string(delta.status()),
+            is_binary,
+            hunks,
+        });
+    }
+
+    Ok(files)
+}
+
+/// Line diff of two files on disk, decoded like the editor decodes them
+fn diff_file_pair(left_path: &str, right_path: &str) -> Result<FileDiff, DiffError> {
+    let left = crate::fs::decode_text(std::fs::read(left_path)?);
+    let right = crate::fs::decode_text(std::fs::read(right_path)?);
+
+    let old_path = Some(left_path.to_string()).filter(|left| left != right_path);
+
+    let (left, right) = match (left, right) {
+        (Some(left), Some(right)) => (left, right),
+        // Diffing bytes isn't useful, so only report that one side is binary
+        _ => {
+            return Ok(FileDiff {
+                path: right_path.to_string(),
+                old_path,
+                status: "modified".to_string(),
+                is_binary: true,
+                hunks: Vec::new(),
+            });
+        }
+    };
+
+    let hunks = diff_text(&left, &right);
+
+    Ok(FileDiff {
+        path: right_path.to_string(),
+        old_path,
+        status: if hunks.is_empty() { "unmodified" } else { "modified" }.to_string(),
+        is_binary: false,
+        hunks,
+    })
+}
+
+/// Line diff of two texts with git's hunk numbering
+fn diff_text(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+
+    let mut hunks = Vec::new();
+    for group in diff.grouped_ops(CONTEXT_LINES) {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old_lines = (last.old_range().end - first.old_range().start) as u32;
+        let new_lines = (last.new_range().end - first.new_range().start) as u32;
+        // Like git, an empty side starts at the line before the change
+        let old_start = first.old_range().start as u32 + u32::from(old_lines > 0);
+        let new_start = first.new_range().start as u32 + u32::from(new_lines > 0);
+
+        let mut lines = Vec::new();
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                lines.push(DiffLine {
+                    origin: match change.tag() {
+                        ChangeTag::Insert => '+',
+                        ChangeTag::Delete => '-',
+                        ChangeTag::Equal => ' ',
+                    },
+                    content: change.value().trim_end_matches(['\r', '\n']).to_string(),
+                    old_line: change.old_index().map(|index| index as u32 + 1),
+                    new_line: change.new_index().map(|index| index as u32 + 1),
+                });
+            }
+        }
+
+        hunks.push(DiffHunk {
+            header: format!("@@ -{},{} +{},{} @@", old_start, old_lines, new_start, new_lines),
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+            lines,
+        });
+    }
+
+    hunks
+}
+
+/// Diffs the working tree, index, commits or two files
+///
+/// # Arguments
+/// * `repo_path` - The repository, required for every target but `file_to_file`
+/// * `target` - What to compare
+/// * `file_path` - Only diff this repository-relative path
+///
+/// # Returns
+/// A Result containing one `FileDiff` per changed file
+#[command]
+pub fn get_diff(repo_path: Option<String>, target: DiffTarget, file_path: Option<String>) -> Result<Vec<FileDiff>, String> {
+    compute_diff(repo_path.as_deref(), &target, file_path.as_deref()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use git2::{Oid, Signature};
+    use tempfile::TempDir;
+
+    const LONG_TEXT: &str = "alpha\nbeta\ngamma\ndelta\nepsilon\nzeta\neta\ntheta\niota\nkappa\n";
+
+    fn init_repo() -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        (dir, repo)
+    }
+
+    fn write(repo: &Repository, path: &str, content: &[u8]) {
+        std::fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+    }
+
+    fn stage(repo: &Repository, paths: &[&str]) {
+        let mut index = repo.index().unwrap();
+        for path in paths {
+            if repo.workdir().unwrap().join(path).exists() {
+                index.add_path(Path::new(path)).unwrap();
+            } else {
+                index.remove_path(Path::new(path)).unwrap();
+            }
+        }
+        index.write().unwrap();
+    }
+
+    fn commit(repo: &Repository, message: &str) -> Oid {
+        let mut index = repo.index().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+    }
+
+    /// A root commit with a text file, a binary file and a file that the second commit renames
+    fn repo_with_history() -> (TempDir, Repository, Oid, Oid) {
+        let (dir, repo) = init_repo();
+        write(&repo, "a.txt", b"one\ntwo\nthree\n");
+        write(&repo, "image.bin", b"\x89PNG\0\0\x01");
+        write(&repo, "long.txt", LONG_TEXT.as_bytes());
+        stage(&repo, &["a.txt", "image.bin", "long.txt"]);
+        let root = commit(&repo, "root");
+
+        std::fs::rename(dir.path().join("long.txt"), dir.path().join("moved.txt")).unwrap();
+        write(&repo, "a.txt", b"one\n2\nthree\n");
+        write(&repo, "image.bin", b"\x89PNG\0\0\x02");
+        stage(&repo, &["a.txt", "image.bin", "long.txt", "moved.txt"]);
+        let second = commit(&repo, "second");
+
+        (dir, repo, root, second)
+    }
+
+    fn diff(dir: &TempDir, target: DiffTarget) -> Vec<FileDiff> {
+        let mut files = compute_diff(dir.path().to_str(), &target, None).unwrap();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    fn find<'a>(files: &'a [FileDiff], path: &str) -> &'a FileDiff {
+        files.iter().find(|file| file.path == path).unwrap_or_else(|| panic!("{} not in diff", path))
+    }
+
+    #[test]
+    fn workdir_to_index_reports_unstaged_and_untracked() {
+        let (dir, repo, _, _) = repo_with_history();
+        write(&repo, "a.txt", b"one\n2\nthree\nfour\n");
+        write(&repo, "new.txt", b"new\n");
+
+        let files = diff(&dir, DiffTarget::WorkdirToIndex);
+        assert_eq!(files.len(), 2);
+
+        let modified = find(&files, "a.txt");
+        assert_eq!(modified.status, "modified");
+        assert_eq!(modified.hunks.len(), 1);
+        assert_eq!(modified.hunks[0].header, "@@ -1,3 +1,4 @@");
+        assert!(modified.hunks[0].lines.iter().any(|line| line.origin == '+' && line.content == "four" && line.new_line == Some(4)));
+
+        assert_eq!(find(&files, "new.txt").status, "added");
+    }
+
+    #[test]
+    fn index_to_head_reports_only_staged_changes() {
+        let (dir, repo, _, _) = repo_with_history();
+        write(&repo, "a.txt", b"staged\n");
+        stage(&repo, &["a.txt"]);
+        write(&repo, "a.txt", b"unstaged\n");
+
+        let files = diff(&dir, DiffTarget::IndexToHead);
+        assert_eq!(files.len(), 1);
+
+        let lines = &files[0].hunks[0].lines;
+        assert!(lines.iter().any(|line| line.origin == '+' && line.content == "staged"));
+        assert!(!lines.iter().any(|line| line.content == "unstaged"));
+    }
+
+    #[test]
+    fn workdir_to_head_combines_staged_and_unstaged() {
+        let (dir, repo, _, _) = repo_with_history();
+        write(&repo, "a.txt", b"staged\n");
+        stage(&repo, &["a.txt"]);
+        write(&repo, "a.txt", b"unstaged\n");
+        std::fs::remove_file(dir.path().join("moved.txt")).unwrap();
+
+        let files = diff(&dir, DiffTarget::WorkdirToHead);
+        assert_eq!(files.len(), 2);
+
+        let lines = &find(&files, "a.txt").hunks[0].lines;
+        assert!(lines.iter().any(|line| line.origin == '+' && line.content == "unstaged"));
+        assert!(!lines.iter().any(|line| line.content == "staged"));
+        assert_eq!(find(&files, "moved.txt").status, "deleted");
+    }
+
+    #[test]
+    fn commit_to_commit_without_from_diffs_against_the_parent() {
+        let (dir, _repo, _, second) = repo_with_history();
+
+        let files = diff(&dir, DiffTarget::CommitToCommit { from: None, to: second.to_string() });
+        assert_eq!(files.len(), 3);
+
+        let renamed = find(&files, "moved.txt");
+        assert_eq!(renamed.status, "renamed");
+        assert_eq!(renamed.old_path.as_deref(), Some("long.txt"));
+        assert!(renamed.hunks.is_empty());
+
+        let binary = find(&files, "image.bin");
+        assert!(binary.is_binary);
+        assert!(binary.hunks.is_empty());
+
+        assert!(!find(&files, "a.txt").is_binary);
+    }
+
+    #[test]
+    fn commit_to_commit_with_from_uses_both_revisions() {
+        let (dir, repo, root, _) = repo_with_history();
+        write(&repo, "b.txt", b"b\n");
+        stage(&repo, &["b.txt"]);
+        commit(&repo, "third");
+
+        let files = diff(&dir, DiffTarget::CommitToCommit { from: Some(root.to_string()), to: "HEAD".to_string() });
+        assert_eq!(files.iter().map(|file| file.path.as_str()).collect::<Vec<_>>(), vec!["a.txt", "b.txt", "image.bin", "moved.txt"]);
+
+        let files = diff(&dir, DiffTarget::CommitToCommit { from: Some("HEAD~1".to_string()), to: "HEAD".to_string() });
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].status, "added");
+    }
+
+    #[test]
+    fn commit_to_commit_root_is_compared_with_the_empty_tree() {
+        let (dir, _repo, root, _) = repo_with_history();
+
+        let files = diff(&dir, DiffTarget::CommitToCommit { from: None, to: root.to_string() });
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|file| file.status == "added"));
+        assert_eq!(find(&files, "a.txt").hunks[0].header, "@@ -0,0 +1,3 @@");
+    }
+
+    #[test]
+    fn commit_to_commit_rejects_unknown_revisions() {
+        let (dir, _repo, _, _) = repo_with_history();
+        let target = DiffTarget::CommitToCommit { from: None, to: "no-such-branch".to_string() };
+
+        assert!(matches!(compute_diff(dir.path().to_str(), &target, None), Err(DiffError::Invalid(_))));
+    }
+
+    #[test]
+    fn file_to_file_diffs_text_and_detects_binary() {
+        let dir = TempDir::new().unwrap();
+        let left = dir.path().join("left.txt");
+        let right = dir.path().join("right.txt");
+        let binary = dir.path().join("right.bin");
+        std::fs::write(&left, "one\ntwo\nthree\n").unwrap();
+        std::fs::write(&right, "one\n2\nthree\n").unwrap();
+        std::fs::write(&binary, b"\0\x01\x02").unwrap();
+
+        let target = DiffTarget::FileToFile {
+            left_path: left.to_string_lossy().to_string(),
+            right_path: right.to_string_lossy().to_string(),
+        };
+        let files = compute_diff(None, &target, None).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].status, "modified");
+        assert_eq!(files[0].hunks[0].header, "@@ -1,3 +1,3 @@");
+        let changed: Vec<(char, &str)> = files[0].hunks[0].lines.iter()
+            .filter(|line| line.origin != ' ')
+            .map(|line| (line.origin, line.content.as_str()))
+            .collect();
+        assert_eq!(changed, vec![('-', "two"), ('+', "2")]);
+
+        let target = DiffTarget::FileToFile {
+            left_path: left.to_string_lossy().to_string(),
+            right_path: binary.to_string_lossy().to_string(),
+        };
+        let files = compute_diff(None, &target, None).unwrap();
+        assert!(files[0].is_binary);
+        assert!(files[0].hunks.is_empty());
+
+        let target = DiffTarget::FileToFile {
+            left_path: left.to_string_lossy().to_string(),
+            right_path: left.to_string_lossy().to_string(),
+        };
+        assert_eq!(compute_diff(None, &target, None).unwrap()[0].status, "unmodified");
+    }
+
+    #[test]
+    fn repository_targets_require_a_repository() {
+        assert!(matches!(compute_diff(None, &DiffTarget::WorkdirToIndex, None), Err(DiffError::Invalid(_))));
+    }
+}
@@ -1,14 +1,86 @@
 /// File system operations module
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::io::Write;
-use tauri::command;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use tauri::{command, AppHandle, Emitter};
 use grep_regex::RegexMatcher;
 use grep_searcher::{Searcher, SearcherBuilder, Sink, SinkMatch, SinkContext, BinaryDetection};
 use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::atomic::{AtomicBool, Ordering};
 use walkdir::WalkDir;
 use globset::{Glob, GlobSetBuilder, GlobSet};
-use std::time::{SystemTime, UNIX_EPOCH};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use fuzzy_matcher::FuzzyMatcher;
+use fuzzy_matcher::skim::SkimMatcherV2;
+
+/// Structured error returned by file system commands.
+/// Serialized as `{ "code": "NotFound", "message": "..." }` so the frontend can branch on `code`.
+#[derive(Debug, thiserror::Error)]
+pub enum FsError {
+    #[error("{0}")]
+    NotFound(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{0}")]
+    AlreadyExists(String),
+    #[error("{0}")]
+    NotADirectory(String),
+    #[error("{0}")]
+    IsADirectory(String),
+    #[error("{0}")]
+    Binary(String),
+    #[error("{0}")]
+    TooLarge(String),
+    /// The file changed on disk since the version the caller expected
+    #[error("{0}")]
+    Conflict(String),
+    #[error("{0}")]
+    Io(String),
+}
+
+impl FsError {
+    /// Map an I/O error to the matching variant, prefixing its message with `context`
+    fn from_io(context: &str, error: std::io::Error) -> Self {
+        let message = format!("{}: {}", context, error);
+        match error.kind() {
+            ErrorKind::NotFound => FsError::NotFound(message),
+            ErrorKind::PermissionDenied => FsError::PermissionDenied(message),
+            ErrorKind::AlreadyExists => FsError::AlreadyExists(message),
+            ErrorKind::NotADirectory => FsError::NotADirectory(message),
+            ErrorKind::IsADirectory => FsError::IsADirectory(message),
+            _ => FsError::Io(message),
+        }
+    }
+    
+    fn code(&self) -> &'static str {
+        match self {
+            FsError::NotFound(_) => "NotFound",
+            FsError::PermissionDenied(_) => "PermissionDenied",
+            FsError::AlreadyExists(_) => "AlreadyExists",
+            FsError::NotADirectory(_) => "NotADirectory",
+            FsError::IsADirectory(_) => "IsADirectory",
+            FsError::Binary(_) => "Binary",
+            FsError::TooLarge(_) => "TooLarge",
+            FsError::Conflict(_) => "Conflict",
+            FsError::Io(_) => "Io",
+        }
+    }
+}
+
+impl serde::Serialize for FsError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("FsError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
 
 /// Create a new directory at the specified path
 /// 
@@ -23,40 +95,289 @@ pub fn create_directory(path: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to create directory: {}", e))
 }
 
+/// Make sure the directory a file will be written to exists
+/// 
+/// With `create_parents`, missing ancestors are created like `mkdir -p`; otherwise a
+/// missing parent is an error. Fails with `NotADirectory` naming the component that
+/// exists but is a file.
+fn prepare_parent_dirs(path: &Path, create_parents: bool) -> Result<(), FsError> {
+    let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) else {
+        return Ok(());
+    };
+
+    if parent.is_dir() {
+        return Ok(());
+    }
+
+    // The closest ancestor that exists decides whether the rest can be created
+    if let Some(existing) = parent.ancestors().find(|ancestor| ancestor.exists()) {
+        if !existing.is_dir() {
+            return Err(FsError::NotADirectory(format!(
+                "Cannot create {}: {} is a file, not a directory",
+                path.display(),
+                existing.display()
+            )));
+        }
+    }
+
+    if !create_parents {
+        return Err(FsError::NotFound(format!("Parent directory does not exist: {}", parent.display())));
+    }
+
+    fs::create_dir_all(parent)
+        .map_err(|e| FsError::from_io("Failed to create parent directory", e))
+}
+
 /// Create a new file with the given content
 /// 
 /// # Arguments
 /// * `path` - The path where the file should be created
 /// * `content` - The content to write to the file
+/// * `create_parents` - Whether to create missing parent directories first (default true)
 /// 
 /// # Returns
 /// Result indicating success or error message
 #[command]
-pub fn create_file(path: String, content: String) -> Result<(), String> {
-    let parent = Path::new(&path).parent();
+pub fn create_file(path: String, content: String, create_parents: Option<bool>) -> Result<(), FsError> {
+    prepare_parent_dirs(Path::new(&path), create_parents.unwrap_or(true))?;
     
-    if let Some(parent_path) = parent {
-        if !parent_path.exists() {
-            fs::create_dir_all(parent_path)
-                .map_err(|e| format!("Failed to create parent directory: {}", e))?;
+    fs::write(&path, content)
+        .map_err(|e| FsError::from_io("Failed to create file", e))
+}
+
+/// Read the content of a file
+/// 
+/// # Arguments
+/// * `path` - The path of the file to read
+/// 
+/// # Returns
+/// The file content with the mtime and hash to pass back to `write_to_file`, or error message
+#[command]
+pub fn read_file(path: String) -> Result<FileContent, FsError> {
+    let mut file = fs::File::open(&path)
+        .map_err(|e| FsError::from_io("Failed to read file", e))?;
+    
+    // Taken before reading, so a write racing the read shows up as a conflict on save
+    let modified = file.metadata()
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| FsError::from_io("Failed to read modification time", e))?;
+    
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .map_err(|e| FsError::from_io("Failed to read file", e))?;
+    
+    let hash = format!("{:x}", Sha256::digest(&bytes));
+    let content = String::from_utf8(bytes)
+        .map_err(|_| FsError::Io("Failed to read file: stream did not contain valid UTF-8".to_string()))?;
+    
+    Ok(FileContent {
+        content,
+        mtime: modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        hash,
+    })
+}
+
+/// Content of a file along with the version it was read at
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FileContent {
+    content: String,
+    /// Same value `get_file_mtime` reports
+    mtime: u64,
+    /// sha256 digest of the content, as `compute_file_hash` reports it
+    hash: String,
+}
+
+/// Read bytes appended to a file since the given offset
+/// 
+/// # Arguments
+/// * `path` - The path of the file to follow
+/// * `from_byte_offset` - The offset returned by the previous call (0 to start from the beginning)
+/// 
+/// # Returns
+/// The newly appended content and the offset to pass on the next call
+#[command]
+pub fn tail_file(path: String, from_byte_offset: u64) -> Result<TailResult, String> {
+    let mut file = fs::File::open(&path)
+        .map_err(|e| format!("Failed to open file: {}", e))?;
+    
+    let file_len = file.metadata()
+        .map_err(|e| format!("Failed to read metadata: {}", e))?
+        .len();
+    
+    // The file shrank below our offset, so it was truncated or rotated
+    let truncated = file_len < from_byte_offset;
+    let start = if truncated { 0 } else { from_byte_offset };
+    
+    file.seek(SeekFrom::Start(start))
+        .map_err(|e| format!("Failed to seek in file: {}", e))?;
+    
+    let mut buffer = Vec::with_capacity((file_len - start) as usize);
+    file.take(file_len - start).read_to_end(&mut buffer)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    
+    // Leave an incomplete trailing UTF-8 sequence for the next poll
+    let mut consumed = buffer.len();
+    if let Err(e) = std::str::from_utf8(&buffer) {
+        if e.error_len().is_none() {
+            consumed = e.valid_up_to();
         }
     }
     
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to create file: {}", e))
+    let content = String::from_utf8_lossy(&buffer[..consumed]).to_string();
+    
+    Ok(TailResult {
+        content,
+        new_offset: start + consumed as u64,
+        truncated,
+    })
 }
 
-/// Read the content of a file
+/// Result of following a file
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct TailResult {
+    content: String,
+    new_offset: u64,
+    truncated: bool,
+}
+
+/// Cached line count of a file along with the size and modification time it was computed for
+type CachedLineCount = (u64, Option<SystemTime>, usize);
+
+/// Line counts of files read through `read_file_range`, keyed by path
+static LINE_COUNT_CACHE: Lazy<Mutex<HashMap<String, CachedLineCount>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Count lines in a file without loading it into memory
+fn count_lines(path: &str) -> Result<usize, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read metadata: {}", e))?;
+    let len = metadata.len();
+    let modified = metadata.modified().ok();
+    
+    if let Some((cached_len, cached_modified, count)) = LINE_COUNT_CACHE.lock().unwrap().get(path) {
+        if *cached_len == len && *cached_modified == modified {
+            return Ok(*count);
+        }
+    }
+    
+    let mut reader = BufReader::new(fs::File::open(path)
+        .map_err(|e| format!("Failed to open file: {}", e))?);
+    let mut chunk = [0u8; 64 * 1024];
+    let mut count = 0;
+    let mut last_byte = None;
+    
+    loop {
+        let read = reader.read(&mut chunk)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        count += chunk[..read].iter().filter(|&&b| b == b'\n').count();
+        last_byte = Some(chunk[read - 1]);
+    }
+    
+    // A final line without a trailing newline still counts
+    if matches!(last_byte, Some(b) if b != b'\n') {
+        count += 1;
+    }
+    
+    LINE_COUNT_CACHE.lock().unwrap().insert(path.to_string(), (len, modified, count));
+    
+    Ok(count)
+}
+
+/// Read a window of lines from a file without loading the whole file
 /// 
 /// # Arguments
 /// * `path` - The path of the file to read
+/// * `start_line` - Zero-based index of the first line to return
+/// * `line_count` - Maximum number of lines to return
+/// 
+/// # Returns
+/// The requested lines, the total number of lines and whether more lines follow
+#[command]
+pub fn read_file_range(path: String, start_line: usize, line_count: usize) -> Result<FileRange, String> {
+    let total_lines = count_lines(&path)?;
+    
+    let mut reader = BufReader::new(fs::File::open(&path)
+        .map_err(|e| format!("Failed to open file: {}", e))?);
+    let mut buffer = Vec::new();
+    let mut lines = Vec::with_capacity(line_count.min(total_lines.saturating_sub(start_line)));
+    let mut line_index = 0;
+    
+    while lines.len() < line_count {
+        buffer.clear();
+        let read = reader.read_until(b'\n', &mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        
+        if line_index >= start_line {
+            let line = String::from_utf8_lossy(&buffer);
+            lines.push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+        
+        line_index += 1;
+    }
+    
+    let has_more = start_line + lines.len() < total_lines;
+    
+    Ok(FileRange {
+        lines,
+        total_lines,
+        has_more,
+    })
+}
+
+/// A window of lines read from a file
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FileRange {
+    lines: Vec<String>,
+    total_lines: usize,
+    has_more: bool,
+}
+
+/// Compute a content hash of a file, streaming it in chunks
+/// 
+/// # Arguments
+/// * `path` - The path of the file to hash
+/// * `algorithm` - Either `sha256` or `blake3`
 /// 
 /// # Returns
-/// The file content or error message
+/// The lowercase hex digest or error message
 #[command]
-pub fn read_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))
+pub fn compute_file_hash(path: String, algorithm: String) -> Result<String, String> {
+    let mut reader = BufReader::new(fs::File::open(&path)
+        .map_err(|e| format!("Failed to open file: {}", e))?);
+    let mut chunk = [0u8; 64 * 1024];
+    
+    match algorithm.to_lowercase().as_str() {
+        "sha256" => {
+            let mut hasher = Sha256::new();
+            loop {
+                let read = reader.read(&mut chunk)
+                    .map_err(|e| format!("Failed to read file: {}", e))?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&chunk[..read]);
+            }
+            Ok(format!("{:x}", hasher.finalize()))
+        },
+        "blake3" => {
+            let mut hasher = blake3::Hasher::new();
+            loop {
+                let read = reader.read(&mut chunk)
+                    .map_err(|e| format!("Failed to read file: {}", e))?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&chunk[..read]);
+            }
+            Ok(hasher.finalize().to_hex().to_string())
+        },
+        other => Err(format!("Unsupported hash algorithm: {}", other)),
+    }
 }
 
 /// Delete a file or directory
@@ -68,20 +389,20 @@ pub fn read_file(path: String) -> Result<String, String> {
 /// # Returns
 /// Result indicating success or error message
 #[command]
-pub fn delete_path(path: String, recursive: bool) -> Result<(), String> {
+pub fn delete_path(path: String, recursive: bool) -> Result<(), FsError> {
     let path_obj = Path::new(&path);
     
     if path_obj.is_dir() {
         if recursive {
             fs::remove_dir_all(&path)
-                .map_err(|e| format!("Failed to delete directory recursively: {}", e))
+                .map_err(|e| FsError::from_io("Failed to delete directory recursively", e))
         } else {
             fs::remove_dir(&path)
-                .map_err(|e| format!("Failed to delete directory: {}", e))
+                .map_err(|e| FsError::from_io("Failed to delete directory", e))
         }
     } else {
         fs::remove_file(&path)
-            .map_err(|e| format!("Failed to delete file: {}", e))
+            .map_err(|e| FsError::from_io("Failed to delete file", e))
     }
 }
 
@@ -94,9 +415,40 @@ pub fn delete_path(path: String, recursive: bool) -> Result<(), String> {
 /// # Returns
 /// Result indicating success or error message
 #[command]
-pub fn rename_path(from_path: String, to_path: String) -> Result<(), String> {
+pub fn rename_path(from_path: String, to_path: String) -> Result<(), FsError> {
+    if is_case_only_rename(&from_path, &to_path) {
+        return rename_case_only(Path::new(&from_path), Path::new(&to_path));
+    }
+    
     fs::rename(&from_path, &to_path)
-        .map_err(|e| format!("Failed to rename: {}", e))
+        .map_err(|e| FsError::from_io("Failed to rename", e))
+}
+
+/// Whether two paths differ only by letter case
+fn is_case_only_rename(from_path: &str, to_path: &str) -> bool {
+    from_path != to_path && from_path.to_lowercase() == to_path.to_lowercase()
+}
+
+/// Rename through a temporary name so case-insensitive filesystems (macOS, Windows)
+/// actually apply a change that only affects letter case
+fn rename_case_only(from: &Path, to: &Path) -> Result<(), FsError> {
+    let file_name = from.file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| FsError::Io(format!("Invalid file name: {}", from.display())))?;
+    let temp = from.with_file_name(format!(".{}.{}.rename-tmp", file_name, uuid::Uuid::new_v4()));
+    
+    fs::rename(from, &temp)
+        .map_err(|e| FsError::from_io("Failed to rename", e))?;
+    
+    if let Err(e) = fs::rename(&temp, to) {
+        // Put the original back so nothing is lost
+        if let Err(restore_error) = fs::rename(&temp, from) {
+            return Err(FsError::from_io(&format!("Failed to rename: {}. The file was left at {}", e, temp.display()), restore_error));
+        }
+        return Err(FsError::from_io("Failed to rename", e));
+    }
+    
+    Ok(())
 }
 
 /// Check if a path exists
@@ -123,6 +475,90 @@ pub fn is_directory(path: String) -> bool {
     Path::new(&path).is_dir()
 }
 
+/// Resolve a path to its absolute form with symlinks, `.` and `..` resolved
+/// 
+/// # Arguments
+/// * `path` - The path to resolve; it must exist
+/// 
+/// # Returns
+/// The canonical path, without the `\\?\` prefix Windows adds to local drive paths
+#[command]
+pub fn canonicalize_path(path: String) -> Result<String, FsError> {
+    let canonical = fs::canonicalize(&path)
+        .map_err(|e| FsError::from_io(&format!("Failed to resolve '{}'", path), e))?;
+    let canonical = canonical.to_string_lossy();
+    
+    // Keep the prefix for UNC paths (\\?\UNC\server\share), where it is meaningful
+    match canonical.strip_prefix(r"\\?\") {
+        Some(stripped) if !stripped.starts_with("UNC\\") => Ok(stripped.to_string()),
+        _ => Ok(canonical.to_string()),
+    }
+}
+
+/// Lexically normalize an absolute path, resolving `.` and `..` without touching the filesystem
+fn normalize_components(path: &Path) -> Vec<std::path::Component<'_>> {
+    use std::path::Component;
+    
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if matches!(components.last(), Some(Component::Normal(_))) {
+                    components.pop();
+                }
+            }
+            other => components.push(other),
+        }
+    }
+    components
+}
+
+/// Compute the relative path that leads from one absolute path to another
+/// 
+/// # Arguments
+/// * `base` - The absolute path to start from (usually a directory)
+/// * `target` - The absolute path to reach
+/// 
+/// # Returns
+/// The relative path (e.g. `../lib/mod.rs`), or `.` when both paths are the same
+#[command]
+pub fn relative_path(base: String, target: String) -> Result<String, String> {
+    let base_path = Path::new(&base);
+    let target_path = Path::new(&target);
+    
+    if !base_path.is_absolute() || !target_path.is_absolute() {
+        return Err(format!("Both paths must be absolute: '{}', '{}'", base, target));
+    }
+    
+    let base_components = normalize_components(base_path);
+    let target_components = normalize_components(target_path);
+    
+    // Paths on different drives (Windows) have no relative path between them
+    if base_components.first() != target_components.first() {
+        return Err(format!("'{}' and '{}' are on different roots", base, target));
+    }
+    
+    let common = base_components.iter()
+        .zip(target_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &target_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    
+    if relative.as_os_str().is_empty() {
+        return Ok(".".to_string());
+    }
+    
+    Ok(relative.to_string_lossy().to_string())
+}
+
 /// Copy a file
 /// 
 /// # Arguments
@@ -149,6 +585,115 @@ pub fn copy_file(from_path: String, to_path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Recursively copy a directory and its contents. Symlinks are copied as links
+/// rather than followed, so a link back to an ancestor can't recurse forever
+fn copy_dir_recursive(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to)
+        .map_err(|e| format!("Failed to create directory: {}", e))?;
+    
+    let entries = fs::read_dir(from)
+        .map_err(|e| format!("Failed to read directory: {}", e))?;
+    
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()
+            .map_err(|e| format!("Failed to read entry type: {}", e))?;
+        
+        if file_type.is_symlink() {
+            copy_symlink(&source, &target)?;
+        } else if file_type.is_dir() {
+            copy_dir_recursive(&source, &target)?;
+        } else {
+            fs::copy(&source, &target)
+                .map_err(|e| format!("Failed to copy file: {}", e))?;
+        }
+    }
+    
+    Ok(())
+}
+
+/// Create a symlink at `target` pointing where the symlink at `source` points
+fn copy_symlink(source: &Path, target: &Path) -> Result<(), String> {
+    let link_target = fs::read_link(source)
+        .map_err(|e| format!("Failed to read symlink: {}", e))?;
+    
+    #[cfg(unix)]
+    let result = std::os::unix::fs::symlink(&link_target, target);
+    
+    #[cfg(windows)]
+    let result = if source.is_dir() {
+        std::os::windows::fs::symlink_dir(&link_target, target)
+    } else {
+        std::os::windows::fs::symlink_file(&link_target, target)
+    };
+    
+    result.map_err(|e| format!("Failed to copy symlink: {}", e))
+}
+
+/// Split a file name into the part before the first extension and the extensions,
+/// so `archive.tar.gz` becomes (`archive`, `.tar.gz`) and `.env` stays whole
+fn split_name_extension(name: &str) -> (&str, &str) {
+    let first_len = name.chars().next().map_or(0, |c| c.len_utf8());
+    match name[first_len..].find('.') {
+        Some(index) => name.split_at(index + first_len),
+        None => (name, ""),
+    }
+}
+
+/// Duplicate a file or directory next to the original
+/// 
+/// # Arguments
+/// * `path` - The file or directory to duplicate
+/// 
+/// # Returns
+/// The path of the copy (`foo copy.rs`, `foo copy 2.rs`, ...) or error message
+#[command]
+pub fn duplicate_path(path: String) -> Result<String, String> {
+    let source = Path::new(&path);
+    if !source.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+    
+    let parent = source.parent()
+        .ok_or_else(|| format!("Cannot find parent directory for: {}", path))?;
+    let name = source.file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Invalid file name: {}", path))?;
+    
+    let (stem, extension) = if source.is_dir() { (name, "") } else { split_name_extension(name) };
+    
+    let mut counter = 1;
+    let target = loop {
+        let candidate = if counter == 1 {
+            parent.join(format!("{} copy{}", stem, extension))
+        } else {
+            parent.join(format!("{} copy {}{}", stem, counter, extension))
+        };
+        
+        if !candidate.exists() {
+            break candidate;
+        }
+        counter += 1;
+    };
+    
+    let is_symlink = fs::symlink_metadata(source)
+        .map(|metadata| metadata.file_type().is_symlink())
+        .unwrap_or(false);
+    
+    if is_symlink {
+        copy_symlink(source, &target)?;
+    } else if source.is_dir() {
+        copy_dir_recursive(source, &target)?;
+    } else {
+        fs::copy(source, &target)
+            .map_err(|e| format!("Failed to copy file: {}", e))?;
+    }
+    
+    Ok(target.to_string_lossy().to_string())
+}
+
 /// List directory contents
 /// 
 /// # Arguments
@@ -166,8 +711,16 @@ pub fn list_directory(path: String) -> Result<Vec<DirEntry>, String> {
     for entry in entries {
         let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
         let path = entry.path();
-        let metadata = entry.metadata()
+        let link_metadata = entry.metadata()
             .map_err(|e| format!("Failed to read metadata: {}", e))?;
+        let is_symlink = link_metadata.file_type().is_symlink();
+        
+        // Describe what a symlink points at; a broken link is listed as the link itself
+        let metadata = if is_symlink {
+            fs::metadata(&path).unwrap_or(link_metadata)
+        } else {
+            link_metadata
+        };
         
         let name = path.file_name()
             .and_then(|n| n.to_str())
@@ -180,6 +733,7 @@ pub fn list_directory(path: String) -> Result<Vec<DirEntry>, String> {
             name,
             path: path.to_string_lossy().to_string(),
             is_directory: is_dir,
+            is_symlink,
             size: if is_dir { 0 } else { metadata.len() }
         });
     }
@@ -193,6 +747,7 @@ pub struct DirEntry {
     name: String,
     path: String,
     is_directory: bool,
+    is_symlink: bool,
     size: u64
 }
 
@@ -216,66 +771,235 @@ pub fn append_to_file(path: String, content: String) -> Result<(), String> {
         .map_err(|e| format!("Failed to append to file: {}", e))
 }
 
-/// Write text to a file, overwriting existing content
+/// Get the last modification time of a file
 /// 
 /// # Arguments
 /// * `path` - The path of the file
-/// * `content` - The content to write
 /// 
 /// # Returns
-/// Result indicating success or error message
+/// Milliseconds since the Unix epoch or error message
 #[command]
-pub fn write_to_file(path: String, content: String) -> Result<(), String> {
-    
-    if content.is_empty() {
-        println!("WARNING: Attempting to write empty content to file: {}", path);
-    }
-    
-    let file = fs::File::create(&path)
-        .map_err(|e| format!("Failed to create file for writing: {}", e))?;
-    
-    let mut writer = std::io::BufWriter::new(file);
-    let bytes_written = writer.write(content.as_bytes())
-        .map_err(|e| format!("Failed to write to file: {}", e))?;
-    
-    println!("Wrote {} bytes to buffer", bytes_written);
-    
-    writer.flush()
-        .map_err(|e| format!("Failed to flush file buffer: {}", e))?;
-    
-    let file = writer.into_inner()
-        .map_err(|e| format!("Failed to get file handle: {}", e))?;
-    
-    file.sync_all()
-        .map_err(|e| format!("Failed to sync file to disk: {}", e))?;
+pub fn get_file_mtime(path: String) -> Result<u64, String> {
+    let modified = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .map_err(|e| format!("Failed to read modification time: {}", e))?;
     
+    Ok(modified.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64)
+}
+
+/// Line ending style applied when saving a file
+#[derive(serde::Deserialize, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    /// Keep the dominant style of the file being overwritten
+    Auto,
+    Lf,
+    Crlf,
+}
+
+/// Number of bytes of the existing file sampled to detect its line ending style
+const LINE_ENDING_SAMPLE_BYTES: u64 = 64 * 1024;
+
+/// Detects the dominant line ending of a text
+/// 
+/// # Returns
+/// "\r\n" or "\n", or None if the text has no line breaks
+fn detect_line_ending(text: &str) -> Option<&'static str> {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
     
-    match fs::read_to_string(&path) {
-        Ok(read_content) => println!("Verification: Read {} bytes after write", read_content.len()),
-        Err(e) => println!("Error verifying file content after write: {}", e),
+    match (crlf, lf) {
+        (0, 0) => None,
+        (crlf, lf) if crlf > lf => Some("\r\n"),
+        _ => Some("\n"),
     }
+}
+
+/// Detects the dominant line ending of an existing file from its first bytes
+fn detect_file_line_ending(path: &str) -> Option<&'static str> {
+    let file = fs::File::open(path).ok()?;
+    let mut sample = Vec::new();
+    file.take(LINE_ENDING_SAMPLE_BYTES).read_to_end(&mut sample).ok()?;
     
-    Ok(())
+    detect_line_ending(&String::from_utf8_lossy(&sample))
 }
 
-/// Get file information
+/// Applies the save options to the content about to be written
 /// 
 /// # Arguments
-/// * `path` - The path of the file
-/// 
-/// # Returns
-/// FileInfo or error message
-#[command]
-pub fn get_file_info(path: String) -> Result<FileInfo, String> {
-    if !Path::new(&path).exists() {
-        return Err(format!("File does not exist: {}", path));
+/// * `content` - The content to write
+/// * `line_ending` - Line ending every line break is converted to, None keeps each one as is
+/// * `trim_trailing_whitespace` - Whether to strip spaces and tabs at the end of lines
+/// * `ensure_final_newline` - Whether to terminate non-empty content with a line break
+fn normalize_for_save(
+    content: String,
+    line_ending: Option<&str>,
+    trim_trailing_whitespace: bool,
+    ensure_final_newline: bool
+) -> String {
+    if line_ending.is_none() && !trim_trailing_whitespace && !ensure_final_newline {
+        return content;
     }
     
-    let mut content = String::new();
-    let mut attempts = 0;
-    let max_attempts = 3;
+    let mut result = String::with_capacity(content.len());
     
-    while attempts < max_attempts {
+    for line in content.split_inclusive('\n') {
+        let (body, ending) = if let Some(body) = line.strip_suffix("\r\n") {
+            (body, "\r\n")
+        } else if let Some(body) = line.strip_suffix('\n') {
+            (body, "\n")
+        } else {
+            (line, "")
+        };
+        
+        if trim_trailing_whitespace {
+            result.push_str(body.trim_end_matches([' ', '\t']));
+        } else {
+            result.push_str(body);
+        }
+        
+        if !ending.is_empty() {
+            result.push_str(line_ending.unwrap_or(ending));
+        }
+    }
+    
+    if ensure_final_newline && !result.is_empty() && !result.ends_with('\n') {
+        let ending = line_ending
+            .or_else(|| detect_line_ending(&content))
+            .unwrap_or("\n");
+        result.push_str(ending);
+    }
+    
+    result
+}
+
+/// Write text to a file, overwriting existing content
+/// 
+/// # Arguments
+/// * `path` - The path of the file
+/// * `content` - The content to write
+/// * `expected_mtime` - Optional modification time (see `get_file_mtime`) the file must still have
+/// * `expected_hash` - Optional sha256 digest (see `compute_file_hash`) the file must still have
+/// * `line_ending` - Optional line ending to normalize to; `Auto` keeps the existing file's style
+/// * `trim_trailing_whitespace` - Whether to strip trailing whitespace from every line (default false)
+/// * `ensure_final_newline` - Whether to end the file with a line break (default false)
+/// * `create_parents` - Whether to create missing parent directories of a new file (default true)
+/// 
+/// # Returns
+/// Result indicating success or error message. Fails with `Conflict` when the
+/// file on disk no longer matches the expected version.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub fn write_to_file(
+    path: String,
+    content: String,
+    expected_mtime: Option<u64>,
+    expected_hash: Option<String>,
+    line_ending: Option<LineEnding>,
+    trim_trailing_whitespace: Option<bool>,
+    ensure_final_newline: Option<bool>,
+    create_parents: Option<bool>
+) -> Result<(), FsError> {
+    if expected_mtime.is_some() || expected_hash.is_some() {
+        check_unmodified(&path, expected_mtime, expected_hash.as_deref())?;
+    }
+    
+    // Sampled before the file is truncated; a new file keeps the content's own line breaks
+    let target_ending = match line_ending {
+        None => None,
+        Some(LineEnding::Auto) => detect_file_line_ending(&path),
+        Some(LineEnding::Lf) => Some("\n"),
+        Some(LineEnding::Crlf) => Some("\r\n"),
+    };
+    
+    let content = normalize_for_save(
+        content,
+        target_ending,
+        trim_trailing_whitespace.unwrap_or(false),
+        ensure_final_newline.unwrap_or(false)
+    );
+    
+    if content.is_empty() {
+        println!("WARNING: Attempting to write empty content to file: {}", path);
+    }
+    
+    prepare_parent_dirs(Path::new(&path), create_parents.unwrap_or(true))?;
+    
+    let file = fs::File::create(&path)
+        .map_err(|e| FsError::from_io("Failed to create file for writing", e))?;
+    
+    let mut writer = std::io::BufWriter::new(file);
+    let bytes_written = writer.write(content.as_bytes())
+        .map_err(|e| FsError::from_io("Failed to write to file", e))?;
+    
+    println!("Wrote {} bytes to buffer", bytes_written);
+    
+    writer.flush()
+        .map_err(|e| FsError::from_io("Failed to flush file buffer", e))?;
+    
+    let file = writer.into_inner()
+        .map_err(|e| FsError::from_io("Failed to get file handle", e.into_error()))?;
+    
+    file.sync_all()
+        .map_err(|e| FsError::from_io("Failed to sync file to disk", e))?;
+    
+    
+    match fs::read_to_string(&path) {
+        Ok(read_content) => println!("Verification: Read {} bytes after write", read_content.len()),
+        Err(e) => println!("Error verifying file content after write: {}", e),
+    }
+    
+    Ok(())
+}
+
+/// Make sure a file still matches the version the editor loaded
+fn check_unmodified(path: &str, expected_mtime: Option<u64>, expected_hash: Option<&str>) -> Result<(), FsError> {
+    let conflict = || FsError::Conflict(format!("{} was modified on disk since it was loaded", path));
+    
+    if !Path::new(path).exists() {
+        return Err(conflict());
+    }
+    
+    if let Some(expected_mtime) = expected_mtime {
+        if get_file_mtime(path.to_string()).map_err(FsError::Io)? != expected_mtime {
+            return Err(conflict());
+        }
+    }
+    
+    if let Some(expected_hash) = expected_hash {
+        let hash = compute_file_hash(path.to_string(), "sha256".to_string()).map_err(FsError::Io)?;
+        if !hash.eq_ignore_ascii_case(expected_hash) {
+            return Err(conflict());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Get file information
+/// 
+/// # Arguments
+/// * `path` - The path of the file
+/// 
+/// # Returns
+/// FileInfo or error message
+#[command]
+pub fn get_file_info(path: String) -> Result<FileInfo, String> {
+    // Stat the link itself so broken symlinks are still reported
+    let link_metadata = fs::symlink_metadata(&path)
+        .map_err(|_| format!("File does not exist: {}", path))?;
+    let is_symlink = link_metadata.file_type().is_symlink();
+    let symlink_target = if is_symlink {
+        fs::read_link(&path).ok().map(|target| target.to_string_lossy().to_string())
+    } else {
+        None
+    };
+    
+    let mut content = String::new();
+    let mut attempts = 0;
+    // A broken symlink has nothing to read
+    let max_attempts = if Path::new(&path).exists() { 3 } else { 0 };
+    
+    while attempts < max_attempts {
         match fs::read_to_string(&path) {
             Ok(file_content) => {
                 content = file_content;
@@ -308,15 +1032,516 @@ pub fn get_file_info(path: String) -> Result<FileInfo, String> {
     
     let id = format!("{}-{}", path, timestamp);
     
+    let mut line_endings = LineEndingCounter::default();
+    line_endings.feed(content.as_bytes());
+    
     Ok(FileInfo {
         id,
         path,
         name,
+        has_bom: content.starts_with('\u{feff}'),
+        line_endings: line_endings.report(),
         content,
         is_unsaved: false,
+        is_symlink,
+        symlink_target,
+    })
+}
+
+/// Outcome of one path in `get_file_info_batch`
+#[derive(serde::Serialize)]
+pub struct FileInfoResult {
+    path: String,
+    /// Same as `get_file_info` would return, None on error
+    info: Option<FileInfo>,
+    error: Option<String>,
+}
+
+/// Get file information for many paths in one call
+/// 
+/// # Arguments
+/// * `paths` - The paths of the files
+/// 
+/// # Returns
+/// One result per path, in the same order; a failing path doesn't fail the batch
+#[command]
+pub fn get_file_info_batch(paths: Vec<String>) -> Vec<FileInfoResult> {
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .min(paths.len().max(1));
+    let chunk_size = paths.len().div_ceil(workers).max(1);
+    
+    let to_result = |path: &String| match get_file_info(path.clone()) {
+        Ok(info) => FileInfoResult { path: path.clone(), info: Some(info), error: None },
+        Err(error) => FileInfoResult { path: path.clone(), info: None, error: Some(error) },
+    };
+    
+    // Each worker handles a contiguous chunk, so joining in order keeps the input order
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = paths.chunks(chunk_size)
+            .map(|chunk| (chunk, scope.spawn(move || chunk.iter().map(to_result).collect::<Vec<_>>())))
+            .collect();
+        
+        handles.into_iter()
+            .flat_map(|(chunk, handle)| handle.join().unwrap_or_else(|_| {
+                chunk.iter()
+                    .map(|path| FileInfoResult { path: path.clone(), info: None, error: Some("Failed to read file info".to_string()) })
+                    .collect()
+            }))
+            .collect()
+    })
+}
+
+/// Line break style of a text file
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DetectedLineEnding {
+    Lf,
+    Crlf,
+    Mixed,
+}
+
+/// Line break summary of a text file, for the editor's "LF / CRLF" indicator
+#[derive(serde::Serialize, serde::Deserialize, Default, Debug)]
+pub struct LineEndingReport {
+    /// `None` when the file has no line breaks
+    line_ending: Option<DetectedLineEnding>,
+    /// The more common of `Lf` and `Crlf`, what a normalization would convert to
+    dominant_line_ending: Option<DetectedLineEnding>,
+    /// Line breaks not using the dominant style
+    minority_line_endings: u64,
+}
+
+/// Counts LF and CRLF line breaks over a stream of chunks
+#[derive(Default)]
+struct LineEndingCounter {
+    lf: u64,
+    crlf: u64,
+    previous_byte: Option<u8>,
+}
+
+impl LineEndingCounter {
+    fn feed(&mut self, chunk: &[u8]) {
+        for &byte in chunk {
+            if byte == b'\n' {
+                if self.previous_byte == Some(b'\r') {
+                    self.crlf += 1;
+                } else {
+                    self.lf += 1;
+                }
+            }
+            // Skipping NULs lets the same check see "\r\n" in UTF-16 text
+            if byte != 0 {
+                self.previous_byte = Some(byte);
+            }
+        }
+    }
+    
+    fn report(&self) -> LineEndingReport {
+        // Ties go to LF
+        let (dominant, minority) = if self.crlf > self.lf {
+            (DetectedLineEnding::Crlf, self.lf)
+        } else {
+            (DetectedLineEnding::Lf, self.crlf)
+        };
+        
+        let line_ending = match (self.lf, self.crlf) {
+            (0, 0) => None,
+            _ if minority > 0 => Some(DetectedLineEnding::Mixed),
+            _ => Some(dominant),
+        };
+        
+        LineEndingReport {
+            line_ending,
+            dominant_line_ending: line_ending.map(|_| dominant),
+            minority_line_endings: minority,
+        }
+    }
+}
+
+/// Cheap file metadata for deciding how to open a file
+#[derive(serde::Serialize)]
+pub struct FileMetrics {
+    size_bytes: u64,
+    line_count: u64,
+    is_binary: bool,
+    /// "utf-8", "utf-8-bom", "utf-16le", "utf-16be" or "unknown"
+    encoding: String,
+    has_bom: bool,
+    #[serde(flatten)]
+    line_endings: LineEndingReport,
+}
+
+/// Number of leading bytes checked for NUL bytes when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8000;
+
+/// Get the size, line count, encoding and binary status of a file
+/// 
+/// # Arguments
+/// * `path` - The path of the file
+/// 
+/// # Returns
+/// The metrics, computed in a single streaming pass without loading the whole file
+#[command]
+pub fn get_file_metrics(path: String) -> Result<FileMetrics, FsError> {
+    let mut file = fs::File::open(&path).map_err(|e| FsError::from_io("Failed to read file", e))?;
+    
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut size_bytes: u64 = 0;
+    let mut newlines: u64 = 0;
+    let mut last_byte = None;
+    let mut has_nul = false;
+    let mut valid_utf8 = true;
+    // Bytes of a character split across two reads
+    let mut pending: Vec<u8> = Vec::new();
+    let mut encoding = None;
+    let mut line_endings = LineEndingCounter::default();
+    
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| FsError::from_io("Failed to read file", e))?;
+        if read == 0 {
+            break;
+        }
+        let chunk = &buffer[..read];
+        
+        if size_bytes == 0 {
+            encoding = if chunk.starts_with(&[0xEF, 0xBB, 0xBF]) {
+                Some("utf-8-bom")
+            } else if chunk.starts_with(&[0xFF, 0xFE]) {
+                Some("utf-16le")
+            } else if chunk.starts_with(&[0xFE, 0xFF]) {
+                Some("utf-16be")
+            } else {
+                None
+            };
+        }
+        
+        if (size_bytes as usize) < BINARY_SNIFF_BYTES {
+            let sniff_len = (BINARY_SNIFF_BYTES - size_bytes as usize).min(read);
+            has_nul |= chunk[..sniff_len].contains(&0);
+        }
+        
+        if valid_utf8 {
+            pending.extend_from_slice(chunk);
+            match std::str::from_utf8(&pending) {
+                Ok(_) => pending.clear(),
+                // Only an incomplete trailing character may continue into the next read
+                Err(e) if e.error_len().is_none() => {
+                    pending.drain(..e.valid_up_to());
+                },
+                Err(_) => {
+                    valid_utf8 = false;
+                    pending.clear();
+                }
+            }
+        }
+        
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count() as u64;
+        line_endings.feed(chunk);
+        last_byte = chunk.last().copied();
+        size_bytes += read as u64;
+    }
+    
+    valid_utf8 &= pending.is_empty();
+    
+    let is_utf16 = matches!(encoding, Some("utf-16le") | Some("utf-16be"));
+    let is_binary = !is_utf16 && (has_nul || !valid_utf8);
+    
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None if valid_utf8 => "utf-8",
+        None => "unknown",
+    };
+    
+    // A final line without a trailing newline still counts
+    let line_count = match last_byte {
+        None => 0,
+        Some(b'\n') => newlines,
+        Some(_) => newlines + 1,
+    };
+    
+    Ok(FileMetrics {
+        size_bytes,
+        line_count,
+        is_binary,
+        has_bom: encoding.starts_with("utf-16") || encoding == "utf-8-bom",
+        encoding: encoding.to_string(),
+        line_endings: line_endings.report(),
+    })
+}
+
+/// Indentation style inferred from a file's leading whitespace
+#[derive(serde::Serialize, Debug, PartialEq)]
+pub struct IndentationInfo {
+    uses_tabs: bool,
+    /// Spaces per indentation level; the default when it can't be inferred
+    width: u8,
+    /// Whether some lines are indented with tabs and others with spaces
+    is_mixed: bool,
+}
+
+/// Indentation width assumed when a file has no indented lines
+const DEFAULT_INDENT_WIDTH: u8 = 4;
+
+/// Lines sampled when inferring indentation, enough for any realistic file head
+const INDENTATION_SAMPLE_LINES: usize = 10_000;
+
+/// Infers the dominant indentation style of a text
+/// 
+/// The width is the most common change in leading spaces between consecutive
+/// non-blank lines. Lines inside fenced code blocks and block comment continuation
+/// lines (` * ...`), which are aligned rather than indented, are skipped.
+/// 
+/// # Arguments
+/// * `content` - The text to inspect
+/// 
+/// # Returns
+/// Whether tabs dominate, the inferred width and whether both styles occur
+pub fn infer_indentation(content: &str) -> IndentationInfo {
+    let mut tab_lines = 0usize;
+    let mut space_lines = 0usize;
+    // Votes for each indentation change of 1 to 8 spaces
+    let mut width_votes = [0usize; 9];
+    let mut previous_spaces = 0usize;
+    let mut in_fence = false;
+    
+    for line in content.lines().take(INDENTATION_SAMPLE_LINES) {
+        let trimmed = line.trim_start();
+        
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || trimmed.is_empty() || trimmed == "*" || trimmed.starts_with("* ") || trimmed.starts_with("*/") {
+            continue;
+        }
+        
+        let indent = &line[..line.len() - trimmed.len()];
+        if indent.starts_with('\t') {
+            tab_lines += 1;
+            continue;
+        }
+        
+        let spaces = indent.len() - indent.trim_start_matches(' ').len();
+        if spaces > 0 {
+            space_lines += 1;
+        }
+        
+        let change = spaces.abs_diff(previous_spaces);
+        if (1..=8).contains(&change) {
+            width_votes[change] += 1;
+        }
+        previous_spaces = spaces;
+    }
+    
+    // Ties go to the smaller width
+    let width = (1..=8)
+        .filter(|&width| width_votes[width] > 0)
+        .max_by_key(|&width| (width_votes[width], std::cmp::Reverse(width)))
+        .map(|width| width as u8)
+        .unwrap_or(DEFAULT_INDENT_WIDTH);
+    
+    IndentationInfo {
+        uses_tabs: tab_lines > space_lines,
+        width,
+        is_mixed: tab_lines > 0 && space_lines > 0,
+    }
+}
+
+/// Detects the indentation style of a file or of unsaved editor content
+/// 
+/// # Arguments
+/// * `path` - The file to read, used when `content` is not given
+/// * `content` - The text to inspect directly
+/// 
+/// # Returns
+/// The inferred indentation, see `infer_indentation`
+#[command]
+pub fn detect_indentation(path: Option<String>, content: Option<String>) -> Result<IndentationInfo, FsError> {
+    let content = match (content, path) {
+        (Some(content), _) => content,
+        (None, Some(path)) => {
+            let bytes = fs::read(&path).map_err(|e| FsError::from_io("Failed to read file", e))?;
+            String::from_utf8_lossy(&bytes).into_owned()
+        },
+        (None, None) => return Err(FsError::Io("Either a path or content is required".to_string())),
+    };
+    
+    Ok(infer_indentation(&content))
+}
+
+/// A selection in a file, zero-based. Columns count characters, `end_line` is inclusive
+/// and `end_column` exclusive; without columns the selection covers whole lines
+#[derive(serde::Deserialize)]
+pub struct TextRange {
+    start_line: usize,
+    start_column: Option<usize>,
+    end_line: usize,
+    end_column: Option<usize>,
+}
+
+/// What was put on the clipboard by `copy_file_to_clipboard`
+#[derive(serde::Serialize)]
+pub struct ClipboardCopy {
+    /// Language detected from the file extension, used to tag the rich text copy
+    language: Option<String>,
+    line_count: usize,
+    char_count: usize,
+}
+
+fn language_for_path(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    
+    let language = match extension.as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" | "cjs" => "javascript",
+        "jsx" => "jsx",
+        "ts" | "mts" | "cts" => "typescript",
+        "tsx" => "tsx",
+        "svelte" => "svelte",
+        "json" => "json",
+        "toml" => "toml",
+        "yaml" | "yml" => "yaml",
+        "md" | "markdown" => "markdown",
+        "html" | "htm" => "html",
+        "css" => "css",
+        "scss" => "scss",
+        "sh" | "bash" | "zsh" => "bash",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cpp" | "cc" | "cxx" | "hpp" => "cpp",
+        "java" => "java",
+        "sql" => "sql",
+        _ => return None,
+    };
+    
+    Some(language)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Decode file contents as text, honoring a UTF-8 or UTF-16 byte order mark
+/// 
+/// # Returns
+/// The text without its BOM, or None if the content looks binary
+pub(crate) fn decode_text(bytes: Vec<u8>) -> Option<String> {
+    // UTF-16 text is full of NUL bytes, so it has to be recognized by its BOM first
+    let utf16 = match bytes.get(..2) {
+        Some([0xFF, 0xFE]) => Some(u16::from_le_bytes as fn([u8; 2]) -> u16),
+        Some([0xFE, 0xFF]) => Some(u16::from_be_bytes as fn([u8; 2]) -> u16),
+        _ => None,
+    };
+    if let Some(from_bytes) = utf16 {
+        let units: Vec<u16> = bytes[2..].chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]])).collect();
+        return String::from_utf16(&units).ok();
+    }
+    
+    if bytes[..bytes.len().min(BINARY_SNIFF_BYTES)].contains(&0) {
+        return None;
+    }
+    
+    let text = String::from_utf8(bytes).ok()?;
+    match text.strip_prefix('\u{feff}') {
+        Some(stripped) => Some(stripped.to_string()),
+        None => Some(text),
+    }
+}
+
+/// Cut a selection out of `content`, keeping the line breaks between selected lines
+fn slice_text_range(content: &str, range: &TextRange) -> String {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    if range.start_line >= lines.len() || range.end_line < range.start_line {
+        return String::new();
+    }
+    let end_line = range.end_line.min(lines.len() - 1);
+    
+    let mut selected = String::new();
+    for (line_number, line) in lines.iter().enumerate().take(end_line + 1).skip(range.start_line) {
+        let text = line.trim_end_matches(['\n', '\r']);
+        let from = if line_number == range.start_line { range.start_column.unwrap_or(0) } else { 0 };
+        let to = if line_number == end_line { range.end_column.unwrap_or(usize::MAX) } else { usize::MAX };
+        
+        selected.extend(text.chars().take(to).skip(from));
+        
+        // The selection ends at the last line's content, not its line break
+        if line_number != end_line {
+            selected.push_str(&line[text.len()..]);
+        }
+    }
+    
+    selected
+}
+
+/// Copy a file, or a selection of it, to the clipboard
+/// 
+/// # Arguments
+/// * `path` - The path of the file
+/// * `range` - The selection to copy, or the whole file when not given
+/// * `app` - The app handle used to access the clipboard
+/// 
+/// # Returns
+/// The detected language and size of the copied text. Binary files are refused with a `Binary` error
+#[command]
+pub fn copy_file_to_clipboard(path: String, range: Option<TextRange>, app: AppHandle) -> Result<ClipboardCopy, FsError> {
+    use tauri_plugin_clipboard_manager::ClipboardExt;
+    
+    let bytes = fs::read(&path).map_err(|e| FsError::from_io("Failed to read file", e))?;
+    let content = decode_text(bytes)
+        .ok_or_else(|| FsError::Binary(format!("{} is a binary file and can't be copied as text", path)))?;
+    
+    let text = match &range {
+        Some(range) => slice_text_range(&content, range),
+        None => content,
+    };
+    let language = language_for_path(Path::new(&path));
+    
+    // Rich text targets get a code block tagged with the language, plain text targets the raw text
+    let html = format!(
+        "<pre><code{}>{}</code></pre>",
+        language.map(|language| format!(" class=\"language-{}\"", language)).unwrap_or_default(),
+        escape_html(&text)
+    );
+    
+    app.clipboard()
+        .write_html(html, Some(text.clone()))
+        .map_err(|e| FsError::Io(format!("Failed to write to clipboard: {}", e)))?;
+    
+    Ok(ClipboardCopy {
+        language: language.map(str::to_string),
+        line_count: text.lines().count(),
+        char_count: text.chars().count(),
     })
 }
 
+/// Compare two files line by line
+/// 
+/// # Arguments
+/// * `left_path` - The original file
+/// * `right_path` - The modified file
+/// 
+/// # Returns
+/// The differences in the same shape as the git diff commands, with 3 lines of context.
+/// If either file is binary, `is_binary` is set and no hunks are computed
+#[command]
+pub fn diff_files(left_path: String, right_path: String) -> Result<crate::git::FileDiff, FsError> {
+    use crate::diff::{DiffError, DiffTarget};
+    
+    let target = DiffTarget::FileToFile { left_path, right_path };
+    match crate::diff::compute_diff(None, &target, None) {
+        Ok(files) => files.into_iter()
+            .next()
+            .ok_or_else(|| FsError::Io("No diff was produced".to_string())),
+        Err(DiffError::Io(e)) => Err(FsError::from_io("Failed to read file", e)),
+        Err(e) => Err(FsError::Io(e.to_string())),
+    }
+}
+
 /// File information structure
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct FileInfo {
@@ -325,6 +1550,12 @@ pub struct FileInfo {
     name: String,
     content: String,
     is_unsaved: bool,
+    is_symlink: bool,
+    /// Where the link points, as stored in the link (may be relative or dangling)
+    symlink_target: Option<String>,
+    has_bom: bool,
+    #[serde(flatten)]
+    line_endings: LineEndingReport,
 }
 
 /// Directory item structure
@@ -406,21 +1637,481 @@ pub fn scan_directory(dir_path: String, depth: u32, max_depth: u32) -> Result<Ve
     Ok(items)
 }
 
-/// Check if a file is an image
-/// 
-/// # Arguments
-/// * `path` - The path of the file
-/// 
-/// # Returns
-/// True if the file is an image, false otherwise
-#[command]
-pub fn is_image_file(path: String) -> bool {
-    let extensions = [".png", ".jpg", ".jpeg", ".gif", ".webp", ".svg", ".bmp"];
+/// Skips `.git` and paths ignored by the repository enclosing a root directory
+struct GitIgnoreFilter {
+    repo: Option<git2::Repository>,
+    workdir: Option<PathBuf>,
+}
+
+impl GitIgnoreFilter {
+    /// Discovers the repository enclosing `root`, which should be canonical
+    fn new(root: &Path) -> Self {
+        let repo = git2::Repository::discover(root).ok();
+        let workdir = repo.as_ref()
+            .and_then(|r| r.workdir())
+            .and_then(|w| w.canonicalize().ok());
+        
+        GitIgnoreFilter { repo, workdir }
+    }
+    
+    fn is_ignored(&self, path: &Path) -> bool {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+        
+        match (&self.repo, &self.workdir) {
+            (Some(repo), Some(workdir)) => path.strip_prefix(workdir)
+                .map(|relative| repo.is_path_ignored(relative).unwrap_or(false))
+                .unwrap_or(false),
+            _ => false,
+        }
+    }
+}
+
+/// Maximum number of rows returned by `scan_directory_flat`
+const MAX_FLAT_SCAN_ENTRIES: usize = 50_000;
+
+/// Flat directory entry for virtualized tree rendering
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct FlatEntry {
+    path: String,
+    name: String,
+    is_dir: bool,
+    depth: u32,
+    /// Index of the parent directory's entry, None for top-level entries
+    parent_index: Option<usize>,
+}
+
+/// Result of a flat directory scan
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct FlatScanResult {
+    entries: Vec<FlatEntry>,
+    truncated: bool,
+}
+
+/// Scan a directory into a flat pre-order list of entries with depth and parent
+/// 
+/// # Arguments
+/// * `path` - The directory path to scan
+/// * `max_depth` - Deepest level to descend into (0 lists only direct children)
+/// 
+/// # Returns
+/// Entries ordered like `scan_directory` (directories first, then alphabetically),
+/// skipping `.git` and paths ignored by the enclosing repository's .gitignore
+#[command]
+pub fn scan_directory_flat(path: String, max_depth: u32) -> Result<FlatScanResult, String> {
+    let root = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve directory: {}", e))?;
+    
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    
+    let ignore_filter = GitIgnoreFilter::new(&root);
+    let is_ignored = |entry_path: &Path| ignore_filter.is_ignored(entry_path);
+    
+    let mut result = FlatScanResult {
+        entries: Vec::new(),
+        truncated: false,
+    };
+    
+    scan_flat_level(&root, 0, max_depth, None, &is_ignored, &mut result)?;
+    
+    Ok(result)
+}
+
+fn scan_flat_level(
+    dir: &Path,
+    depth: u32,
+    max_depth: u32,
+    parent_index: Option<usize>,
+    is_ignored: &dyn Fn(&Path) -> bool,
+    result: &mut FlatScanResult
+) -> Result<(), String> {
+    let mut children: Vec<(String, PathBuf, bool)> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read directory: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let entry_path = entry.path();
+            if is_ignored(&entry_path) {
+                return None;
+            }
+            let is_dir = entry.file_type().map(|t| t.is_dir()).unwrap_or(false);
+            Some((name, entry_path, is_dir))
+        })
+        .collect();
+    
+    // Sort: directories first, then alphabetically
+    children.sort_by(|a, b| {
+        match (a.2, b.2) {
+            (true, false) => std::cmp::Ordering::Less,
+            (false, true) => std::cmp::Ordering::Greater,
+            _ => a.0.to_lowercase().cmp(&b.0.to_lowercase()),
+        }
+    });
+    
+    for (name, entry_path, is_dir) in children {
+        if result.entries.len() >= MAX_FLAT_SCAN_ENTRIES {
+            result.truncated = true;
+            return Ok(());
+        }
+        
+        let index = result.entries.len();
+        result.entries.push(FlatEntry {
+            path: entry_path.to_string_lossy().to_string(),
+            name,
+            is_dir,
+            depth,
+            parent_index,
+        });
+        
+        if is_dir && depth < max_depth {
+            // Unreadable subdirectories are listed but left empty
+            let _ = scan_flat_level(&entry_path, depth + 1, max_depth, Some(index), is_ignored, result);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Number of entries sent per `scan://entry` event
+const SCAN_BATCH_SIZE: usize = 500;
+
+/// Cancellation flags of the streaming scans in progress, keyed by scan id
+static ACTIVE_SCANS: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `scan://entry` event
+#[derive(serde::Serialize, Clone)]
+pub struct ScanEntriesEvent {
+    scan_id: String,
+    entries: Vec<FlatEntry>,
+}
+
+/// Payload of the `scan://done` event
+#[derive(serde::Serialize, Clone)]
+pub struct ScanDoneEvent {
+    scan_id: String,
+    total: usize,
+    cancelled: bool,
+}
+
+/// Scan a directory into a flat pre-order list, reporting entries in batches as they are walked
+/// 
+/// Emits `scan://entry` events carrying up to `batch_size` entries and a final `scan://done`
+/// event, both tagged with `scan_id`. Entries have the same order and shape as
+/// `scan_directory_flat`, with `parent_index` counting across all batches, but the
+/// number of entries is not capped.
+/// 
+/// # Arguments
+/// * `scan_id` - Caller-chosen id of this scan, used for events and cancellation
+/// * `path` - The directory path to scan
+/// * `max_depth` - Deepest level to descend into (0 lists only direct children)
+/// * `batch_size` - Entries per event (defaults to 500)
+/// * `app` - The Tauri application handle
+/// 
+/// # Returns
+/// Ok once the scan has started, or an error if the directory can't be scanned
+#[command]
+pub async fn scan_directory_streaming(
+    scan_id: String,
+    path: String,
+    max_depth: u32,
+    batch_size: Option<usize>,
+    app: AppHandle
+) -> Result<(), String> {
+    let root = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve directory: {}", e))?;
+    
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    
+    let batch_size = batch_size.unwrap_or(SCAN_BATCH_SIZE).max(1);
+    
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut scans = ACTIVE_SCANS.lock().unwrap();
+        if scans.contains_key(&scan_id) {
+            return Err(format!("A scan with id {} is already running", scan_id));
+        }
+        scans.insert(scan_id.clone(), cancelled.clone());
+    }
+    
+    tauri::async_runtime::spawn_blocking(move || {
+        let ignore_filter = GitIgnoreFilter::new(&root);
+        
+        let entries = WalkDir::new(&root)
+            .min_depth(1)
+            .max_depth(max_depth as usize + 1)
+            // Sort: directories first, then alphabetically
+            .sort_by(|a, b| {
+                match (a.file_type().is_dir(), b.file_type().is_dir()) {
+                    (true, false) => std::cmp::Ordering::Less,
+                    (false, true) => std::cmp::Ordering::Greater,
+                    _ => a.file_name().to_string_lossy().to_lowercase().cmp(&b.file_name().to_string_lossy().to_lowercase()),
+                }
+            })
+            .into_iter()
+            .filter_entry(|e| !ignore_filter.is_ignored(e.path()))
+            .filter_map(|e| e.ok());
+        
+        // Index of the latest directory entry at each depth, the parent of whatever follows it one level down
+        let mut directory_indices: Vec<usize> = Vec::new();
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut total = 0;
+        
+        for entry in entries {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            
+            let depth = entry.depth() - 1;
+            let is_dir = entry.file_type().is_dir();
+            
+            directory_indices.truncate(depth);
+            let parent_index = depth.checked_sub(1).and_then(|parent_depth| directory_indices.get(parent_depth).copied());
+            if is_dir {
+                directory_indices.push(total);
+            }
+            
+            batch.push(FlatEntry {
+                path: entry.path().to_string_lossy().to_string(),
+                name: entry.file_name().to_string_lossy().to_string(),
+                is_dir,
+                depth: depth as u32,
+                parent_index,
+            });
+            total += 1;
+            
+            if batch.len() >= batch_size {
+                let _ = app.emit("scan://entry", ScanEntriesEvent {
+                    scan_id: scan_id.clone(),
+                    entries: std::mem::replace(&mut batch, Vec::with_capacity(batch_size)),
+                });
+            }
+        }
+        
+        if !batch.is_empty() {
+            let _ = app.emit("scan://entry", ScanEntriesEvent {
+                scan_id: scan_id.clone(),
+                entries: batch,
+            });
+        }
+        
+        ACTIVE_SCANS.lock().unwrap().remove(&scan_id);
+        
+        let _ = app.emit("scan://done", ScanDoneEvent {
+            scan_id,
+            total,
+            cancelled: cancelled.load(Ordering::Relaxed),
+        });
+    });
+    
+    Ok(())
+}
+
+/// Cancels a streaming scan started with `scan_directory_streaming`
+/// 
+/// # Arguments
+/// * `scan_id` - The id of the scan to cancel
+/// 
+/// # Returns
+/// True if the scan was still running, false otherwise
+#[command]
+pub fn cancel_scan(scan_id: String) -> bool {
+    match ACTIVE_SCANS.lock().unwrap().get(&scan_id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        },
+        None => false,
+    }
+}
+
+/// Quiet period after the last write to a watched file before its change is reported
+const FILE_WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watchers of the files registered with `watch_file`, keyed by the path they were registered with
+static WATCHED_FILES: Lazy<Mutex<HashMap<String, RecommendedWatcher>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `file://external-change` event
+#[derive(serde::Serialize, Clone)]
+pub struct ExternalChangeEvent {
+    /// The path as it was passed to `watch_file`
+    path: String,
+    /// Milliseconds since the Unix epoch, None once the file is gone
+    mtime: Option<u64>,
+    /// sha256 digest of the new content (see `compute_file_hash`), so the editor can
+    /// ignore changes it wrote itself
+    hash: Option<String>,
+    deleted: bool,
+}
+
+/// Watch a single file and emit `file://external-change` events when it changes on disk
+/// 
+/// The file's directory is watched non-recursively, so saves that replace the file
+/// (writing a temporary file and renaming it over) are still noticed. A burst of
+/// writes is reported once, after the file has been quiet for a moment.
+/// 
+/// # Arguments
+/// * `path` - The path of the file to watch
+/// * `app` - The Tauri application handle
+/// 
+/// # Returns
+/// Ok once the file is watched (also when it already was), or an error message
+#[command]
+pub fn watch_file(path: String, app: AppHandle) -> Result<(), String> {
+    let target = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve file: {}", e))?;
+    
+    if target.is_dir() {
+        return Err(format!("Not a file: {}", path));
+    }
+    
+    let directory = target.parent()
+        .ok_or_else(|| format!("File has no parent directory: {}", path))?
+        .to_path_buf();
+    
+    let mut watched_files = WATCHED_FILES.lock().unwrap();
+    if watched_files.contains_key(&path) {
+        return Ok(());
+    }
+    
+    let (change_tx, change_rx) = mpsc::channel::<()>();
+    let watched_path = target.clone();
+    let mut watcher = notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+        if let Ok(event) = result {
+            if !matches!(event.kind, EventKind::Access(_)) && event.paths.contains(&watched_path) {
+                let _ = change_tx.send(());
+            }
+        }
+    }).map_err(|e| format!("Failed to create file watcher: {}", e))?;
+    
+    watcher.watch(&directory, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch file: {}", e))?;
+    
+    let event_path = path.clone();
+    // Ends when `unwatch_file` drops the watcher, and with it the sender
+    std::thread::spawn(move || {
+        while change_rx.recv().is_ok() {
+            loop {
+                match change_rx.recv_timeout(FILE_WATCH_DEBOUNCE) {
+                    Ok(()) => {},
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+            
+            let target_path = target.to_string_lossy().to_string();
+            let _ = app.emit("file://external-change", ExternalChangeEvent {
+                path: event_path.clone(),
+                mtime: get_file_mtime(target_path.clone()).ok(),
+                hash: compute_file_hash(target_path, "sha256".to_string()).ok(),
+                deleted: !target.exists(),
+            });
+        }
+    });
+    
+    watched_files.insert(path, watcher);
+    
+    Ok(())
+}
+
+/// Stop watching a file registered with `watch_file`
+/// 
+/// # Arguments
+/// * `path` - The path the file was registered with
+/// 
+/// # Returns
+/// True if the file was being watched, false otherwise
+#[command]
+pub fn unwatch_file(path: String) -> bool {
+    WATCHED_FILES.lock().unwrap().remove(&path).is_some()
+}
+
+/// Check if a file is an image
+/// 
+/// # Arguments
+/// * `path` - The path of the file
+/// 
+/// # Returns
+/// True if the file is an image, false otherwise
+#[command]
+pub fn is_image_file(path: String) -> bool {
+    let extensions = [".png", ".jpg", ".jpeg", ".gif", ".webp", ".svg", ".bmp"];
     let path_lower = path.to_lowercase();
     
     extensions.iter().any(|ext| path_lower.ends_with(ext))
 }
 
+/// Largest image `read_file_as_data_url` will encode, to keep IPC payloads reasonable
+const MAX_DATA_URL_BYTES: u64 = 25 * 1024 * 1024;
+
+/// MIME type of an image, sniffed from its first bytes and falling back to the extension
+fn image_mime_type(path: &Path, bytes: &[u8]) -> Option<&'static str> {
+    let sniffed = if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        Some("image/webp")
+    } else if bytes.starts_with(b"BM") {
+        Some("image/bmp")
+    } else {
+        None
+    };
+    
+    sniffed.or_else(|| {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "png" => Some("image/png"),
+            "jpg" | "jpeg" => Some("image/jpeg"),
+            "gif" => Some("image/gif"),
+            "webp" => Some("image/webp"),
+            "svg" => Some("image/svg+xml"),
+            "bmp" => Some("image/bmp"),
+            _ => None,
+        }
+    })
+}
+
+/// Read an image as a `data:` URL that can be used directly as an `<img>` source
+/// 
+/// # Arguments
+/// * `path` - The path of the image
+/// 
+/// # Returns
+/// The base64-encoded data URL, or an error if the file is not an image
+/// or is larger than 25 MB
+#[command]
+pub fn read_file_as_data_url(path: String) -> Result<String, FsError> {
+    use base64::Engine;
+    
+    if !is_image_file(path.clone()) {
+        return Err(FsError::Io(format!("{} is not an image", path)));
+    }
+    
+    let size = fs::metadata(&path)
+        .map_err(|e| FsError::from_io("Failed to read file metadata", e))?
+        .len();
+    if size > MAX_DATA_URL_BYTES {
+        return Err(FsError::TooLarge(format!(
+            "{} is too large to preview ({:.1} MB, the limit is {} MB)",
+            path,
+            size as f64 / (1024.0 * 1024.0),
+            MAX_DATA_URL_BYTES / (1024 * 1024)
+        )));
+    }
+    
+    let bytes = fs::read(&path).map_err(|e| FsError::from_io("Failed to read file", e))?;
+    let mime_type = image_mime_type(Path::new(&path), &bytes)
+        .ok_or_else(|| FsError::Io(format!("Unknown image type: {}", path)))?;
+    
+    Ok(format!("data:{};base64,{}", mime_type, base64::engine::general_purpose::STANDARD.encode(&bytes)))
+}
+
 /// Check if a file is an audio file
 /// 
 /// # Arguments
@@ -436,6 +2127,36 @@ pub fn is_audio_file(path: String) -> bool {
     extensions.iter().any(|ext| path_lower.ends_with(ext))
 }
 
+/// Check if a file is a video file
+/// 
+/// # Arguments
+/// * `path` - The path of the file
+/// * `check_content` - Also confirm the file starts with a video container's magic bytes (default: false)
+/// 
+/// # Returns
+/// True if the file is a video file, false otherwise
+#[command]
+pub fn is_video_file(path: String, check_content: Option<bool>) -> bool {
+    let extensions = [".mp4", ".m4v", ".mov", ".webm", ".mkv", ".avi", ".ogv"];
+    let path_lower = path.to_lowercase();
+
+    if !extensions.iter().any(|ext| path_lower.ends_with(ext)) {
+        return false;
+    }
+
+    if !check_content.unwrap_or(false) {
+        return true;
+    }
+
+    match crate::media::read_header(Path::new(&path)) {
+        Ok(header) => matches!(
+            crate::media::sniff_format(&header),
+            Some("mp4" | "webm" | "matroska" | "avi" | "ogg")
+        ),
+        Err(_) => false,
+    }
+}
+
 /// Search file contents with advanced features
 /// 
 /// # Arguments
@@ -760,6 +2481,368 @@ pub fn search_file_contents(query: String, dir_path: String, max_results: u32) -
     Ok(directory_items)
 }
 
+/// Cancellation flags of the streaming searches in progress, keyed by search id
+static ACTIVE_SEARCHES: Lazy<Mutex<HashMap<String, Arc<AtomicBool>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Payload of the `search://result` event
+#[derive(serde::Serialize, Clone)]
+pub struct SearchResultEvent {
+    search_id: String,
+    result: MatchResult,
+}
+
+/// Payload of the `search://done` event
+#[derive(serde::Serialize, Clone)]
+pub struct SearchDoneEvent {
+    search_id: String,
+    total: u32,
+    truncated: bool,
+    cancelled: bool,
+}
+
+/// Search file contents, reporting matches as they are found
+/// 
+/// Emits a `search://result` event per match and a final `search://done` event,
+/// both tagged with `search_id` so concurrent searches can be told apart.
+/// Directories ignored by the enclosing repository (e.g. `target/`) are not scanned.
+/// 
+/// # Arguments
+/// * `search_id` - Caller-chosen id of this search, used for events and cancellation
+/// * `query` - The search query (regex supported)
+/// * `dir_path` - The directory path to search in
+/// * `max_results` - Maximum number of matches to report
+/// * `ignore_case` - Whether to ignore case in search (defaults to true)
+/// * `include_patterns` - Optional glob patterns to include
+/// * `exclude_patterns` - Optional glob patterns to exclude
+/// * `app` - The Tauri application handle
+/// 
+/// # Returns
+/// Ok once the search has started, or an error if the query or patterns are invalid
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_file_contents_streaming(
+    search_id: String,
+    query: String,
+    dir_path: String,
+    max_results: u32,
+    ignore_case: Option<bool>,
+    include_patterns: Option<Vec<String>>,
+    exclude_patterns: Option<Vec<String>>,
+    app: AppHandle
+) -> Result<(), String> {
+    let include_glob = compile_glob_patterns(include_patterns)?;
+    let exclude_glob = compile_glob_patterns(exclude_patterns)?;
+    
+    let pattern = if ignore_case.unwrap_or(true) { format!("(?i){}", query) } else { query.clone() };
+    let matcher = RegexMatcher::new_line_matcher(&pattern)
+        .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+    
+    let root = fs::canonicalize(&dir_path)
+        .map_err(|e| format!("Failed to resolve directory: {}", e))?;
+    
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut searches = ACTIVE_SEARCHES.lock().unwrap();
+        if searches.contains_key(&search_id) {
+            return Err(format!("A search with id {} is already running", search_id));
+        }
+        searches.insert(search_id.clone(), cancelled.clone());
+    }
+    
+    tauri::async_runtime::spawn_blocking(move || {
+        let ignore_filter = GitIgnoreFilter::new(&root);
+        let mut searcher = SearcherBuilder::new()
+            .binary_detection(BinaryDetection::quit(b'\x00'))
+            .line_number(true)
+            .build();
+        
+        let mut total: u32 = 0;
+        let mut truncated = false;
+        
+        let entries = WalkDir::new(&root)
+            .follow_links(true)
+            .into_iter()
+            // Pruning here keeps ignored directories from being walked at all
+            .filter_entry(|e| e.depth() == 0 || !ignore_filter.is_ignored(e.path()))
+            .filter_map(|e| e.ok())
+            .filter(|e| {
+                e.file_type().is_file() &&
+                !is_ignored_file(e.path()) &&
+                include_glob.as_ref().is_none_or(|glob| glob.is_match(e.path())) &&
+                !exclude_glob.as_ref().is_some_and(|glob| glob.is_match(e.path()))
+            });
+        
+        for entry in entries {
+            if cancelled.load(Ordering::Relaxed) {
+                break;
+            }
+            if total >= max_results {
+                truncated = true;
+                break;
+            }
+            
+            let path = entry.path();
+            let name = path.file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_string();
+            let path_str = path.to_string_lossy().to_string();
+            
+            // Files that can't be searched (binary, unreadable) are skipped
+            let _ = searcher.search_path(&matcher, path, grep_searcher::sinks::Lossy(|line_number, line| {
+                if cancelled.load(Ordering::Relaxed) {
+                    return Ok(false);
+                }
+                if total >= max_results {
+                    truncated = true;
+                    return Ok(false);
+                }
+                
+                let _ = app.emit("search://result", SearchResultEvent {
+                    search_id: search_id.clone(),
+                    result: MatchResult {
+                        path: path_str.clone(),
+                        name: name.clone(),
+                        line_number,
+                        preview_text: line.trim().to_string(),
+                        is_directory: false,
+                    },
+                });
+                total += 1;
+                Ok(true)
+            }));
+        }
+        
+        ACTIVE_SEARCHES.lock().unwrap().remove(&search_id);
+        
+        let _ = app.emit("search://done", SearchDoneEvent {
+            search_id,
+            total,
+            truncated,
+            cancelled: cancelled.load(Ordering::Relaxed),
+        });
+    });
+    
+    Ok(())
+}
+
+/// Cancels a streaming search started with `search_file_contents_streaming`
+/// 
+/// # Arguments
+/// * `search_id` - The id of the search to cancel
+/// 
+/// # Returns
+/// True if the search was still running, false otherwise
+#[command]
+pub fn cancel_search(search_id: String) -> bool {
+    match ACTIVE_SEARCHES.lock().unwrap().get(&search_id) {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            true
+        },
+        None => false,
+    }
+}
+
+/// Options of `replace_in_files`
+#[derive(serde::Deserialize, Default)]
+#[serde(default)]
+pub struct ReplaceOptions {
+    /// Whether to ignore case when matching (default false)
+    ignore_case: bool,
+    /// Treat the pattern and replacement as plain text instead of a regex and `$1` template
+    literal: bool,
+    include_patterns: Option<Vec<String>>,
+    exclude_patterns: Option<Vec<String>>,
+    /// Report the changes without writing them
+    dry_run: bool,
+}
+
+/// One changed line of a replacement preview
+#[derive(serde::Serialize)]
+pub struct ReplacePreview {
+    line_number: u64,
+    before: String,
+    after: String,
+}
+
+/// Replacements made (or that would be made) in one file
+#[derive(serde::Serialize)]
+pub struct ReplaceFileResult {
+    path: String,
+    replacements: u32,
+    /// Changed lines, only filled in for dry runs
+    previews: Vec<ReplacePreview>,
+}
+
+/// A file that matched but could not be rewritten
+#[derive(serde::Serialize)]
+pub struct ReplaceFailure {
+    path: String,
+    error: String,
+}
+
+/// Result of `replace_in_files`
+#[derive(serde::Serialize)]
+pub struct ReplaceInFilesResult {
+    files: Vec<ReplaceFileResult>,
+    total_replacements: u32,
+    failed: Vec<ReplaceFailure>,
+}
+
+/// Writes a file by renaming a fully written sibling over it, so readers never see partial content
+pub(crate) fn write_file_atomically(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let file_name = path.file_name()
+        .ok_or_else(|| std::io::Error::new(ErrorKind::InvalidInput, "Path has no file name"))?;
+    let temp_path = path.with_file_name(format!(".{}.horizon-tmp-{}", file_name.to_string_lossy(), std::process::id()));
+    
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+        
+        fs::rename(&temp_path, path)
+    })();
+    
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Search and replace across the files of a directory
+/// 
+/// # Arguments
+/// * `root` - The directory to search in
+/// * `pattern` - The regex (or plain text with `literal`) to look for, matched within single lines
+/// * `replacement` - The replacement, which may reference capture groups like `$1`
+/// * `options` - Case sensitivity, literal mode, glob filters and dry run
+/// 
+/// # Returns
+/// The number of replacements per file, with line previews for dry runs.
+/// Paths ignored by the enclosing repository, binary files and symlinks are skipped.
+#[command]
+pub fn replace_in_files(
+    root: String,
+    pattern: String,
+    replacement: String,
+    options: Option<ReplaceOptions>
+) -> Result<ReplaceInFilesResult, String> {
+    let options = options.unwrap_or_default();
+    
+    if pattern.is_empty() {
+        return Err("Search pattern is empty".to_string());
+    }
+    
+    let include_glob = compile_glob_patterns(options.include_patterns)?;
+    let exclude_glob = compile_glob_patterns(options.exclude_patterns)?;
+    
+    let pattern = if options.literal { regex::escape(&pattern) } else { pattern };
+    let regex = regex::RegexBuilder::new(&pattern)
+        .case_insensitive(options.ignore_case)
+        .build()
+        .map_err(|e| format!("Invalid regex pattern: {}", e))?;
+    
+    let root = fs::canonicalize(&root)
+        .map_err(|e| format!("Failed to resolve directory: {}", e))?;
+    let ignore_filter = GitIgnoreFilter::new(&root);
+    
+    let mut result = ReplaceInFilesResult {
+        files: Vec::new(),
+        total_replacements: 0,
+        failed: Vec::new(),
+    };
+    
+    // Symlinks are not followed: the atomic write would replace the link with a regular file
+    // instead of changing its target, which may also lie outside the root
+    let entries = WalkDir::new(&root)
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !ignore_filter.is_ignored(e.path()))
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_type().is_file() &&
+            !is_ignored_file(e.path()) &&
+            include_glob.as_ref().is_none_or(|glob| glob.is_match(e.path())) &&
+            !exclude_glob.as_ref().is_some_and(|glob| glob.is_match(e.path()))
+        });
+    
+    for entry in entries {
+        let path = entry.path();
+        
+        // Binary and non UTF-8 files are never rewritten
+        let Ok(bytes) = fs::read(path) else { continue };
+        if bytes[..bytes.len().min(8000)].contains(&0) {
+            continue;
+        }
+        let Ok(content) = String::from_utf8(bytes) else { continue };
+        
+        if !regex.is_match(&content) {
+            continue;
+        }
+        
+        let mut new_content = String::with_capacity(content.len());
+        let mut replacements: u32 = 0;
+        let mut previews = Vec::new();
+        
+        for (index, line) in content.split_inclusive('\n').enumerate() {
+            let body_len = line.trim_end_matches(['\r', '\n']).len();
+            let (body, ending) = line.split_at(body_len);
+            
+            let count = regex.find_iter(body).count() as u32;
+            if count == 0 {
+                new_content.push_str(line);
+                continue;
+            }
+            
+            let replaced = if options.literal {
+                regex.replace_all(body, regex::NoExpand(&replacement))
+            } else {
+                regex.replace_all(body, replacement.as_str())
+            };
+            
+            if options.dry_run {
+                previews.push(ReplacePreview {
+                    line_number: index as u64 + 1,
+                    before: body.to_string(),
+                    after: replaced.to_string(),
+                });
+            }
+            
+            new_content.push_str(&replaced);
+            new_content.push_str(ending);
+            replacements += count;
+        }
+        
+        // Patterns that only matched across line breaks leave the file untouched
+        if replacements == 0 {
+            continue;
+        }
+        
+        let path_str = path.to_string_lossy().to_string();
+        
+        if !options.dry_run {
+            if let Err(e) = write_file_atomically(path, new_content.as_bytes()) {
+                result.failed.push(ReplaceFailure { path: path_str, error: e.to_string() });
+                continue;
+            }
+        }
+        
+        result.total_replacements += replacements;
+        result.files.push(ReplaceFileResult {
+            path: path_str,
+            replacements,
+            previews,
+        });
+    }
+    
+    Ok(result)
+}
+
 /// Maintain backward compatibility with existing API
 #[command]
 pub fn search_files_by_name(query: String, dir_path: String, max_results: u32) -> Result<Vec<DirectoryItem>, String> {
@@ -771,4 +2854,361 @@ pub fn search_files_by_name(query: String, dir_path: String, max_results: u32) -
         None,  // include_patterns = None
         None   // exclude_patterns = None
     )
-} 
\ No newline at end of file
+} 
+/// Open a file or directory with the system's default application
+/// 
+/// # Arguments
+/// * `path` - The path to open
+/// 
+/// # Returns
+/// Result indicating success or error message
+#[command]
+pub fn open_with_default_app(path: String) -> Result<(), String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+    
+    tauri_plugin_opener::open_path(&path, None::<&str>)
+        .map_err(|e| format!("Failed to open path: {}", e))
+}
+
+/// Show a file or directory selected in the OS file manager
+/// 
+/// # Arguments
+/// * `path` - The path to reveal
+/// 
+/// # Returns
+/// Result indicating success or error message
+#[command]
+pub fn reveal_in_file_manager(path: String) -> Result<(), String> {
+    let target = Path::new(&path);
+    if !target.exists() {
+        return Err(format!("Path does not exist: {}", path));
+    }
+    
+    #[cfg(target_os = "macos")]
+    let status = std::process::Command::new("open")
+        .arg("-R")
+        .arg(target)
+        .status();
+    
+    #[cfg(target_os = "windows")]
+    let status = std::process::Command::new("explorer")
+        .arg(format!("/select,{}", target.display()))
+        .status()
+        // explorer.exe reports a non-zero exit code even when it succeeds
+        .map(|_| std::process::ExitStatus::default());
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let status = {
+        // Ask the freedesktop FileManager1 service to select the item, falling back to opening the parent folder
+        let canonical = target.canonicalize().unwrap_or_else(|_| target.to_path_buf());
+        // Only absolute paths have a file URI; the folder fallback handles the rest
+        let selected = url::Url::from_file_path(&canonical).ok().and_then(|uri| {
+            std::process::Command::new("dbus-send")
+                .args([
+                    "--session",
+                    "--print-reply",
+                    "--dest=org.freedesktop.FileManager1",
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1.ShowItems",
+                ])
+                .arg(format!("array:string:{}", uri))
+                .arg("string:")
+                .status()
+                .ok()
+        });
+        
+        match selected {
+            Some(status) if status.success() => Ok(status),
+            _ => {
+                let folder = if target.is_dir() { target } else { target.parent().unwrap_or(target) };
+                std::process::Command::new("xdg-open").arg(folder).status()
+            }
+        }
+    };
+    
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("File manager exited with status: {}", status)),
+        Err(e) => Err(format!("Failed to launch file manager: {}", e)),
+    }
+}
+
+/// Fuzzy file name match with its score and highlighted characters
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct FuzzyFileMatch {
+    name: String,
+    path: String,
+    relative_path: String,
+    score: i64,
+    /// Character indices into `relative_path` that matched the query
+    matched_indices: Vec<usize>,
+}
+
+/// Search files by name with fuzzy matching, ranked by score
+/// 
+/// # Arguments
+/// * `query` - The fuzzy query (an empty query lists files in walk order)
+/// * `dir_path` - The directory path to search in
+/// * `max_results` - Maximum number of results to return
+/// * `include_patterns` - Optional glob patterns to include
+/// * `exclude_patterns` - Optional glob patterns to exclude
+/// 
+/// # Returns
+/// Matching files sorted by score descending, with relative paths and matched indices
+#[command]
+pub fn search_files_by_name_fuzzy(
+    query: String,
+    dir_path: String,
+    max_results: u32,
+    include_patterns: Option<Vec<String>>,
+    exclude_patterns: Option<Vec<String>>
+) -> Result<Vec<FuzzyFileMatch>, String> {
+    if dir_path.is_empty() {
+        return Ok(Vec::new());
+    }
+    
+    let include_glob = compile_glob_patterns(include_patterns)?;
+    let exclude_glob = compile_glob_patterns(exclude_patterns)?;
+    
+    let matcher = SkimMatcherV2::default().smart_case();
+    let root = Path::new(&dir_path);
+    let mut results = Vec::new();
+    
+    for entry in WalkDir::new(&dir_path)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter(|e| {
+            include_glob.as_ref().is_none_or(|glob| glob.is_match(e.path())) &&
+            !exclude_glob.as_ref().is_some_and(|glob| glob.is_match(e.path()))
+        })
+    {
+        let path = entry.path();
+        let relative_path = path.strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string();
+        
+        // Empty query falls back to plain substring mode, where everything matches
+        let (score, matched_indices) = if query.is_empty() {
+            (0, Vec::new())
+        } else {
+            match matcher.fuzzy_indices(&relative_path, &query) {
+                Some(result) => result,
+                None => continue,
+            }
+        };
+        
+        results.push(FuzzyFileMatch {
+            name: entry.file_name().to_string_lossy().to_string(),
+            path: path.to_string_lossy().to_string(),
+            relative_path,
+            score,
+            matched_indices,
+        });
+        
+        if query.is_empty() && results.len() >= max_results as usize {
+            break;
+        }
+    }
+    
+    results.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.relative_path.len().cmp(&b.relative_path.len())));
+    results.truncate(max_results as usize);
+    
+    Ok(results)
+}
+
+/// Disk space of the volume containing a path
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct DiskUsage {
+    total_bytes: u64,
+    available_bytes: u64,
+    used_bytes: u64,
+}
+
+/// Get total and free space of the disk that contains a path
+/// 
+/// # Arguments
+/// * `path` - Any path on the volume to query
+/// 
+/// # Returns
+/// The matching disk's figures, or zeros when no mounted disk contains the path
+/// (e.g. network mounts that the system does not report)
+#[command]
+pub fn get_disk_usage(path: String) -> Result<DiskUsage, String> {
+    let path = fs::canonicalize(&path)
+        .map_err(|e| format!("Failed to resolve path: {}", e))?;
+    
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    
+    // The most specific mount point wins, e.g. /home over /
+    let disk = disks.list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().components().count());
+    
+    Ok(match disk {
+        Some(disk) => DiskUsage {
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+            used_bytes: disk.total_space().saturating_sub(disk.available_space()),
+        },
+        None => DiskUsage {
+            total_bytes: 0,
+            available_bytes: 0,
+            used_bytes: 0,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn path_string(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn case_only_rename_keeps_content_and_lists_the_new_name() {
+        let dir = TempDir::new().unwrap();
+        let from = dir.path().join("foo.txt");
+        let to = dir.path().join("Foo.txt");
+        fs::write(&from, "content survives").unwrap();
+
+        rename_path(path_string(&from), path_string(&to)).unwrap();
+
+        let names: Vec<String> = list_directory(path_string(dir.path())).unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(names, vec!["Foo.txt"]);
+        assert_eq!(fs::read_to_string(&to).unwrap(), "content survives");
+    }
+
+    #[test]
+    fn case_only_rename_detection() {
+        assert!(is_case_only_rename("/tmp/foo.txt", "/tmp/Foo.txt"));
+        assert!(!is_case_only_rename("/tmp/foo.txt", "/tmp/foo.txt"));
+        assert!(!is_case_only_rename("/tmp/foo.txt", "/tmp/bar.txt"));
+    }
+
+    fn save(path: &str, content: &str, loaded: &FileContent, check_hash: bool) -> Result<(), FsError> {
+        let expected_hash = check_hash.then(|| loaded.hash.clone());
+        let expected_mtime = (!check_hash).then_some(loaded.mtime);
+        write_to_file(path.to_string(), content.to_string(), expected_mtime, expected_hash, None, None, None, None)
+    }
+
+    #[test]
+    fn read_file_reports_the_version_write_to_file_checks() {
+        let dir = TempDir::new().unwrap();
+        let path = path_string(&dir.path().join("notes.txt"));
+        fs::write(&path, "first").unwrap();
+
+        let loaded = read_file(path.clone()).unwrap();
+        assert_eq!(loaded.content, "first");
+        assert_eq!(loaded.mtime, get_file_mtime(path.clone()).unwrap());
+        assert_eq!(loaded.hash, compute_file_hash(path.clone(), "sha256".to_string()).unwrap());
+
+        save(&path, "second", &loaded, true).unwrap();
+        assert!(matches!(save(&path, "third", &loaded, true), Err(FsError::Conflict(_))));
+
+        let reloaded = read_file(path.clone()).unwrap();
+        save(&path, "third", &reloaded, false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "third");
+    }
+
+    #[test]
+    fn write_to_file_conflicts_when_the_file_was_deleted() {
+        let dir = TempDir::new().unwrap();
+        let path = path_string(&dir.path().join("gone.txt"));
+        fs::write(&path, "content").unwrap();
+        let loaded = read_file(path.clone()).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let error = save(&path, "content", &loaded, false).unwrap_err();
+        assert!(matches!(error, FsError::Conflict(_)));
+        assert_eq!(error.code(), "Conflict");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn duplicate_copies_symlinks_as_links() {
+        let dir = TempDir::new().unwrap();
+        let project = dir.path().join("project");
+        fs::create_dir_all(project.join("src")).unwrap();
+        fs::write(project.join("src/main.rs"), "fn main() {}").unwrap();
+        // Following this link would copy the project into itself forever
+        std::os::unix::fs::symlink("..", project.join("src/parent")).unwrap();
+
+        let copy = PathBuf::from(duplicate_path(path_string(&project)).unwrap());
+
+        assert_eq!(copy, dir.path().join("project copy"));
+        assert_eq!(fs::read_to_string(copy.join("src/main.rs")).unwrap(), "fn main() {}");
+        let link = copy.join("src/parent");
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), PathBuf::from(".."));
+
+        let link_copy = PathBuf::from(duplicate_path(path_string(&project.join("src/parent"))).unwrap());
+        assert!(fs::symlink_metadata(&link_copy).unwrap().file_type().is_symlink());
+    }
+
+    fn indentation(uses_tabs: bool, width: u8, is_mixed: bool) -> IndentationInfo {
+        IndentationInfo { uses_tabs, width, is_mixed }
+    }
+
+    #[test]
+    fn infers_tab_indentation() {
+        let content = "fn main() {\n\tif x {\n\t\ty();\n\t}\n}\n";
+        assert_eq!(infer_indentation(content), indentation(true, DEFAULT_INDENT_WIDTH, false));
+    }
+
+    #[test]
+    fn infers_two_space_indentation() {
+        let content = "root:\n  child:\n    leaf: 1\n    other: 2\n  sibling: 3\n";
+        assert_eq!(infer_indentation(content), indentation(false, 2, false));
+    }
+
+    #[test]
+    fn infers_four_space_indentation() {
+        let content = "def f():\n    if x:\n        return 1\n    return 2\n\n\nclass A:\n    pass\n";
+        assert_eq!(infer_indentation(content), indentation(false, 4, false));
+    }
+
+    #[test]
+    fn reports_mixed_indentation() {
+        let content = "a {\n    b;\n    c;\n\td;\n}\n";
+        assert_eq!(infer_indentation(content), indentation(false, 4, true));
+
+        let content = "a {\n\tb;\n\tc;\n  d;\n}\n";
+        assert_eq!(infer_indentation(content), indentation(true, 2, true));
+    }
+
+    #[test]
+    fn skips_fenced_code_and_block_comments() {
+        let content = "\
+# Title
+- item
+  - nested
+```
+        eight spaces in a fence
+\tand a tab
+```
+  - nested again
+/**
+ * aligned comment
+ */
+";
+        assert_eq!(infer_indentation(content), indentation(false, 2, false));
+    }
+
+    #[test]
+    fn unindented_text_uses_the_default_width() {
+        assert_eq!(infer_indentation("one\ntwo\n"), indentation(false, DEFAULT_INDENT_WIDTH, false));
+        assert_eq!(infer_indentation(""), indentation(false, DEFAULT_INDENT_WIDTH, false));
+    }
+}
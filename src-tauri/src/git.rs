@@ -1,6 +1,7 @@
 use git2::{Repository, BranchType, Time, Status, StatusOptions, Signature};
 use serde::{Deserialize, Serialize};
 use tauri::command;
+use std::collections::HashMap;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitBranch {
@@ -8,9 +9,12 @@ pub struct GitBranch {
     pub is_current: bool,
     pub is_remote: bool,
     pub commit_id: String,
+    pub upstream_name: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GitCommit {
     pub id: String,
     pub short_id: String,
@@ -21,9 +25,28 @@ pub struct GitCommit {
     pub date: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitGraphCommit {
+    #[serde(flatten)]
+    pub commit: GitCommit,
+    pub parents: Vec<String>,
+    /// Branches and tags pointing at this commit, e.g. "main", "origin/main", "v1.0"
+    pub refs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlameLine {
+    /// One-based line number in the file at HEAD
+    pub line: u32,
+    #[serde(flatten)]
+    pub commit: GitCommit,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitStatus {
     pub current_branch: Option<String>,
+    pub is_detached: bool,
+    pub head_short_id: Option<String>,
     pub is_repo: bool,
     pub has_changes: bool,
     pub ahead: usize,
@@ -36,6 +59,8 @@ pub struct GitFileStatus {
     pub status: String, // "modified", "added", "deleted", "renamed", "untracked"
     pub staged: bool,
     pub unstaged: bool,
+    /// Where a renamed file was moved from
+    pub old_path: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -44,6 +69,14 @@ pub struct GitChanges {
     pub unstaged: Vec<GitFileStatus>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitIgnoredFiles {
+    /// Absolute paths; an ignored directory is listed once and covers everything below it
+    pub ignored: Vec<String>,
+    /// Untracked paths, only filled when requested; anything else listed in the directory is tracked
+    pub untracked: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitRemoteStatus {
     pub remote_name: String,
@@ -53,6 +86,14 @@ pub struct GitRemoteStatus {
     pub has_remote: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitTrackingBranch {
+    pub remote: String,
+    pub remote_ref: String,
+    pub ahead: usize,
+    pub behind: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitPushResult {
     pub success: bool,
@@ -60,6 +101,13 @@ pub struct GitPushResult {
     pub pushed_commits: usize,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitPruneResult {
+    pub pruned_count: usize,
+    /// Remote-tracking refs that were deleted, e.g. "refs/remotes/origin/old-feature"
+    pub pruned_refs: Vec<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitPullResult {
     pub success: bool,
@@ -68,6 +116,45 @@ pub struct GitPullResult {
     pub conflicts: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitRevertResult {
+    pub success: bool,
+    pub message: String,
+    pub commit_id: Option<String>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoState {
+    /// "clean", "merge", "rebase", "cherry_pick" or "revert"
+    pub operation: String,
+    /// Commit being merged, cherry-picked or reverted, or the commit a rebase replays onto
+    pub target_commit: Option<String>,
+    /// Branch being rebased, e.g. "feature"
+    pub rebase_branch: Option<String>,
+    /// One-based step of the rebase and its number of steps
+    pub rebase_step: Option<usize>,
+    pub rebase_total: Option<usize>,
+    pub has_conflicts: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConflictVersions {
+    pub path: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationIssue {
+    /// "empty_subject", "subject_too_long", "missing_type", "invalid_format" or "missing_blank_line"
+    pub code: String,
+    pub message: String,
+    /// Zero-based line of the message the issue refers to
+    pub line: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitUserConfig {
     pub name: String,
@@ -75,6 +162,43 @@ pub struct GitUserConfig {
     pub has_config: bool,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffLine {
+    /// '+' for additions, '-' for deletions, ' ' for context
+    pub origin: char,
+    pub content: String,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<DiffLine>,
+}
+
+/// Identifies a hunk by its line ranges; a `DiffHunk` can be passed as is
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HunkRange {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDiff {
+    pub path: String,
+    pub old_path: Option<String>,
+    pub status: String,
+    pub is_binary: bool,
+    pub hunks: Vec<DiffHunk>,
+}
+
 fn format_timestamp(time: Time) -> String {
     let datetime = chrono::DateTime::from_timestamp(time.seconds(), 0)
         .unwrap_or_else(|| chrono::DateTime::from_timestamp(0, 0).unwrap());
@@ -95,12 +219,26 @@ fn status_to_string(status: Status) -> String {
     }
 }
 
+// (new path, old path) of a renamed delta
+fn rename_paths(delta: &git2::DiffDelta) -> Option<(String, String)> {
+    if delta.status() != git2::Delta::Renamed {
+        return None;
+    }
+    
+    let new_path = delta.new_file().path()?.to_string_lossy().to_string();
+    let old_path = delta.old_file().path()?.to_string_lossy().to_string();
+    Some((new_path, old_path))
+}
+
 #[command]
 pub fn get_git_changes(path: String) -> Result<GitChanges, String> {
     let repo = Repository::open(&path).map_err(|e| e.to_string())?;
     let mut opts = StatusOptions::new();
     opts.include_untracked(true);
     opts.include_ignored(false);
+    // Like `git status`, only staged renames are detected: an unstaged rename is a
+    // deletion plus an untracked file, which have to be staged separately
+    opts.renames_head_to_index(true);
     
     let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
     
@@ -108,10 +246,15 @@ pub fn get_git_changes(path: String) -> Result<GitChanges, String> {
     let mut unstaged = Vec::new();
     
     for entry in statuses.iter() {
-        let file_path = entry.path().unwrap_or("").to_string();
         let status = entry.status();
         let status_str = status_to_string(status);
         
+        // For renames entry.path() is the source; report the destination with the source alongside
+        let staged_rename = entry.head_to_index().and_then(|delta| rename_paths(&delta));
+        let file_path = staged_rename.as_ref()
+            .map(|(new_path, _)| new_path.clone())
+            .unwrap_or_else(|| entry.path().unwrap_or("").to_string());
+        
         // Check if file is staged (in index)
         let is_staged = status.intersects(
             Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_DELETED | Status::INDEX_RENAMED | Status::INDEX_TYPECHANGE
@@ -125,9 +268,10 @@ pub fn get_git_changes(path: String) -> Result<GitChanges, String> {
         if is_staged {
             staged.push(GitFileStatus {
                 path: file_path.clone(),
-                status: status_str.clone(),
+                status: if staged_rename.is_some() { "renamed".to_string() } else { status_str.clone() },
                 staged: true,
                 unstaged: is_unstaged,
+                old_path: staged_rename.map(|(_, old_path)| old_path),
             });
         }
         
@@ -137,6 +281,7 @@ pub fn get_git_changes(path: String) -> Result<GitChanges, String> {
                 status: status_str,
                 staged: is_staged,
                 unstaged: true,
+                old_path: None,
             });
         }
     }
@@ -163,10 +308,104 @@ pub fn unstage_file(repo_path: String, file_path: String) -> Result<(), String>
     let head_tree = head_commit.tree().map_err(|e| e.to_string())?;
     
     let mut index = repo.index().map_err(|e| e.to_string())?;
-    let path = std::path::Path::new(&file_path);
+    
+    reset_index_path(&mut index, Some(&head_tree), &file_path).map_err(|e| e.to_string())?;
+    
+    index.write().map_err(|e| e.to_string())?;
+    
+    Ok(())
+}
+
+fn hunk_matches(candidate: &git2::DiffHunk, hunk: &HunkRange) -> bool {
+    candidate.old_start() == hunk.old_start
+        && candidate.old_lines() == hunk.old_lines
+        && candidate.new_start() == hunk.new_start
+        && candidate.new_lines() == hunk.new_lines
+}
+
+// Applies the single hunk of `diff` matching `hunk` to the index. The ranges must
+// come from a diff with the default 3 lines of context, like `crate::diff::compute_diff`
+fn apply_hunk_to_index(repo: &Repository, diff: &git2::Diff, hunk: &HunkRange) -> Result<(), String> {
+    let found = std::cell::Cell::new(false);
+    
+    let mut options = git2::ApplyOptions::new();
+    options.hunk_callback(|candidate| {
+        let matches = !found.get() && candidate.is_some_and(|candidate| hunk_matches(&candidate, hunk));
+        if matches {
+            found.set(true);
+        }
+        matches
+    });
+    
+    repo.apply(diff, git2::ApplyLocation::Index, Some(&mut options))
+        .map_err(|e| e.to_string())?;
+    drop(options);
+    
+    if !found.get() {
+        return Err("Hunk not found, the file may have changed since the diff was taken".to_string());
+    }
+    
+    Ok(())
+}
+
+#[command]
+pub fn stage_hunk(repo_path: String, file_path: String, hunk: HunkRange) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    let mut opts = crate::diff::diff_options(Some(&file_path));
+    let diff = repo.diff_index_to_workdir(None, Some(&mut opts)).map_err(|e| e.to_string())?;
+    
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    if index.get_path(std::path::Path::new(&file_path), 0).is_none() {
+        // Patches can't be applied to paths missing from the index, but an untracked
+        // file's diff is a single hunk covering the whole file, so stage all of it
+        let patch = git2::Patch::from_diff(&diff, 0).map_err(|e| e.to_string())?;
+        let whole_file = patch.as_ref()
+            .filter(|patch| patch.num_hunks() == 1)
+            .and_then(|patch| patch.hunk(0).ok())
+            .is_some_and(|(candidate, _)| hunk_matches(&candidate, &hunk));
+        if !whole_file {
+            return Err("Hunk not found, the file may have changed since the diff was taken".to_string());
+        }
+    
+        index.add_path(std::path::Path::new(&file_path)).map_err(|e| e.to_string())?;
+        index.write().map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    
+    apply_hunk_to_index(&repo, &diff, &hunk)
+}
+
+#[command]
+pub fn unstage_hunk(repo_path: String, file_path: String, hunk: HunkRange) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().map_err(|e| e.to_string())?),
+        Err(_) => None,
+    };
+    
+    // The staged diff reversed (index -> HEAD) undoes the hunk when applied to the index
+    let mut opts = crate::diff::diff_options(Some(&file_path));
+    opts.reverse(true);
+    let diff = repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))
+        .map_err(|e| e.to_string())?;
+    
+    // `hunk` describes the staged (HEAD -> index) diff, so its sides are swapped here
+    let reversed = HunkRange {
+        old_start: hunk.new_start,
+        old_lines: hunk.new_lines,
+        new_start: hunk.old_start,
+        new_lines: hunk.old_lines,
+    };
+    
+    apply_hunk_to_index(&repo, &diff, &reversed)
+}
+
+fn reset_index_path(index: &mut git2::Index, head_tree: Option<&git2::Tree>, file_path: &str) -> Result<(), git2::Error> {
+    let path = std::path::Path::new(file_path);
     
     // Reset the file in index to HEAD version
-    if let Ok(entry) = head_tree.get_path(path) {
+    if let Some(entry) = head_tree.and_then(|tree| tree.get_path(path).ok()) {
         index.add(&git2::IndexEntry {
             ctime: git2::IndexTime::new(0, 0),
             mtime: git2::IndexTime::new(0, 0),
@@ -180,34 +419,168 @@ pub fn unstage_file(repo_path: String, file_path: String) -> Result<(), String>
             flags: 0,
             flags_extended: 0,
             path: file_path.as_bytes().to_vec(),
-        }).map_err(|e| e.to_string())?;
+        })
     } else {
         // File is new, remove it from index
-        index.remove_path(path).map_err(|e| e.to_string())?;
+        index.remove_path(path)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitBatchFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitBatchResult {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<GitBatchFailure>,
+}
+
+#[command]
+pub fn stage_files(repo_path: String, paths: Vec<String>) -> Result<GitBatchResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?.to_path_buf();
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    
+    let mut result = GitBatchResult { succeeded: Vec::new(), failed: Vec::new() };
+    
+    for file_path in paths {
+        let path = std::path::Path::new(&file_path);
+        
+        // Deleted files can't be added, staging them means removing them from the index
+        let staged = if workdir.join(path).exists() {
+            index.add_path(path)
+        } else {
+            index.remove_path(path)
+        };
+        
+        match staged {
+            Ok(()) => result.succeeded.push(file_path),
+            Err(e) => result.failed.push(GitBatchFailure { path: file_path, error: e.to_string() }),
+        }
     }
     
+    // Write the index once for the whole batch
     index.write().map_err(|e| e.to_string())?;
     
-    Ok(())
+    Ok(result)
+}
+
+#[command]
+pub fn unstage_files(repo_path: String, paths: Vec<String>) -> Result<GitBatchResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    // An unborn HEAD has no tree, so every staged file is new
+    let head_tree = match repo.head() {
+        Ok(head) => Some(head.peel_to_tree().map_err(|e| e.to_string())?),
+        Err(_) => None,
+    };
+    
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    
+    let mut result = GitBatchResult { succeeded: Vec::new(), failed: Vec::new() };
+    
+    for file_path in paths {
+        match reset_index_path(&mut index, head_tree.as_ref(), &file_path) {
+            Ok(()) => result.succeeded.push(file_path),
+            Err(e) => result.failed.push(GitBatchFailure { path: file_path, error: e.to_string() }),
+        }
+    }
+    
+    index.write().map_err(|e| e.to_string())?;
+    
+    Ok(result)
 }
 
 #[command]
-pub fn stage_all_files(repo_path: String) -> Result<(), String> {
+pub fn stage_all_files(repo_path: String, only_dirty: Option<bool>) -> Result<(), String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
     let mut index = repo.index().map_err(|e| e.to_string())?;
     
-    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None).map_err(|e| e.to_string())?;
+    if only_dirty.unwrap_or(false) {
+        stage_dirty_paths(&repo, &mut index)?;
+    } else {
+        index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, None).map_err(|e| e.to_string())?;
+    }
     index.write().map_err(|e| e.to_string())?;
     
     Ok(())
 }
 
+// Stages only what the status scan reports as changed, one path at a time, instead of
+// letting add_all re-add every file in the working tree
+fn stage_dirty_paths(repo: &Repository, index: &mut git2::Index) -> Result<(), String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true);
+    opts.recurse_untracked_dirs(true);
+    opts.include_ignored(false);
+    
+    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
+    
+    for entry in statuses.iter() {
+        let status = entry.status();
+        let is_dirty = status.intersects(
+            Status::WT_NEW | Status::WT_MODIFIED | Status::WT_DELETED | Status::WT_RENAMED | Status::WT_TYPECHANGE | Status::CONFLICTED
+        );
+        let Some(path) = entry.path().filter(|_| is_dirty) else {
+            continue;
+        };
+        let path = std::path::Path::new(path);
+        
+        if status.contains(Status::WT_DELETED) {
+            index.remove_path(path).map_err(|e| e.to_string())?;
+        } else {
+            index.add_path(path).map_err(|e| e.to_string())?;
+        }
+    }
+    
+    Ok(())
+}
+
+fn resolve_commit_signature(repo: &Repository, author_name: &str, author_email: &str) -> Result<Signature<'static>, String> {
+    let author_name = author_name.trim();
+    let author_email = author_email.trim();
+    
+    if !author_name.is_empty() && !author_email.is_empty() {
+        return Signature::now(author_name, author_email).map_err(|e| e.to_string());
+    }
+    
+    // Fall back to user.name / user.email from the repository (and global) config
+    let configured = repo.signature().map_err(|_| {
+        "Git identity is not configured. Set it with `git config --global user.name \"Your Name\"` and `git config --global user.email \"you@example.com\"`".to_string()
+    })?;
+    
+    let name = if author_name.is_empty() { configured.name().unwrap_or("") } else { author_name };
+    let email = if author_email.is_empty() { configured.email().unwrap_or("") } else { author_email };
+    
+    Signature::now(name, email).map_err(|e| e.to_string())
+}
+
 #[command]
-pub fn commit_changes(repo_path: String, message: String, author_name: String, author_email: String) -> Result<String, String> {
+pub fn commit_changes(
+    repo_path: String,
+    message: String,
+    author_name: String,
+    author_email: String,
+    validate_conventional: Option<bool>,
+) -> Result<String, String> {
+    if validate_conventional.unwrap_or(false) {
+        let issues = validate_commit_message(message.clone());
+        if !issues.is_empty() {
+            let messages: Vec<String> = issues.into_iter().map(|issue| issue.message).collect();
+            return Err(format!("Invalid commit message: {}", messages.join("; ")));
+        }
+    }
+    
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
-    let signature = Signature::now(&author_name, &author_email).map_err(|e| e.to_string())?;
+    let signature = resolve_commit_signature(&repo, &author_name, &author_email)?;
     
     let mut index = repo.index().map_err(|e| e.to_string())?;
+    if index.has_conflicts() {
+        return Err("Resolve all merge conflicts before committing".to_string());
+    }
     let tree_id = index.write_tree().map_err(|e| e.to_string())?;
     let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
     
@@ -216,10 +589,16 @@ pub fn commit_changes(repo_path: String, message: String, author_name: String, a
         Err(_) => None, // First commit
     };
     
-    let parents: Vec<&git2::Commit> = match &parent_commit {
-        Some(commit) => vec![commit],
-        None => vec![],
-    };
+    // Concluding a merge left in progress by pull_from_remote or merge_branch: MERGE_HEAD is the second parent
+    let merge_heads = read_git_file(repo.path(), "MERGE_HEAD").unwrap_or_default();
+    let merge_commits = merge_heads.lines()
+        .map(|id| git2::Oid::from_str(id.trim()).and_then(|id| repo.find_commit(id)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    
+    let parents: Vec<&git2::Commit> = parent_commit.iter()
+        .chain(merge_commits.iter())
+        .collect();
     
     let commit_id = repo.commit(
         Some("HEAD"),
@@ -230,9 +609,106 @@ pub fn commit_changes(repo_path: String, message: String, author_name: String, a
         &parents,
     ).map_err(|e| e.to_string())?;
     
+    // This commit concludes a merge, revert or cherry-pick left in progress
+    let concludes_operation = !merge_commits.is_empty() || matches!(
+        repo.state(),
+        git2::RepositoryState::Revert | git2::RepositoryState::CherryPick
+    );
+    if concludes_operation {
+        repo.cleanup_state().map_err(|e| e.to_string())?;
+    }
+    
     Ok(commit_id.to_string())
 }
 
+const MAX_SUBJECT_LENGTH: usize = 72;
+
+static CONVENTIONAL_HEADER: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+    regex::Regex::new(r"^[a-zA-Z]+(\([^()\s][^()]*\))?!?: \S").unwrap()
+});
+
+static HEADER_TYPE: once_cell::sync::Lazy<regex::Regex> = once_cell::sync::Lazy::new(|| {
+    regex::Regex::new(r"^[a-zA-Z]+(\([^()]*\))?!?:").unwrap()
+});
+
+fn validation_issue(code: &str, message: String, line: usize) -> ValidationIssue {
+    ValidationIssue { code: code.to_string(), message, line }
+}
+
+#[command]
+pub fn validate_commit_message(message: String) -> Vec<ValidationIssue> {
+    // Comment lines are stripped by git before committing, so they don't count
+    let lines: Vec<&str> = message.lines()
+        .filter(|line| !line.starts_with('#'))
+        .collect();
+    
+    let mut issues = Vec::new();
+    
+    let subject = lines.first().map(|line| line.trim_end()).unwrap_or("");
+    if subject.trim().is_empty() {
+        issues.push(validation_issue("empty_subject", "Subject line is empty".to_string(), 0));
+        return issues;
+    }
+    
+    let subject_length = subject.chars().count();
+    if subject_length > MAX_SUBJECT_LENGTH {
+        issues.push(validation_issue(
+            "subject_too_long",
+            format!("Subject line is {} characters long, the limit is {}", subject_length, MAX_SUBJECT_LENGTH),
+            0,
+        ));
+    }
+    
+    if !HEADER_TYPE.is_match(subject) {
+        issues.push(validation_issue(
+            "missing_type",
+            "Subject must start with a type, e.g. \"feat: ...\" or \"fix(parser): ...\"".to_string(),
+            0,
+        ));
+    } else if !CONVENTIONAL_HEADER.is_match(subject) {
+        issues.push(validation_issue(
+            "invalid_format",
+            "Subject must follow \"type(scope): description\" with a space after the colon".to_string(),
+            0,
+        ));
+    }
+    
+    if lines.get(1).is_some_and(|line| !line.trim().is_empty()) {
+        issues.push(validation_issue(
+            "missing_blank_line",
+            "Subject and body must be separated by a blank line".to_string(),
+            1,
+        ));
+    }
+    
+    issues
+}
+
+#[command]
+pub fn get_commit_template(repo_path: String) -> Result<Option<String>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir.to_path_buf(),
+        None => return Ok(None),
+    };
+    
+    // An explicit commit.template wins over a .gitmessage at the repository root
+    let configured = repo.config()
+        .and_then(|config| config.get_path("commit.template"))
+        .ok()
+        .map(|path| if path.is_relative() { workdir.join(path) } else { path });
+    
+    let candidates = configured.into_iter().chain(std::iter::once(workdir.join(".gitmessage")));
+    for path in candidates {
+        if path.is_file() {
+            let template = std::fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            return Ok(Some(template));
+        }
+    }
+    
+    Ok(None)
+}
+
 #[command]
 pub fn get_git_status(path: String) -> Result<GitStatus, String> {
     let repo = match Repository::open(&path) {
@@ -240,6 +716,8 @@ pub fn get_git_status(path: String) -> Result<GitStatus, String> {
         Err(_) => {
             return Ok(GitStatus {
                 current_branch: None,
+                is_detached: false,
+                head_short_id: None,
                 is_repo: false,
                 has_changes: false,
                 ahead: 0,
@@ -254,12 +732,22 @@ pub fn get_git_status(path: String) -> Result<GitStatus, String> {
     } else {
         None
     };
+    
+    // Detached after checking out a tag or a specific commit, show where HEAD points instead
+    let is_detached = repo.head_detached().map_err(|e| e.to_string())?;
+    let head_short_id = head
+        .peel_to_commit()
+        .ok()
+        .and_then(|commit| commit.as_object().short_id().ok())
+        .and_then(|buf| buf.as_str().map(|s| s.to_string()));
 
     let statuses = repo.statuses(None).map_err(|e| e.to_string())?;
     let has_changes = !statuses.is_empty();
 
     Ok(GitStatus {
         current_branch,
+        is_detached,
+        head_short_id,
         is_repo: true,
         has_changes,
         ahead: 0, // TODO: implement ahead/behind calculation
@@ -272,9 +760,11 @@ pub fn get_git_branches(path: String) -> Result<Vec<GitBranch>, String> {
     let repo = Repository::open(&path).map_err(|e| e.to_string())?;
     let mut branches = Vec::new();
 
+    // A detached HEAD's shorthand is "HEAD", which must not mark any branch as current
     let current_branch_name = repo
         .head()
         .ok()
+        .filter(|head| head.is_branch())
         .and_then(|head| head.shorthand().map(|s| s.to_string()));
 
     // Get local branches
@@ -289,11 +779,27 @@ pub fn get_git_branches(path: String) -> Result<Vec<GitBranch>, String> {
                 .map(|oid| oid.to_string())
                 .unwrap_or_default();
 
+            // Compare against the configured upstream, if any
+            let mut upstream_name = None;
+            let (mut ahead, mut behind) = (0, 0);
+            if let Ok(upstream) = branch.upstream() {
+                upstream_name = upstream.name().ok().flatten().map(|n| n.to_string());
+                if let (Some(local_oid), Some(upstream_oid)) = (branch.get().target(), upstream.get().target()) {
+                    if let Ok((a, b)) = repo.graph_ahead_behind(local_oid, upstream_oid) {
+                        ahead = a;
+                        behind = b;
+                    }
+                }
+            }
+
             branches.push(GitBranch {
                 name: name.to_string(),
                 is_current,
                 is_remote: false,
                 commit_id,
+                upstream_name,
+                ahead,
+                behind,
             });
         }
     }
@@ -319,6 +825,9 @@ pub fn get_git_branches(path: String) -> Result<Vec<GitBranch>, String> {
                 is_current: false,
                 is_remote: true,
                 commit_id,
+                upstream_name: None,
+                ahead: 0,
+                behind: 0,
             });
         }
     }
@@ -344,87 +853,361 @@ pub fn get_git_commits(path: String, limit: Option<usize>) -> Result<Vec<GitComm
         let oid = oid.map_err(|e| e.to_string())?;
         let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
 
-        let message = commit.message().unwrap_or("").to_string();
-        let author = commit.author();
-        let author_name = author.name().unwrap_or("Unknown").to_string();
-        let author_email = author.email().unwrap_or("").to_string();
-        let time = author.when();
-
-        commits.push(GitCommit {
-            id: oid.to_string(),
-            short_id: oid.to_string()[..7].to_string(),
-            message,
-            author_name,
-            author_email,
-            timestamp: time.seconds(),
-            date: format_timestamp(time),
-        });
+        commits.push(commit_info(&commit));
     }
 
     Ok(commits)
 }
 
-#[command]
-pub fn is_git_repository(path: String) -> Result<bool, String> {
-    match Repository::open(&path) {
-        Ok(_) => Ok(true),
-        Err(_) => Ok(false),
+fn commit_info(commit: &git2::Commit) -> GitCommit {
+    let oid = commit.id();
+    let message = commit.message().unwrap_or("").to_string();
+    let author = commit.author();
+    let author_name = author.name().unwrap_or("Unknown").to_string();
+    let author_email = author.email().unwrap_or("").to_string();
+    let time = author.when();
+
+    GitCommit {
+        id: oid.to_string(),
+        short_id: oid.to_string()[..7].to_string(),
+        message,
+        author_name,
+        author_email,
+        timestamp: time.seconds(),
+        date: format_timestamp(time),
     }
 }
 
 #[command]
-pub fn get_remote_status(repo_path: String) -> Result<GitRemoteStatus, String> {
+pub fn get_commit_graph(repo_path: String, limit: Option<usize>) -> Result<Vec<GitGraphCommit>, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(200);
     
-    // Get current branch
-    let head = repo.head().map_err(|e| e.to_string())?;
-    let local_branch = head.peel_to_commit().map_err(|e| e.to_string())?;
-    
-    // Try to get remote info
-    let remote_name = "origin".to_string();
-    let remote = match repo.find_remote(&remote_name) {
-        Ok(remote) => remote,
-        Err(_) => {
-            return Ok(GitRemoteStatus {
-                remote_name,
-                remote_url: None,
-                ahead: 0,
-                behind: 0,
-                has_remote: false,
-            });
+    // Branch and tag names by the commit they point at (annotated tags are peeled)
+    let mut refs_by_commit: HashMap<git2::Oid, Vec<String>> = HashMap::new();
+    for reference in repo.references().map_err(|e| e.to_string())? {
+        let reference = reference.map_err(|e| e.to_string())?;
+        if !(reference.is_branch() || reference.is_remote() || reference.is_tag()) {
+            continue;
         }
-    };
-    
-    let remote_url = remote.url().map(|s| s.to_string());
+        
+        let (Some(name), Ok(commit)) = (reference.shorthand(), reference.peel_to_commit()) else {
+            continue;
+        };
+        
+        // Skip the symbolic origin/HEAD, it always duplicates a real branch
+        if name.ends_with("/HEAD") {
+            continue;
+        }
+        
+        refs_by_commit.entry(commit.id()).or_default().push(name.to_string());
+    }
     
-    // Get remote branch reference
-    let current_branch_name = head.shorthand().unwrap_or("main");
-    let remote_branch_name = format!("refs/remotes/origin/{}", current_branch_name);
+    // Walk every branch, not just HEAD, so unmerged lanes show up too
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk.push_glob("refs/heads").map_err(|e| e.to_string())?;
+    revwalk.push_glob("refs/remotes").map_err(|e| e.to_string())?;
+    if repo.head().is_ok() {
+        revwalk.push_head().map_err(|e| e.to_string())?;
+    }
+    revwalk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME).map_err(|e| e.to_string())?;
     
-    let (ahead, behind) = match repo.find_reference(&remote_branch_name) {
-        Ok(remote_ref) => {
-            let remote_commit = remote_ref.peel_to_commit().map_err(|e| e.to_string())?;
-            
-            // Calculate ahead/behind
-            let (ahead, behind) = repo.graph_ahead_behind(local_branch.id(), remote_commit.id())
-                .map_err(|e| e.to_string())?;
-            
-            (ahead, behind)
-        }
-        Err(_) => (0, 0), // Remote branch not found
-    };
+    let mut commits = Vec::new();
+    for oid in revwalk.take(limit) {
+        let oid = oid.map_err(|e| e.to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+        
+        commits.push(GitGraphCommit {
+            commit: commit_info(&commit),
+            parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+            refs: refs_by_commit.remove(&oid).unwrap_or_default(),
+        });
+    }
     
-    Ok(GitRemoteStatus {
-        remote_name,
-        remote_url,
-        ahead,
-        behind,
-        has_remote: true,
-    })
+    Ok(commits)
 }
 
 #[command]
-pub fn fetch_from_remote(repo_path: String, remote_name: Option<String>) -> Result<String, String> {
+pub fn get_file_at_commit(repo_path: String, commit_id: String, file_path: String) -> Result<String, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    // Accept full ids, short ids and other revspecs like HEAD~1
+    let commit = repo.revparse_single(&commit_id)
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|e| format!("Commit '{}' not found: {}", commit_id, e))?;
+    let tree = commit.tree().map_err(|e| e.to_string())?;
+    
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?;
+    let relative_path = repo_relative_path(workdir, &file_path)
+        .ok_or_else(|| format!("'{}' is outside of the repository", file_path))?;
+    
+    // The path is looked up as it existed in that commit, so renamed files use their old name
+    let entry = tree.get_path(&relative_path)
+        .map_err(|_| format!("'{}' does not exist in commit {}", file_path, commit.id()))?;
+    let blob = entry.to_object(&repo)
+        .and_then(|object| object.peel_to_blob())
+        .map_err(|_| format!("'{}' is not a file in commit {}", file_path, commit.id()))?;
+    
+    if blob.is_binary() {
+        return Err(format!("'{}' is a binary file in commit {}", file_path, commit.id()));
+    }
+    
+    String::from_utf8(blob.content().to_vec())
+        .map_err(|_| format!("'{}' is not valid UTF-8 in commit {}", file_path, commit.id()))
+}
+
+#[command]
+pub fn blame_range(repo_path: String, file_path: String, start_line: u32, end_line: u32) -> Result<Vec<BlameLine>, String> {
+    if start_line == 0 || end_line < start_line {
+        return Err(format!("Invalid line range {}-{}", start_line, end_line));
+    }
+    
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?;
+    let relative_path = repo_relative_path(workdir, &file_path)
+        .ok_or_else(|| format!("'{}' is outside of the repository", file_path))?;
+    
+    // libgit2 rejects a range past the end of the file, so clamp it to the file at HEAD
+    let head_tree = repo.head()
+        .and_then(|head| head.peel_to_tree())
+        .map_err(|e| e.to_string())?;
+    let blob = head_tree.get_path(&relative_path)
+        .and_then(|entry| entry.to_object(&repo))
+        .and_then(|object| object.peel_to_blob())
+        .map_err(|_| format!("'{}' is not committed", relative_path.display()))?;
+    let line_count = blob.content().split(|&b| b == b'\n').count() as u32
+        - u32::from(blob.content().last().is_none_or(|&b| b == b'\n'));
+    let end_line = end_line.min(line_count);
+    if start_line > end_line {
+        return Ok(Vec::new());
+    }
+    
+    let mut options = git2::BlameOptions::new();
+    options.min_line(start_line as usize).max_line(end_line as usize);
+    let blame = repo.blame_file(&relative_path, Some(&mut options))
+        .map_err(|e| e.to_string())?;
+    
+    // Hunks of the same commit are common, so each commit is only looked up once
+    let mut commits: HashMap<git2::Oid, GitCommit> = HashMap::new();
+    let mut lines = Vec::new();
+    
+    for hunk in blame.iter() {
+        let commit_id = hunk.final_commit_id();
+        let commit = match commits.entry(commit_id) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let commit = repo.find_commit(commit_id).map_err(|e| e.to_string())?;
+                entry.insert(commit_info(&commit))
+            },
+        };
+        
+        let first_line = hunk.final_start_line() as u32;
+        let last_line = first_line + hunk.lines_in_hunk() as u32 - 1;
+        for line in first_line.max(start_line)..=last_line.min(end_line) {
+            lines.push(BlameLine {
+                line,
+                commit: commit.clone(),
+            });
+        }
+    }
+    
+    Ok(lines)
+}
+
+#[command]
+pub fn get_merge_base(repo_path: String, ref_a: String, ref_b: String) -> Result<String, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    // Branch names, tags and (short) commit ids all resolve through revparse
+    let resolve = |reference: &str| {
+        repo.revparse_single(reference)
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id())
+            .map_err(|e| format!("Ref '{}' not found: {}", reference, e))
+    };
+    
+    let commit_a = resolve(&ref_a)?;
+    let commit_b = resolve(&ref_b)?;
+    
+    match repo.merge_base(commit_a, commit_b) {
+        Ok(base) => Ok(base.to_string()),
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            Err(format!("'{}' and '{}' have no common history", ref_a, ref_b))
+        },
+        Err(e) => Err(e.to_string()),
+    }
+}
+#[command]
+pub fn is_git_repository(path: String) -> Result<bool, String> {
+    match Repository::open(&path) {
+        Ok(_) => Ok(true),
+        Err(_) => Ok(false),
+    }
+}
+
+#[command]
+pub fn is_path_ignored(repo_path: String, file_path: String) -> Result<bool, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = match repo.workdir() {
+        Some(workdir) => workdir,
+        None => return Ok(false),
+    };
+    
+    let relative_path = match repo_relative_path(workdir, &file_path) {
+        Some(relative_path) if !relative_path.as_os_str().is_empty() => relative_path,
+        _ => return Ok(false), // Outside of the repository
+    };
+    
+    repo.is_path_ignored(&relative_path).map_err(|e| e.to_string())
+}
+
+// git2 expects paths relative to the repository root; None when `path` is outside of it
+fn repo_relative_path(workdir: &std::path::Path, path: &str) -> Option<std::path::PathBuf> {
+    let path = std::path::Path::new(path);
+    
+    let relative_path = if path.is_absolute() {
+        let canonical_workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.to_path_buf());
+        let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        
+        canonical_path.strip_prefix(&canonical_workdir)
+            .or_else(|_| path.strip_prefix(workdir))
+            .ok()?
+            .to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+    
+    if relative_path.components().any(|c| c == std::path::Component::ParentDir) {
+        return None;
+    }
+    
+    Some(relative_path)
+}
+
+#[command]
+pub fn list_ignored_files(
+    repo_path: String,
+    directory: String,
+    include_untracked: Option<bool>,
+) -> Result<GitIgnoredFiles, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?.to_path_buf();
+    let relative_dir = repo_relative_path(&workdir, &directory)
+        .ok_or_else(|| format!("{} is outside of the repository", directory))?;
+    
+    let include_untracked = include_untracked.unwrap_or(false);
+    
+    // Ignored and untracked directories are reported once instead of walking their contents
+    let mut opts = StatusOptions::new();
+    opts.include_ignored(true);
+    opts.recurse_ignored_dirs(false);
+    opts.include_untracked(include_untracked);
+    opts.recurse_untracked_dirs(false);
+    opts.exclude_submodules(true);
+    if !relative_dir.as_os_str().is_empty() {
+        opts.pathspec(relative_dir.to_string_lossy().to_string());
+    }
+    
+    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
+    
+    let mut ignored = Vec::new();
+    let mut untracked = Vec::new();
+    for entry in statuses.iter() {
+        let Some(path) = entry.path() else {
+            continue;
+        };
+        let absolute = workdir.join(path.trim_end_matches('/')).to_string_lossy().to_string();
+        
+        if entry.status().contains(Status::IGNORED) {
+            ignored.push(absolute);
+        } else if entry.status() == Status::WT_NEW {
+            untracked.push(absolute);
+        }
+    }
+    
+    Ok(GitIgnoredFiles { ignored, untracked })
+}
+
+fn read_tracking_branch(repo: &Repository) -> Result<Option<GitTrackingBranch>, String> {
+    let head = repo.head().map_err(|e| e.to_string())?;
+    
+    // Detached HEAD has no upstream
+    if !head.is_branch() {
+        return Ok(None);
+    }
+    
+    let head_name = head.name().ok_or("Invalid branch name")?;
+    let branch_name = head.shorthand().ok_or("Invalid branch name")?;
+    let branch = repo.find_branch(branch_name, BranchType::Local).map_err(|e| e.to_string())?;
+    
+    let upstream = match branch.upstream() {
+        Ok(upstream) => upstream,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+    
+    let remote = repo.branch_upstream_remote(head_name).map_err(|e| e.to_string())?;
+    let remote = remote.as_str().ok_or("Invalid remote name")?.to_string();
+    let remote_ref = upstream.name().map_err(|e| e.to_string())?
+        .ok_or("Invalid upstream name")?
+        .to_string();
+    
+    let local_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+    let upstream_commit = upstream.get().peel_to_commit().map_err(|e| e.to_string())?;
+    let (ahead, behind) = repo.graph_ahead_behind(local_commit.id(), upstream_commit.id())
+        .map_err(|e| e.to_string())?;
+    
+    Ok(Some(GitTrackingBranch {
+        remote,
+        remote_ref,
+        ahead,
+        behind,
+    }))
+}
+
+#[command]
+pub fn get_tracking_branch(repo_path: String) -> Result<Option<GitTrackingBranch>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    read_tracking_branch(&repo)
+}
+
+#[command]
+pub fn get_remote_status(repo_path: String) -> Result<GitRemoteStatus, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    let tracking = read_tracking_branch(&repo)?;
+    
+    // Without an upstream there is nothing to compare against, so only report the default remote
+    let remote_name = tracking.as_ref()
+        .map(|t| t.remote.clone())
+        .unwrap_or_else(|| "origin".to_string());
+    let remote = match repo.find_remote(&remote_name) {
+        Ok(remote) => remote,
+        Err(_) => {
+            return Ok(GitRemoteStatus {
+                remote_name,
+                remote_url: None,
+                ahead: 0,
+                behind: 0,
+                has_remote: false,
+            });
+        }
+    };
+    
+    let remote_url = remote.url().map(|s| s.to_string());
+    let (ahead, behind) = tracking.map(|t| (t.ahead, t.behind)).unwrap_or((0, 0));
+    
+    Ok(GitRemoteStatus {
+        remote_name,
+        remote_url,
+        ahead,
+        behind,
+        has_remote: true,
+    })
+}
+
+#[command]
+pub fn fetch_from_remote(repo_path: String, remote_name: Option<String>) -> Result<String, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
     let remote_name = remote_name.unwrap_or_else(|| "origin".to_string());
     
@@ -454,6 +1237,46 @@ pub fn fetch_from_remote(repo_path: String, remote_name: Option<String>) -> Resu
     Ok(message)
 }
 
+#[command]
+pub fn prune_remote(repo_path: String, remote_name: Option<String>) -> Result<GitPruneResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let remote_name = remote_name.unwrap_or_else(|| "origin".to_string());
+    
+    let mut remote = repo.find_remote(&remote_name).map_err(|e| e.to_string())?;
+    
+    let pruned_refs: std::cell::RefCell<Vec<String>> = std::cell::RefCell::new(Vec::new());
+    
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(|_url, username_from_url, _allowed_types| {
+        git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+    });
+    
+    // Pruned refs are reported as tip updates to the zero id
+    callbacks.update_tips(|refname, _old, new| {
+        if new.is_zero() {
+            pruned_refs.borrow_mut().push(refname.to_string());
+        }
+        true
+    });
+    
+    // Pruning only applies to the refs matched by the remote's fetch refspecs,
+    // which map into refs/remotes/<remote>/, so local branches are never touched
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(callbacks);
+    fetch_options.prune(git2::FetchPrune::On);
+    
+    remote.fetch(&[] as &[&str], Some(&mut fetch_options), None)
+        .map_err(|e| e.to_string())?;
+    drop(fetch_options);
+    
+    let pruned_refs = pruned_refs.into_inner();
+    
+    Ok(GitPruneResult {
+        pruned_count: pruned_refs.len(),
+        pruned_refs,
+    })
+}
+
 #[command]
 pub fn pull_from_remote(repo_path: String, remote_name: Option<String>) -> Result<GitPullResult, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
@@ -472,8 +1295,23 @@ pub fn pull_from_remote(repo_path: String, remote_name: Option<String>) -> Resul
     let remote_commit = remote_ref.peel_to_commit().map_err(|e| e.to_string())?;
     let local_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
     
+    let message = format!("Merge remote-tracking branch '{}/{}'", remote_name, current_branch_name);
+    merge_commit_into_head(&repo, &local_commit, &remote_commit, &message)
+}
+
+fn merge_commit_into_head(
+    repo: &Repository,
+    local_commit: &git2::Commit,
+    other_commit: &git2::Commit,
+    message: &str,
+) -> Result<GitPullResult, String> {
+    // A second merge would overwrite MERGE_HEAD of the one still waiting for its conflicts to be resolved
+    if repo.state() != git2::RepositoryState::Clean {
+        return Err("Another merge or operation is in progress; finish or abort it first".to_string());
+    }
+    
     // Check if we're already up to date
-    if local_commit.id() == remote_commit.id() {
+    if local_commit.id() == other_commit.id() {
         return Ok(GitPullResult {
             success: true,
             message: "Already up to date".to_string(),
@@ -482,26 +1320,66 @@ pub fn pull_from_remote(repo_path: String, remote_name: Option<String>) -> Resul
         });
     }
     
-    // Perform merge
-    let local_tree = local_commit.tree().map_err(|e| e.to_string())?;
-    let remote_tree = remote_commit.tree().map_err(|e| e.to_string())?;
+    let (ahead, behind) = repo.graph_ahead_behind(local_commit.id(), other_commit.id())
+        .map_err(|e| e.to_string())?;
+    
+    // Other commit is already contained in HEAD
+    if behind == 0 {
+        return Ok(GitPullResult {
+            success: true,
+            message: "Already up to date".to_string(),
+            new_commits: 0,
+            conflicts: vec![],
+        });
+    }
     
-    // Find merge base
-    let merge_base = repo.merge_base(local_commit.id(), remote_commit.id())
+    if ahead == 0 {
+        // Fast-forward merge, no merge commit needed
+        let head = repo.head().map_err(|e| e.to_string())?;
+        let refname = head.name().ok_or_else(|| "Invalid HEAD reference name".to_string())?;
+        
+        // Update working directory first, so local changes that would be overwritten abort before HEAD moves
+        let target_tree = other_commit.tree().map_err(|e| e.to_string())?;
+        repo.checkout_tree(target_tree.as_object(), Some(git2::build::CheckoutBuilder::default().safe()))
+            .map_err(|e| {
+                if e.code() == git2::ErrorCode::Conflict {
+                    "Your local changes would be overwritten by the merge; commit or stash them first".to_string()
+                } else {
+                    e.to_string()
+                }
+            })?;
+        
+        repo.reference(refname, other_commit.id(), true, "Fast-forward merge")
+            .map_err(|e| e.to_string())?;
+        
+        return Ok(GitPullResult {
+            success: true,
+            message: format!("Fast-forward: {} new commits", behind),
+            new_commits: behind,
+            conflicts: vec![],
+        });
+    }
+    
+    // Merge into the index and working directory. This also writes MERGE_HEAD and MERGE_MSG,
+    // so a conflicted merge stays in progress for get_conflict_versions, resolve_conflict and abort_merge
+    let annotated = repo.find_annotated_commit(other_commit.id()).map_err(|e| e.to_string())?;
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.allow_conflicts(true).conflict_style_merge(true);
+    repo.merge(&[&annotated], None, Some(&mut checkout_builder))
         .map_err(|e| e.to_string())?;
-    let base_commit = repo.find_commit(merge_base).map_err(|e| e.to_string())?;
-    let base_tree = base_commit.tree().map_err(|e| e.to_string())?;
     
-    // Perform three-way merge
-    let merge_index = repo.merge_trees(&base_tree, &local_tree, &remote_tree, None)
+    // The merge commit made after resolving conflicts uses MERGE_MSG as its message
+    std::fs::write(repo.path().join("MERGE_MSG"), format!("{}\n", message))
         .map_err(|e| e.to_string())?;
     
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    
     // Check for conflicts
-    if merge_index.has_conflicts() {
-        let conflicts: Vec<String> = merge_index.conflicts().map_err(|e| e.to_string())?
+    if index.has_conflicts() {
+        let conflicts: Vec<String> = index.conflicts().map_err(|e| e.to_string())?
             .flatten()
             .filter_map(|conflict| {
-                conflict.our.as_ref().and_then(|entry| {
+                conflict.our.as_ref().or(conflict.their.as_ref()).and_then(|entry| {
                     std::str::from_utf8(&entry.path).ok().map(|s| s.to_string())
                 })
             })
@@ -515,72 +1393,259 @@ pub fn pull_from_remote(repo_path: String, remote_name: Option<String>) -> Resul
         });
     }
     
-    // Write merged index to repository index
-    let mut repo_index = repo.index().map_err(|e| e.to_string())?;
+    // Create merge commit
+    let tree_id = index.write_tree().map_err(|e| e.to_string())?;
+    let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+    
+    let signature = repo.signature().map_err(|e| e.to_string())?;
+    
+    let _merge_commit = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &[local_commit, other_commit],
+    ).map_err(|e| e.to_string())?;
+    
+    repo.cleanup_state().map_err(|e| e.to_string())?;
+    
+    Ok(GitPullResult {
+        success: true,
+        message: format!("Merge completed: {} new commits", behind),
+        new_commits: behind,
+        conflicts: vec![],
+    })
+}
+
+#[command]
+pub fn merge_branch(repo_path: String, branch_name: String) -> Result<GitPullResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    let head = repo.head().map_err(|e| e.to_string())?;
+    let local_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+    
+    let branch = repo.find_branch(&branch_name, BranchType::Local).map_err(|e| e.to_string())?;
+    let branch_commit = branch.get().peel_to_commit().map_err(|e| e.to_string())?;
+    
+    let message = format!("Merge branch '{}'", branch_name);
+    merge_commit_into_head(&repo, &local_commit, &branch_commit, &message)
+}
+
+// Trimmed content of a file in the .git directory, e.g. MERGE_HEAD
+fn read_git_file(git_dir: &std::path::Path, name: &str) -> Option<String> {
+    std::fs::read_to_string(git_dir.join(name))
+        .ok()
+        .map(|content| content.trim().to_string())
+        .filter(|content| !content.is_empty())
+}
+
+#[command]
+pub fn get_repo_operation_state(repo_path: String) -> Result<RepoState, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let git_dir = repo.path();
+    
+    let has_conflicts = repo.index()
+        .map(|index| index.has_conflicts())
+        .unwrap_or(false);
+    
+    let mut state = RepoState {
+        operation: "clean".to_string(),
+        target_commit: None,
+        rebase_branch: None,
+        rebase_step: None,
+        rebase_total: None,
+        has_conflicts,
+    };
+    
+    // Interactive and merge-based rebases keep their state in rebase-merge, `git am` style ones in rebase-apply
+    let rebase = [("rebase-merge", "msgnum", "end"), ("rebase-apply", "next", "last")]
+        .into_iter()
+        .find(|(dir, _, _)| git_dir.join(dir).is_dir());
     
-    // Copy entries from merge_index to repo_index
-    repo_index.clear().map_err(|e| e.to_string())?;
-    for i in 0..merge_index.len() {
-        if let Some(entry) = merge_index.get(i) {
-            repo_index.add(&entry).map_err(|e| e.to_string())?;
+    if let Some((dir, step_file, total_file)) = rebase {
+        let rebase_dir = git_dir.join(dir);
+        
+        state.operation = "rebase".to_string();
+        state.target_commit = read_git_file(&rebase_dir, "onto");
+        state.rebase_branch = read_git_file(&rebase_dir, "head-name")
+            .map(|name| name.strip_prefix("refs/heads/").unwrap_or(&name).to_string());
+        state.rebase_step = read_git_file(&rebase_dir, step_file).and_then(|step| step.parse().ok());
+        state.rebase_total = read_git_file(&rebase_dir, total_file).and_then(|total| total.parse().ok());
+        
+        return Ok(state);
+    }
+    
+    for (file, operation) in [("MERGE_HEAD", "merge"), ("CHERRY_PICK_HEAD", "cherry_pick"), ("REVERT_HEAD", "revert")] {
+        if let Some(content) = read_git_file(git_dir, file) {
+            state.operation = operation.to_string();
+            // An octopus merge lists one commit per line
+            state.target_commit = content.lines().next().map(str::to_string);
+            break;
         }
     }
-    repo_index.write().map_err(|e| e.to_string())?;
+    
+    Ok(state)
+}
+
+#[command]
+pub fn abort_merge(repo_path: String) -> Result<String, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    let head = repo.head().map_err(|e| e.to_string())?;
+    let head_commit = head.peel_to_commit().map_err(|e| e.to_string())?;
+    let head_tree = head_commit.tree().map_err(|e| e.to_string())?;
+    
+    // Restore tracked files to HEAD, leaving untracked files alone
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.force();
+    repo.checkout_tree(head_tree.as_object(), Some(&mut checkout_builder))
+        .map_err(|e| e.to_string())?;
+    
+    // Reset the index to match HEAD, dropping any conflict entries
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    index.read_tree(&head_tree).map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())?;
+    
+    // Remove MERGE_HEAD, MERGE_MSG and friends
+    repo.cleanup_state().map_err(|e| e.to_string())?;
+    
+    Ok("Merge aborted".to_string())
+}
 
-    // If fast-forward possible, just update HEAD
-    let (ahead, behind) = repo.graph_ahead_behind(local_commit.id(), remote_commit.id())
+#[command]
+pub fn revert_commit(repo_path: String, commit_id: String) -> Result<GitRevertResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let commit = repo.revparse_single(&commit_id)
+        .and_then(|object| object.peel_to_commit())
         .map_err(|e| e.to_string())?;
     
-    if ahead == 0 {
-        // Fast-forward merge
-        let refname = format!("refs/heads/{}", current_branch_name);
-        repo.reference(&refname, remote_commit.id(), true, "Fast-forward merge")
-            .map_err(|e| e.to_string())?;
-        
-        // Update working directory
-        repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
-            .map_err(|e| e.to_string())?;
-        
-        Ok(GitPullResult {
-            success: true,
-            message: format!("Fast-forward: {} new commits", behind),
-            new_commits: behind,
-            conflicts: vec![],
-        })
-    } else {
-        // Create merge commit
-        let tree_id = repo_index.write_tree().map_err(|e| e.to_string())?;
-        let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
-        
-        let signature = repo.signature().map_err(|e| e.to_string())?;
-        let message = format!("Merge remote-tracking branch '{}/{}'", remote_name, current_branch_name);
-        
-        let _merge_commit = repo.commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
-            &message,
-            &tree,
-            &[&local_commit, &remote_commit],
-        ).map_err(|e| e.to_string())?;
+    if commit.parent_count() > 1 {
+        return Err("Reverting merge commits is not supported".to_string());
+    }
+    
+    let head_commit = repo.head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|e| e.to_string())?;
+    
+    // Applies the inverse diff to the index and working directory
+    repo.revert(&commit, None).map_err(|e| e.to_string())?;
+    
+    let short_id = commit.as_object().short_id()
+        .ok()
+        .and_then(|id| id.as_str().map(|s| s.to_string()))
+        .unwrap_or_else(|| commit.id().to_string());
+    let summary = commit.summary().unwrap_or("").to_string();
+    
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    
+    // Leave the revert in progress so the conflicts can be resolved and committed
+    if index.has_conflicts() {
+        let conflicts: Vec<String> = index.conflicts().map_err(|e| e.to_string())?
+            .flatten()
+            .filter_map(|conflict| {
+                conflict.our.as_ref().or(conflict.their.as_ref()).and_then(|entry| {
+                    std::str::from_utf8(&entry.path).ok().map(|s| s.to_string())
+                })
+            })
+            .collect();
         
-        // Update working directory
-        repo.checkout_head(Some(git2::build::CheckoutBuilder::default().force()))
-            .map_err(|e| e.to_string())?;
+        return Ok(GitRevertResult {
+            success: false,
+            message: format!("Revert of {} has conflicts in {} files", short_id, conflicts.len()),
+            commit_id: None,
+            conflicts,
+        });
+    }
+    
+    let tree_id = index.write_tree().map_err(|e| e.to_string())?;
+    let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+    let signature = resolve_commit_signature(&repo, "", "")?;
+    
+    let message = format!("Revert \"{}\"\n\nThis reverts commit {}.", summary, short_id);
+    
+    let revert_commit_id = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&head_commit],
+    ).map_err(|e| e.to_string())?;
+    
+    repo.cleanup_state().map_err(|e| e.to_string())?;
+    
+    Ok(GitRevertResult {
+        success: true,
+        message: format!("Reverted {}", short_id),
+        commit_id: Some(revert_commit_id.to_string()),
+        conflicts: vec![],
+    })
+}
+
+fn read_conflict_blob(repo: &Repository, entry: Option<&git2::IndexEntry>) -> Result<Option<String>, String> {
+    match entry {
+        Some(entry) => {
+            let blob = repo.find_blob(entry.id).map_err(|e| e.to_string())?;
+            Ok(Some(String::from_utf8_lossy(blob.content()).to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+#[command]
+pub fn get_conflict_versions(repo_path: String, file_path: String) -> Result<ConflictVersions, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let index = repo.index().map_err(|e| e.to_string())?;
+    
+    // Stage 1 = common ancestor, stage 2 = ours, stage 3 = theirs
+    for conflict in index.conflicts().map_err(|e| e.to_string())? {
+        let conflict = conflict.map_err(|e| e.to_string())?;
+        let matches_path = [&conflict.ancestor, &conflict.our, &conflict.their]
+            .iter()
+            .any(|entry| entry.as_ref().is_some_and(|entry| entry.path == file_path.as_bytes()));
         
-        Ok(GitPullResult {
-            success: true,
-            message: format!("Merge completed: {} new commits", behind),
-            new_commits: behind,
-            conflicts: vec![],
-        })
+        if matches_path {
+            return Ok(ConflictVersions {
+                path: file_path,
+                base: read_conflict_blob(&repo, conflict.ancestor.as_ref())?,
+                ours: read_conflict_blob(&repo, conflict.our.as_ref())?,
+                theirs: read_conflict_blob(&repo, conflict.their.as_ref())?,
+            });
+        }
     }
+    
+    Err(format!("No merge conflict found for {}", file_path))
+}
+
+#[command]
+pub fn resolve_conflict(repo_path: String, file_path: String, resolved_content: String) -> Result<(), String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or_else(|| "Repository has no working directory".to_string())?;
+    let path = std::path::Path::new(&file_path);
+    
+    std::fs::write(workdir.join(path), resolved_content).map_err(|e| e.to_string())?;
+    
+    // Drop the conflict stages and stage the resolved file in their place
+    let mut index = repo.index().map_err(|e| e.to_string())?;
+    index.remove_path(path).map_err(|e| e.to_string())?;
+    index.add_path(path).map_err(|e| e.to_string())?;
+    index.write().map_err(|e| e.to_string())?;
+    
+    Ok(())
 }
 
 #[command]
-pub fn push_to_remote(repo_path: String, remote_name: Option<String>) -> Result<GitPushResult, String> {
+pub fn push_to_remote(
+    repo_path: String,
+    remote_name: Option<String>,
+    force: Option<bool>,
+    set_upstream: Option<bool>,
+) -> Result<GitPushResult, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
     let remote_name = remote_name.unwrap_or_else(|| "origin".to_string());
+    let force = force.unwrap_or(false);
+    let set_upstream = set_upstream.unwrap_or(false);
     
     // Get current branch
     let head = repo.head().map_err(|e| e.to_string())?;
@@ -588,6 +1653,9 @@ pub fn push_to_remote(repo_path: String, remote_name: Option<String>) -> Result<
     
     let mut remote = repo.find_remote(&remote_name).map_err(|e| e.to_string())?;
     
+    // Rejections (e.g. non-fast-forward) are reported per ref rather than as a push error
+    let rejection: std::cell::RefCell<Option<String>> = std::cell::RefCell::new(None);
+    
     // Create callbacks for authentication
     let mut callbacks = git2::RemoteCallbacks::new();
     callbacks.credentials(|_url, username_from_url, _allowed_types| {
@@ -598,6 +1666,7 @@ pub fn push_to_remote(repo_path: String, remote_name: Option<String>) -> Result<
         match status {
             Some(msg) => {
                 println!("Failed to push {}: {}", refname, msg);
+                *rejection.borrow_mut() = Some(format!("Failed to push {}: {}", refname, msg));
                 Ok(())
             },
             None => {
@@ -611,11 +1680,34 @@ pub fn push_to_remote(repo_path: String, remote_name: Option<String>) -> Result<
     let mut push_options = git2::PushOptions::new();
     push_options.remote_callbacks(callbacks);
     
-    // Push the current branch
-    let refspec = format!("refs/heads/{}:refs/heads/{}", current_branch_name, current_branch_name);
+    // Push the current branch, a leading '+' forces the update
+    let refspec = format!(
+        "{}refs/heads/{}:refs/heads/{}",
+        if force { "+" } else { "" },
+        current_branch_name,
+        current_branch_name
+    );
+    
+    let push_result = remote.push(&[&refspec], Some(&mut push_options));
+    drop(push_options);
+    
+    if let Some(message) = rejection.into_inner() {
+        return Ok(GitPushResult {
+            success: false,
+            message,
+            pushed_commits: 0,
+        });
+    }
     
-    match remote.push(&[&refspec], Some(&mut push_options)) {
+    match push_result {
         Ok(_) => {
+            if set_upstream {
+                let mut branch = repo.find_branch(current_branch_name, BranchType::Local)
+                    .map_err(|e| e.to_string())?;
+                branch.set_upstream(Some(&format!("{}/{}", remote_name, current_branch_name)))
+                    .map_err(|e| e.to_string())?;
+            }
+            
             // Calculate how many commits were pushed
             let remote_status = get_remote_status(repo_path)?;
             
@@ -633,6 +1725,67 @@ pub fn push_to_remote(repo_path: String, remote_name: Option<String>) -> Result<
     }
 }
 
+#[command]
+pub fn delete_remote_branch(
+    repo_path: String,
+    remote_name: String,
+    branch_name: String,
+) -> Result<GitPushResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let mut remote = repo.find_remote(&remote_name).map_err(|e| e.to_string())?;
+    
+    let rejection: std::cell::RefCell<Option<String>> = std::cell::RefCell::new(None);
+    
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(|_url, username_from_url, _allowed_types| {
+        git2::Cred::ssh_key_from_agent(username_from_url.unwrap_or("git"))
+    });
+    
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(msg) = status {
+            *rejection.borrow_mut() = Some(format!("Failed to delete {}: {}", refname, msg));
+        }
+        Ok(())
+    });
+    
+    let mut push_options = git2::PushOptions::new();
+    push_options.remote_callbacks(callbacks);
+    
+    // An empty source deletes the destination ref on the remote
+    let refspec = format!(":refs/heads/{}", branch_name);
+    
+    let push_result = remote.push(&[&refspec], Some(&mut push_options));
+    drop(push_options);
+    
+    if let Some(message) = rejection.into_inner() {
+        return Ok(GitPushResult {
+            success: false,
+            message,
+            pushed_commits: 0,
+        });
+    }
+    
+    match push_result {
+        Ok(_) => {
+            // Drop the stale remote-tracking branch like `git push --delete` does
+            if let Ok(mut tracking) = repo.find_reference(&format!("refs/remotes/{}/{}", remote_name, branch_name)) {
+                tracking.delete().map_err(|e| e.to_string())?;
+            }
+    
+            Ok(GitPushResult {
+                success: true,
+                message: format!("Deleted {}/{}", remote_name, branch_name),
+                pushed_commits: 0,
+            })
+        }
+        Err(e) => Ok(GitPushResult {
+            success: false,
+            message: e.to_string(),
+            pushed_commits: 0,
+        })
+    }
+}
+
 #[command]
 pub fn discard_all_changes(repo_path: String) -> Result<String, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
@@ -665,6 +1818,75 @@ pub fn discard_all_changes(repo_path: String) -> Result<String, String> {
     Ok(format!("Discarded changes in {} files", files_count))
 }
 
+// Untracked (and optionally ignored) paths as `git clean -nd` reports them;
+// wholly untracked directories are listed once with a trailing '/'
+fn list_cleanable_paths(repo: &Repository, include_ignored: bool) -> Result<Vec<String>, String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true);
+    opts.recurse_untracked_dirs(false);
+    opts.include_ignored(include_ignored);
+    opts.recurse_ignored_dirs(false);
+    
+    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.to_string())?;
+    
+    let mut paths = Vec::new();
+    for entry in statuses.iter() {
+        let status = entry.status();
+        if status == Status::WT_NEW || (include_ignored && status.contains(Status::IGNORED)) {
+            if let Some(path) = entry.path() {
+                paths.push(path.to_string());
+            }
+        }
+    }
+    
+    Ok(paths)
+}
+
+#[command]
+pub fn git_clean_preview(repo_path: String, include_ignored: Option<bool>) -> Result<Vec<String>, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    list_cleanable_paths(&repo, include_ignored.unwrap_or(false))
+}
+
+#[command]
+pub fn git_clean(repo_path: String, paths: Vec<String>, remove_ignored: bool) -> Result<GitBatchResult, String> {
+    let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    let workdir = repo.workdir().ok_or("Repository has no working directory")?.to_path_buf();
+    
+    // Only paths that are still untracked (or ignored, when allowed) are removed, never tracked files.
+    // Every path is checked before anything is deleted, so a rejected one leaves the tree untouched
+    let cleanable = list_cleanable_paths(&repo, remove_ignored)?;
+    
+    for path in &paths {
+        let normalized = path.trim_end_matches('/');
+        let allowed = cleanable.iter()
+            .any(|candidate| candidate.trim_end_matches('/') == normalized);
+        
+        if !allowed {
+            return Err(format!("'{}' is not an untracked path; nothing was removed", path));
+        }
+    }
+    
+    let mut result = GitBatchResult { succeeded: Vec::new(), failed: Vec::new() };
+    
+    for path in paths {
+        let full_path = workdir.join(path.trim_end_matches('/'));
+        let removed = if full_path.is_dir() {
+            std::fs::remove_dir_all(&full_path)
+        } else {
+            std::fs::remove_file(&full_path)
+        };
+        
+        match removed {
+            Ok(()) => result.succeeded.push(path),
+            Err(e) => result.failed.push(GitBatchFailure { path, error: e.to_string() }),
+        }
+    }
+    
+    Ok(result)
+}
+
 #[command]
 pub fn get_git_user_config(repo_path: String) -> Result<GitUserConfig, String> {
     let repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
@@ -704,4 +1926,202 @@ pub fn get_git_user_config(repo_path: String) -> Result<GitUserConfig, String> {
         email,
         has_config,
     })
-} 
\ No newline at end of file
+} 
+
+#[command]
+pub fn get_stash_diff(repo_path: String, index: usize) -> Result<Vec<FileDiff>, String> {
+    let mut repo = Repository::open(&repo_path).map_err(|e| e.to_string())?;
+    
+    let mut stash_id = None;
+    repo.stash_foreach(|stash_index, _message, oid| {
+        if stash_index == index {
+            stash_id = Some(*oid);
+            return false;
+        }
+        true
+    }).map_err(|e| e.to_string())?;
+    
+    let stash_id = stash_id.ok_or_else(|| format!("Stash {} not found", index))?;
+    let stash = repo.find_commit(stash_id).map_err(|e| e.to_string())?;
+    
+    // A stash commit's first parent is the commit it was created on
+    let target = crate::diff::DiffTarget::CommitToCommit {
+        from: Some(stash.parent_id(0).map_err(|e| e.to_string())?.to_string()),
+        to: stash_id.to_string(),
+    };
+    let mut files = crate::diff::compute_diff(Some(&repo_path), &target, None)
+        .map_err(|e| e.to_string())?;
+    
+    // Stashes made with --include-untracked keep those files in a third parent
+    if let Ok(untracked) = stash.parent(2) {
+        let untracked_tree = untracked.tree().map_err(|e| e.to_string())?;
+        let diff = repo.diff_tree_to_tree(None, Some(&untracked_tree), None)
+            .map_err(|e| e.to_string())?;
+        files.extend(crate::diff::collect_file_diffs(&diff).map_err(|e| e.to_string())?);
+    }
+    
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn init_repo() -> (TempDir, Repository) {
+        let dir = TempDir::new().unwrap();
+        let repo = Repository::init(dir.path()).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+        (dir, repo)
+    }
+
+    fn commit_file(repo: &Repository, path: &str, content: &str, message: &str) -> git2::Oid {
+        std::fs::write(repo.workdir().unwrap().join(path), content).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = repo.signature().unwrap();
+        let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+        let parents: Vec<&git2::Commit> = parent.iter().collect();
+        repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents).unwrap()
+    }
+
+    fn checkout(repo: &Repository, refname: &str) {
+        repo.set_head(refname).unwrap();
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force())).unwrap();
+    }
+
+    /// A repository whose current branch and `side` both changed the same line of a.txt
+    fn diverged_repo() -> (TempDir, Repository, String) {
+        let (dir, repo) = init_repo();
+        let base = commit_file(&repo, "a.txt", "base\n", "base");
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+
+        repo.branch("side", &repo.find_commit(base).unwrap(), false).unwrap();
+        commit_file(&repo, "a.txt", "ours\n", "ours");
+        checkout(&repo, "refs/heads/side");
+        commit_file(&repo, "a.txt", "theirs\n", "theirs");
+        checkout(&repo, &main_ref);
+
+        let repo_path = dir.path().to_string_lossy().to_string();
+        (dir, repo, repo_path)
+    }
+
+    #[test]
+    fn conflicting_merge_is_reported_as_an_operation_in_progress() {
+        let (dir, repo, repo_path) = diverged_repo();
+        let side = repo.find_branch("side", BranchType::Local).unwrap().get().target().unwrap();
+
+        let result = merge_branch(repo_path.clone(), "side".to_string()).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.conflicts, vec!["a.txt"]);
+        assert!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap().contains("<<<<<<<"));
+
+        let state = get_repo_operation_state(repo_path.clone()).unwrap();
+        assert_eq!(state.operation, "merge");
+        assert_eq!(state.target_commit, Some(side.to_string()));
+        assert!(state.has_conflicts);
+
+        let versions = get_conflict_versions(repo_path, "a.txt".to_string()).unwrap();
+        assert_eq!(versions.base.as_deref(), Some("base\n"));
+        assert_eq!(versions.ours.as_deref(), Some("ours\n"));
+        assert_eq!(versions.theirs.as_deref(), Some("theirs\n"));
+    }
+
+    #[test]
+    fn resolved_merge_is_committed_with_both_parents() {
+        let (dir, repo, repo_path) = diverged_repo();
+        let head = repo.head().unwrap().target().unwrap();
+        let side = repo.find_branch("side", BranchType::Local).unwrap().get().target().unwrap();
+        merge_branch(repo_path.clone(), "side".to_string()).unwrap();
+
+        assert!(commit_changes(repo_path.clone(), "early".to_string(), String::new(), String::new(), None).is_err());
+        assert!(merge_branch(repo_path.clone(), "side".to_string()).is_err());
+
+        resolve_conflict(repo_path.clone(), "a.txt".to_string(), "resolved\n".to_string()).unwrap();
+        let state = get_repo_operation_state(repo_path.clone()).unwrap();
+        assert_eq!(state.operation, "merge");
+        assert!(!state.has_conflicts);
+
+        let message = std::fs::read_to_string(dir.path().join(".git/MERGE_MSG")).unwrap();
+        assert_eq!(message, "Merge branch 'side'\n");
+        commit_changes(repo_path.clone(), message, String::new(), String::new(), None).unwrap();
+
+        let merge_commit = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(merge_commit.parent_ids().collect::<Vec<_>>(), vec![head, side]);
+        assert_eq!(get_repo_operation_state(repo_path).unwrap().operation, "clean");
+    }
+
+    #[test]
+    fn resolved_revert_allows_a_later_merge() {
+        let (_dir, repo, repo_path) = diverged_repo();
+        let ours = repo.head().unwrap().target().unwrap();
+        commit_file(&repo, "a.txt", "ours again\n", "ours again");
+
+        let result = revert_commit(repo_path.clone(), ours.to_string()).unwrap();
+        assert!(!result.success);
+        assert_eq!(result.conflicts, vec!["a.txt"]);
+        assert_eq!(repo.state(), git2::RepositoryState::Revert);
+
+        resolve_conflict(repo_path.clone(), "a.txt".to_string(), "reverted\n".to_string()).unwrap();
+        commit_changes(repo_path.clone(), "Revert \"ours\"".to_string(), String::new(), String::new(), None).unwrap();
+        assert_eq!(get_repo_operation_state(repo_path.clone()).unwrap().operation, "clean");
+
+        let merge = merge_branch(repo_path.clone(), "side".to_string()).unwrap();
+        assert_eq!(merge.conflicts, vec!["a.txt"]);
+        assert_eq!(get_repo_operation_state(repo_path).unwrap().operation, "merge");
+    }
+
+    #[test]
+    fn fast_forward_keeps_local_changes_it_would_overwrite() {
+        let (dir, repo) = init_repo();
+        let base = commit_file(&repo, "a.txt", "base\n", "base");
+        let main_ref = repo.head().unwrap().name().unwrap().to_string();
+        repo.branch("ahead", &repo.find_commit(base).unwrap(), false).unwrap();
+        checkout(&repo, "refs/heads/ahead");
+        commit_file(&repo, "a.txt", "ahead\n", "ahead");
+        checkout(&repo, &main_ref);
+        let repo_path = dir.path().to_string_lossy().to_string();
+
+        std::fs::write(dir.path().join("a.txt"), "local edit\n").unwrap();
+        assert!(merge_branch(repo_path.clone(), "ahead".to_string()).is_err());
+        assert_eq!(repo.head().unwrap().target(), Some(base));
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "local edit\n");
+
+        std::fs::write(dir.path().join("a.txt"), "base\n").unwrap();
+        let result = merge_branch(repo_path, "ahead".to_string()).unwrap();
+        assert!(result.success);
+        assert_eq!(result.new_commits, 1);
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "ahead\n");
+    }
+
+    #[test]
+    fn file_at_commit_accepts_absolute_paths() {
+        let (dir, repo) = init_repo();
+        let first = commit_file(&repo, "a.txt", "first\n", "first");
+        commit_file(&repo, "a.txt", "second\n", "second");
+        let repo_path = dir.path().to_string_lossy().to_string();
+        let absolute = dir.path().join("a.txt").to_string_lossy().to_string();
+
+        assert_eq!(get_file_at_commit(repo_path.clone(), first.to_string(), absolute).unwrap(), "first\n");
+        assert_eq!(get_file_at_commit(repo_path.clone(), "HEAD".to_string(), "a.txt".to_string()).unwrap(), "second\n");
+        assert!(get_file_at_commit(repo_path, "HEAD".to_string(), "/elsewhere/a.txt".to_string()).is_err());
+    }
+
+    #[test]
+    fn aborted_merge_returns_to_a_clean_state() {
+        let (dir, _repo, repo_path) = diverged_repo();
+        merge_branch(repo_path.clone(), "side".to_string()).unwrap();
+
+        abort_merge(repo_path.clone()).unwrap();
+
+        let state = get_repo_operation_state(repo_path).unwrap();
+        assert_eq!(state.operation, "clean");
+        assert!(!state.has_conflicts);
+        assert_eq!(std::fs::read_to_string(dir.path().join("a.txt")).unwrap(), "ours\n");
+    }
+}
@@ -3,13 +3,19 @@ pub mod process_tracker;
 pub mod fs;
 pub mod lsp;
 pub mod git;
+pub mod diff;
+pub mod archive;
+pub mod bookmarks;
+pub mod recent_projects;
+pub mod media;
+pub mod util;
+
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let terminal_state = terminal::init_terminal_state();
 
-    lsp::logger::safe_init("./horizon_custom_lsp.log", lsp::logger::LogLevel::Info);
-
     tauri::Builder::default()
     
         .plugin(tauri_plugin_opener::init())
@@ -20,6 +26,12 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .manage(terminal_state)
+        .setup(|app| {
+            let log_file = app.path().app_data_dir()?.join("logs").join("lsp.log");
+            lsp::logger::safe_init(&log_file.to_string_lossy(), lsp::logger::LogLevel::Info);
+            lsp::set_app_handle(app.handle().clone());
+            Ok(())
+        })
         .on_window_event(|_window, event| {
             if let tauri::WindowEvent::CloseRequested { .. } = event {
                 lsp::cleanup_on_exit();
@@ -35,46 +47,135 @@ pub fn run() {
             terminal::load_command_history,
             terminal::send_terminal_signal,
             terminal::has_child_process,
+            terminal::kill_terminal_children,
+            terminal::get_terminal_cwd,
+            terminal::list_terminal_sessions,
+            terminal::get_terminal_plain_output,
+            terminal::get_terminal_scrollback,
             terminal::detect_terminal_urls,
+            bookmarks::save_bookmarks,
+            bookmarks::load_bookmarks,
+            recent_projects::add_recent_project,
+            recent_projects::get_recent_projects,
+            recent_projects::remove_recent_project,
             fs::create_directory,
             fs::create_file,
             fs::read_file,
+            fs::tail_file,
+            fs::read_file_range,
+            fs::compute_file_hash,
+            fs::get_file_mtime,
+            archive::create_zip,
+            archive::extract_zip,
             fs::delete_path,
             fs::rename_path,
             fs::path_exists,
             fs::is_directory,
+            fs::canonicalize_path,
+            fs::relative_path,
             fs::copy_file,
+            fs::duplicate_path,
+            fs::open_with_default_app,
+            fs::reveal_in_file_manager,
             fs::list_directory,
             fs::append_to_file,
             fs::write_to_file,
             fs::get_file_info,
+            fs::get_file_info_batch,
+            fs::get_file_metrics,
+            fs::detect_indentation,
+            fs::copy_file_to_clipboard,
+            fs::diff_files,
             fs::scan_directory,
+            fs::scan_directory_flat,
+            fs::scan_directory_streaming,
+            fs::cancel_scan,
+            fs::watch_file,
+            fs::unwatch_file,
             fs::is_image_file,
+            fs::read_file_as_data_url,
             fs::is_audio_file,
+            fs::is_video_file,
+            media::get_media_info,
             fs::search_file_contents,
             fs::search_files_by_name,
             fs::search_file_contents_advanced,
+            fs::search_file_contents_streaming,
+            fs::cancel_search,
+            fs::replace_in_files,
             fs::search_files_by_name_advanced,
+            fs::search_files_by_name_fuzzy,
+            fs::get_disk_usage,
             lsp::start_lsp_server,
             lsp::start_lsp_websocket_server,
             lsp::stop_lsp_websocket_server,
+            lsp::restart_lsp_websocket_server,
             lsp::is_lsp_websocket_running,
             lsp::find_project_root,
+            lsp::detect_project_type,
+            lsp::uri_to_path,
+            lsp::path_to_uri,
+            lsp::resolve_location,
+            lsp::add_workspace_folder,
+            lsp::remove_workspace_folder,
             lsp::format_hover_data_enhanced,
+            lsp::render_hover_html,
+            lsp::get_hover_list_items,
+            lsp::get_rust_runnables,
+            lsp::expand_macro,
+            lsp::goto_parent_module,
+            lsp::find_references,
+            lsp::hover_and_definition,
+            lsp::set_check_on_save,
+            lsp::apply_workspace_edit,
+            lsp::apply_completion_edits,
+            lsp::format_range_basic,
+            lsp::cancel_lsp_request,
+            lsp::get_all_diagnostics,
+            lsp::set_diagnostic_severity_filter,
+            lsp::get_lsp_semantic_token_legend,
+            lsp::get_effective_lsp_config,
+            lsp::set_lsp_log_level,
+            lsp::set_lsp_log_file,
             git::get_git_status,
             git::get_git_branches,
             git::get_git_commits,
+            git::get_commit_graph,
+            git::get_file_at_commit,
+            git::blame_range,
+            git::get_merge_base,
             git::is_git_repository,
+            git::is_path_ignored,
+            git::list_ignored_files,
             git::get_git_changes,
             git::stage_file,
             git::unstage_file,
+            git::stage_files,
+            git::unstage_files,
+            git::stage_hunk,
+            git::unstage_hunk,
             git::stage_all_files,
             git::commit_changes,
+            git::validate_commit_message,
+            git::get_commit_template,
             git::get_remote_status,
+            git::get_tracking_branch,
             git::fetch_from_remote,
+            git::prune_remote,
             git::pull_from_remote,
+            git::merge_branch,
+            git::abort_merge,
+            git::get_repo_operation_state,
+            git::revert_commit,
             git::push_to_remote,
+            git::delete_remote_branch,
+            git::get_conflict_versions,
+            git::resolve_conflict,
             git::discard_all_changes,
+            git::git_clean_preview,
+            git::git_clean,
+            git::get_stash_diff,
+            diff::get_diff,
             git::get_git_user_config
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,86 @@
+use tower_lsp::lsp_types::{FormattingOptions, Position, Range, TextEdit};
+
+/**
+ * Width in columns of a run of leading whitespace, expanding tabs to the next tab stop
+ */
+fn indent_width(indent: &str, tab_size: usize) -> usize {
+    indent.chars().fold(0, |width, ch| match ch {
+        '\t' => (width / tab_size + 1) * tab_size,
+        _ => width + 1,
+    })
+}
+
+fn render_indent(width: usize, options: &FormattingOptions, tab_size: usize) -> String {
+    if options.insert_spaces {
+        " ".repeat(width)
+    } else {
+        format!("{}{}", "\t".repeat(width / tab_size), " ".repeat(width % tab_size))
+    }
+}
+
+fn format_line(line: &str, options: &FormattingOptions, tab_size: usize, trim_trailing: bool) -> String {
+    let body = line.trim_start_matches([' ', '\t']);
+    let indent = &line[..line.len() - body.len()];
+    let body = if trim_trailing { body.trim_end_matches([' ', '\t']) } else { body };
+
+    // Whitespace-only lines lose their indentation entirely when trimming
+    if body.is_empty() && trim_trailing {
+        return String::new();
+    }
+
+    format!("{}{}", render_indent(indent_width(indent, tab_size), options, tab_size), body)
+}
+
+/**
+ * Normalize indentation (tabs or `tab_size` spaces) and trailing whitespace of every
+ * line touched by `range`. Unlike the servers' `formatting` handler this never talks to
+ * rust-analyzer/rustfmt, so it is instant and works while the server is still indexing.
+ * Returns a single edit covering those lines, or no edits when nothing changes
+ */
+pub fn format_range_basic(content: &str, range: Range, options: &FormattingOptions) -> Vec<TextEdit> {
+    let tab_size = (options.tab_size as usize).max(1);
+    let trim_trailing = options.trim_trailing_whitespace.unwrap_or(true);
+
+    let start_line = range.start.line.min(range.end.line) as usize;
+    let mut end_line = range.start.line.max(range.end.line) as usize;
+    // A selection ending at the start of a line doesn't include that line
+    if end_line > start_line && range.end.character == 0 {
+        end_line -= 1;
+    }
+
+    let lines: Vec<&str> = content.split('\n').collect();
+    if start_line >= lines.len() {
+        return Vec::new();
+    }
+    let end_line = end_line.min(lines.len() - 1);
+
+    let mut changed = false;
+    let formatted: Vec<String> = lines[start_line..=end_line].iter()
+        .map(|line| {
+            let (text, line_ending) = match line.strip_suffix('\r') {
+                Some(text) => (text, "\r"),
+                None => (*line, ""),
+            };
+            let new_text = format_line(text, options, tab_size, trim_trailing);
+            changed |= new_text != text;
+            new_text + line_ending
+        })
+        .collect();
+
+    if !changed {
+        return Vec::new();
+    }
+
+    // Replace whole lines without touching their line breaks
+    let last_line = lines[end_line].strip_suffix('\r').unwrap_or(lines[end_line]);
+    let edit_range = Range::new(
+        Position::new(start_line as u32, 0),
+        Position::new(end_line as u32, last_line.encode_utf16().count() as u32),
+    );
+    let mut new_text = formatted.join("\n");
+    if new_text.ends_with('\r') {
+        new_text.pop();
+    }
+
+    vec![TextEdit::new(edit_range, new_text)]
+}
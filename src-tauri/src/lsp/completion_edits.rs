@@ -0,0 +1,158 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use tower_lsp::lsp_types::{CompletionItem, CompletionTextEdit, InsertTextFormat, Position, TextEdit};
+
+use crate::lsp::protocol::LSPUtils;
+use crate::lsp::workspace_edit::apply_text_edits;
+
+struct EditUtils;
+
+impl LSPUtils for EditUtils {}
+
+/**
+ * A completion applied to a document
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletionEditsResult {
+    /// The document with the completion and its additional edits applied
+    pub content: String,
+    /// Every edit with snippets expanded, last in the document first,
+    /// so they can be applied one after another without adjusting ranges
+    pub edits: Vec<TextEdit>,
+    /// Where the cursor goes in the new content: the snippet's final tab stop,
+    /// or the end of the inserted text
+    pub cursor: Position,
+}
+
+/**
+ * Expand an LSP snippet (`$1`, `${1:default}`, `${1|a,b|}`, `$0`) to plain text.
+ * Placeholders keep their default text and choices their first option.
+ * Returns the text and the offset of the final tab stop, or of the first one when there is no `$0`
+ */
+fn expand_snippet(snippet: &str) -> (String, Option<usize>) {
+    let chars: Vec<char> = snippet.chars().collect();
+    let mut text = String::new();
+    let mut tab_stops: Vec<(u32, usize)> = Vec::new();
+    // `${` groups whose closing brace hasn't been reached yet
+    let mut open_groups = 0;
+    let mut i = 0;
+
+    let read_number = |i: &mut usize| -> Option<u32> {
+        let start = *i;
+        while chars.get(*i).is_some_and(|c| c.is_ascii_digit()) {
+            *i += 1;
+        }
+        chars[start..*i].iter().collect::<String>().parse().ok()
+    };
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if matches!(chars.get(i + 1), Some('$' | '}' | '\\')) => {
+                text.push(chars[i + 1]);
+                i += 2;
+            },
+            '$' if chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) => {
+                i += 1;
+                if let Some(number) = read_number(&mut i) {
+                    tab_stops.push((number, text.len()));
+                }
+            },
+            '$' if chars.get(i + 1) == Some(&'{') => {
+                i += 2;
+                match read_number(&mut i) {
+                    Some(number) => tab_stops.push((number, text.len())),
+                    // A variable such as ${TM_FILENAME}; only its default text is kept
+                    None => {
+                        while chars.get(i).is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+                            i += 1;
+                        }
+                    },
+                }
+
+                match chars.get(i) {
+                    Some(':') => {
+                        open_groups += 1;
+                        i += 1;
+                    },
+                    Some('|') => {
+                        let choices_end = (i + 1..chars.len())
+                            .find(|&j| chars[j] == '|' && chars.get(j + 1) == Some(&'}'))
+                            .unwrap_or(chars.len());
+                        text.extend(chars[i + 1..choices_end].iter().take_while(|c| **c != ','));
+                        i = choices_end + 2;
+                    },
+                    _ => i += 1,
+                }
+            },
+            '}' if open_groups > 0 => {
+                open_groups -= 1;
+                i += 1;
+            },
+            c => {
+                text.push(c);
+                i += 1;
+            },
+        }
+    }
+
+    let cursor = tab_stops.iter()
+        .find(|(number, _)| *number == 0)
+        .or_else(|| tab_stops.iter().min_by_key(|(number, _)| *number))
+        .map(|(_, offset)| *offset);
+
+    (text, cursor)
+}
+
+/**
+ * Apply a completion item's main edit together with its `additionalTextEdits`
+ * (e.g. the `use` statement of an auto-import) to `content`.
+ *
+ * Additional edits usually come before the main edit in the document, so the
+ * cursor position accounts for the text they insert above it
+ */
+pub fn apply_completion_edits(content: &str, item: &CompletionItem) -> Result<CompletionEditsResult> {
+    let main_edit = match &item.text_edit {
+        Some(CompletionTextEdit::Edit(edit)) => edit.clone(),
+        // Accepting replaces up to the cursor only, like a plain insert
+        Some(CompletionTextEdit::InsertAndReplace(edit)) => TextEdit::new(edit.insert, edit.new_text.clone()),
+        None => return Err(anyhow!("Completion item '{}' has no text edit", item.label)),
+    };
+
+    let (new_text, cursor_in_text) = if item.insert_text_format == Some(InsertTextFormat::SNIPPET) {
+        expand_snippet(&main_edit.new_text)
+    } else {
+        (main_edit.new_text.clone(), None)
+    };
+    let cursor_in_text = cursor_in_text.unwrap_or(new_text.len());
+    let main_edit = TextEdit::new(main_edit.range, new_text);
+
+    let to_offset = |position: Position| {
+        EditUtils::position_to_offset(content, lsp_types::Position::new(position.line, position.character))
+            .unwrap_or(content.len())
+    };
+
+    let mut edits = item.additional_text_edits.clone().unwrap_or_default();
+    edits.push(main_edit.clone());
+
+    let new_content = apply_text_edits(content, &edits)?;
+
+    // Text inserted or removed above the main edit shifts where the cursor ends up
+    let main_start = to_offset(main_edit.range.start);
+    let shift: isize = item.additional_text_edits.iter()
+        .flatten()
+        .filter(|edit| to_offset(edit.range.end) <= main_start)
+        .map(|edit| edit.new_text.len() as isize - (to_offset(edit.range.end) - to_offset(edit.range.start)) as isize)
+        .sum();
+    let cursor_offset = (main_start as isize + shift) as usize + cursor_in_text;
+    let cursor = EditUtils::offset_to_position(&new_content, cursor_offset);
+
+    // Inserts at the same position keep their order when applied back to front
+    let mut ordered: Vec<(usize, TextEdit)> = edits.into_iter().enumerate().collect();
+    ordered.sort_by_key(|(index, edit)| std::cmp::Reverse((to_offset(edit.range.start), to_offset(edit.range.end), *index)));
+
+    Ok(CompletionEditsResult {
+        content: new_content,
+        edits: ordered.into_iter().map(|(_, edit)| edit).collect(),
+        cursor: Position::new(cursor.line, cursor.character),
+    })
+}
@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use anyhow::Result;
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
@@ -30,13 +31,65 @@ impl Default for ServerCapabilities {
     }
 }
 
-#[derive(Debug, Clone)]
+/**
+ * Project settings for one language's server, read from `.horizon/lsp.json`:
+ * `{ "rust": { "executable_path": ..., "additional_args": [...], "env_vars": {...}, "initialization_options": {...} } }`
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectLspSettings {
+    pub executable_path: Option<PathBuf>,
+    pub additional_args: Option<Vec<String>>,
+    #[serde(default)]
+    pub env_vars: HashMap<String, String>,
+    pub initialization_options: Option<serde_json::Value>,
+}
+
+/**
+ * Project-relative location of the LSP settings file
+ */
+pub const PROJECT_SETTINGS_FILE: &str = ".horizon/lsp.json";
+
+/**
+ * Read the settings for `language` from the project's `.horizon/lsp.json`, if any
+ */
+pub fn load_project_settings(root_path: &Path, language: &str) -> Result<Option<ProjectLspSettings>> {
+    let settings_path = root_path.join(PROJECT_SETTINGS_FILE);
+    if !settings_path.exists() {
+        return Ok(None);
+    }
+    
+    let content = std::fs::read_to_string(&settings_path)?;
+    let mut settings: HashMap<String, ProjectLspSettings> = serde_json::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Invalid {}: {}", settings_path.display(), e))?;
+    
+    Ok(settings.remove(&language.to_lowercase()))
+}
+
+/**
+ * Recursively merge `overlay` into `base`; objects are merged key by key, anything else is replaced
+ */
+pub(crate) fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                merge_json(base.entry(key).or_insert(serde_json::Value::Null), value);
+            }
+        },
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ServerConfig {
     pub root_path: PathBuf,
     pub capabilities: ServerCapabilities,
     pub executable_path: Option<PathBuf>,
     pub additional_args: Vec<String>,
     pub env_vars: HashMap<String, String>,
+    pub request_timeout: Duration,
+    pub did_change_debounce: Duration,
+    pub file_watch_debounce: Duration,
+    pub initialization_options: Option<serde_json::Value>,
 }
 
 impl ServerConfig {
@@ -47,6 +100,10 @@ impl ServerConfig {
             executable_path: None,
             additional_args: Vec::new(),
             env_vars: HashMap::new(),
+            request_timeout: Duration::from_secs(15),
+            did_change_debounce: Duration::from_millis(150),
+            file_watch_debounce: Duration::from_millis(300),
+            initialization_options: None,
         })
     }
     
@@ -65,8 +122,72 @@ impl ServerConfig {
         self
     }
     
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+    
+    pub fn with_did_change_debounce(mut self, window: Duration) -> Self {
+        self.did_change_debounce = window;
+        self
+    }
+    
+    pub fn with_file_watch_debounce(mut self, window: Duration) -> Self {
+        self.file_watch_debounce = window;
+        self
+    }
+    
     pub fn with_capabilities(mut self, capabilities: ServerCapabilities) -> Self {
         self.capabilities = capabilities;
         self
     }
+    
+    pub fn with_initialization_options(mut self, options: serde_json::Value) -> Self {
+        match self.initialization_options.as_mut() {
+            Some(current) => merge_json(current, options),
+            None => self.initialization_options = Some(options),
+        }
+        self
+    }
+    
+    /**
+     * Apply the project's `.horizon/lsp.json` settings for `language` over the current values.
+     * Executable and arguments are replaced, env vars and initialization options are merged
+     */
+    pub fn with_project_settings(mut self, language: &str) -> Self {
+        let settings = match load_project_settings(&self.root_path, language) {
+            Ok(Some(settings)) => settings,
+            Ok(None) => return self,
+            Err(e) => {
+                crate::lsp::logger::warn("ServerConfig", &format!("Ignoring project LSP settings: {}", e));
+                return self;
+            }
+        };
+        
+        if let Some(executable_path) = settings.executable_path {
+            self.executable_path = Some(executable_path);
+        }
+        if let Some(additional_args) = settings.additional_args {
+            self.additional_args = additional_args;
+        }
+        self.env_vars.extend(settings.env_vars);
+        if let Some(options) = settings.initialization_options {
+            self = self.with_initialization_options(options);
+        }
+        
+        self
+    }
+    
+    /**
+     * Merge the configured initialization options over those sent by the client
+     */
+    pub fn apply_initialization_options(&self, client_options: Option<serde_json::Value>) -> Option<serde_json::Value> {
+        match (client_options, self.initialization_options.clone()) {
+            (Some(mut client_options), Some(options)) => {
+                merge_json(&mut client_options, options);
+                Some(client_options)
+            },
+            (client_options, options) => options.or(client_options),
+        }
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak, Mutex as StdMutex};
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use tower_lsp::Client;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, PublishDiagnosticsParams, Url};
+
+use crate::lsp::logger;
+
+/**
+ * Diagnostics side of a server instance: the unfiltered diagnostics cache
+ * plus the severity threshold applied when forwarding them to the client
+ */
+pub struct DiagnosticSession {
+    diagnostics: DashMap<String, Vec<Diagnostic>>,
+    min_severity: StdMutex<Option<DiagnosticSeverity>>,
+    client: StdMutex<Option<Client>>,
+}
+
+/**
+ * Diagnostic sessions of every live server instance regardless of language, so
+ * workspace-wide views (e.g. the problems panel) can read diagnostics without a connection handle
+ */
+static DIAGNOSTIC_SESSIONS: Lazy<StdMutex<Vec<Weak<DiagnosticSession>>>> = Lazy::new(|| StdMutex::new(Vec::new()));
+
+fn live_diagnostic_sessions() -> Vec<Arc<DiagnosticSession>> {
+    DIAGNOSTIC_SESSIONS.lock().unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect()
+}
+
+impl DiagnosticSession {
+    /**
+     * Create a session and add it to the shared store; it drops out once the server owning it is gone
+     */
+    pub fn register() -> Arc<Self> {
+        let session = Arc::new(Self {
+            diagnostics: DashMap::new(),
+            min_severity: StdMutex::new(None),
+            client: StdMutex::new(None),
+        });
+
+        let mut sessions = DIAGNOSTIC_SESSIONS.lock().unwrap();
+        sessions.retain(|weak| weak.strong_count() > 0);
+        sessions.push(Arc::downgrade(&session));
+
+        session
+    }
+
+    pub fn set_client(&self, client: Client) {
+        *self.client.lock().unwrap() = Some(client);
+    }
+
+    /**
+     * Cache a `textDocument/publishDiagnostics` payload and forward what passes the session's filter
+     *
+     * # Returns
+     * Number of diagnostics received and number forwarded to the client
+     */
+    pub async fn publish(&self, params: PublishDiagnosticsParams) -> (usize, usize) {
+        let uri = params.uri.to_string();
+        let received = params.diagnostics.len();
+
+        if params.diagnostics.is_empty() {
+            self.diagnostics.remove(&uri);
+        } else {
+            self.diagnostics.insert(uri, params.diagnostics.clone());
+        }
+
+        let min_severity = *self.min_severity.lock().unwrap();
+        let forwarded: Vec<Diagnostic> = params.diagnostics.into_iter()
+            .filter(|d| meets_severity(d, min_severity))
+            .collect();
+        let forwarded_count = forwarded.len();
+
+        let client = self.client.lock().unwrap().clone();
+        if let Some(client) = client {
+            client.publish_diagnostics(params.uri, forwarded, params.version).await;
+        }
+
+        (received, forwarded_count)
+    }
+}
+
+/**
+ * Whether a diagnostic is at least as severe as `min_severity` (diagnostics without a severity count as errors)
+ */
+pub fn meets_severity(diagnostic: &Diagnostic, min_severity: Option<DiagnosticSeverity>) -> bool {
+    min_severity.is_none_or(|min| diagnostic.severity.unwrap_or(DiagnosticSeverity::ERROR) <= min)
+}
+
+/**
+ * Snapshot diagnostics of every running server keyed by URI, keeping only those
+ * at least as severe as `min_severity` (`None` keeps all). With `use_session_filter`,
+ * a missing `min_severity` falls back to the filter set through `set_diagnostic_severity_filter`
+ */
+pub fn collect_diagnostics(min_severity: Option<DiagnosticSeverity>, use_session_filter: bool) -> HashMap<String, Vec<Diagnostic>> {
+    let mut result: HashMap<String, Vec<Diagnostic>> = HashMap::new();
+
+    for session in live_diagnostic_sessions() {
+        let min_severity = match min_severity {
+            None if use_session_filter => *session.min_severity.lock().unwrap(),
+            explicit => explicit,
+        };
+
+        for entry in session.diagnostics.iter() {
+            let diagnostics: Vec<Diagnostic> = entry.value().iter()
+                .filter(|d| meets_severity(d, min_severity))
+                .cloned()
+                .collect();
+
+            if !diagnostics.is_empty() {
+                result.entry(entry.key().clone()).or_default().extend(diagnostics);
+            }
+        }
+    }
+
+    result
+}
+
+/**
+ * Set the minimum severity forwarded to the client by every running server (`None` shows all)
+ *
+ * Diagnostics are cached unfiltered, so documents are republished
+ * from the cache right away instead of waiting for the server to recheck them
+ */
+pub async fn set_diagnostic_severity_filter(min_severity: Option<DiagnosticSeverity>) {
+    for session in live_diagnostic_sessions() {
+        *session.min_severity.lock().unwrap() = min_severity;
+
+        let client = session.client.lock().unwrap().clone();
+        let Some(client) = client else {
+            continue;
+        };
+
+        let documents: Vec<(String, Vec<Diagnostic>)> = session.diagnostics.iter()
+            .map(|entry| (
+                entry.key().clone(),
+                entry.value().iter().filter(|d| meets_severity(d, min_severity)).cloned().collect(),
+            ))
+            .collect();
+
+        for (uri, diagnostics) in documents {
+            if let Ok(uri) = Url::parse(&uri) {
+                client.publish_diagnostics(uri, diagnostics, None).await;
+            }
+        }
+    }
+
+    logger::info("Diagnostics", &format!("Diagnostic severity filter set to {:?}", min_severity));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    fn diagnostic(severity: DiagnosticSeverity, message: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range::new(Position::new(0, 0), Position::new(0, 1)),
+            severity: Some(severity),
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn publish_params(uri: &str, diagnostics: Vec<Diagnostic>) -> PublishDiagnosticsParams {
+        PublishDiagnosticsParams::new(Url::parse(uri).unwrap(), diagnostics, None)
+    }
+
+    #[tokio::test]
+    async fn diagnostics_are_collected_across_servers() {
+        let rust = DiagnosticSession::register();
+        let toml = DiagnosticSession::register();
+        let rust_uri = "file:///collect-test/src/main.rs";
+        let toml_uri = "file:///collect-test/Cargo.toml";
+
+        rust.publish(publish_params(rust_uri, vec![diagnostic(DiagnosticSeverity::WARNING, "unused")])).await;
+        toml.publish(publish_params(toml_uri, vec![diagnostic(DiagnosticSeverity::ERROR, "bad key")])).await;
+
+        let all = collect_diagnostics(None, false);
+        assert_eq!(all[rust_uri][0].message, "unused");
+        assert_eq!(all[toml_uri][0].message, "bad key");
+
+        let errors = collect_diagnostics(Some(DiagnosticSeverity::ERROR), false);
+        assert!(!errors.contains_key(rust_uri));
+        assert!(errors.contains_key(toml_uri));
+
+        toml.publish(publish_params(toml_uri, Vec::new())).await;
+        drop(rust);
+        let all = collect_diagnostics(None, false);
+        assert!(!all.contains_key(rust_uri));
+        assert!(!all.contains_key(toml_uri));
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::Result;
+use globset::{GlobBuilder, GlobMatcher};
+use notify::event::ModifyKind;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tower_lsp::lsp_types::{FileChangeType, FileSystemWatcher, GlobPattern, OneOf, WatchKind};
+
+use crate::lsp::logger;
+
+/**
+ * Directories whose churn is never forwarded to language servers
+ * (build output and VCS internals change constantly during builds and checkouts)
+ */
+const IGNORED_DIRECTORIES: &[&str] = &["target", ".git", "node_modules"];
+
+/**
+ * Glob registered by a language server through `workspace/didChangeWatchedFiles`
+ */
+pub struct WatchedGlob {
+    matcher: GlobMatcher,
+    kind: WatchKind,
+}
+
+impl WatchedGlob {
+    pub fn from_watchers(watchers: &[FileSystemWatcher]) -> Vec<WatchedGlob> {
+        watchers.iter().filter_map(|watcher| {
+            let pattern = match &watcher.glob_pattern {
+                GlobPattern::String(pattern) => pattern.clone(),
+                GlobPattern::Relative(relative) => {
+                    let base = match &relative.base_uri {
+                        OneOf::Left(folder) => folder.uri.to_file_path().ok(),
+                        OneOf::Right(uri) => uri.to_file_path().ok(),
+                    }?;
+                    format!("{}/{}", base.to_string_lossy().trim_end_matches('/'), relative.pattern)
+                }
+            };
+
+            match GlobBuilder::new(&pattern).literal_separator(true).build() {
+                Ok(glob) => Some(WatchedGlob {
+                    matcher: glob.compile_matcher(),
+                    kind: watcher.kind.unwrap_or(WatchKind::all()),
+                }),
+                Err(e) => {
+                    logger::warn("FileWatcher", &format!("Ignoring invalid watch pattern {}: {}", pattern, e));
+                    None
+                }
+            }
+        }).collect()
+    }
+
+    pub fn matches(&self, path: &Path, change: FileChangeType) -> bool {
+        let kind = match change {
+            FileChangeType::CREATED => WatchKind::Create,
+            FileChangeType::DELETED => WatchKind::Delete,
+            _ => WatchKind::Change,
+        };
+
+        self.kind.contains(kind) && self.matcher.is_match(path)
+    }
+}
+
+/**
+ * Debounced batch of changed paths
+ */
+pub type FileChangeBatch = Vec<(PathBuf, FileChangeType)>;
+
+/**
+ * Recursive watcher over a workspace root that delivers debounced batches of changes
+ */
+pub struct WorkspaceFileWatcher {
+    _watcher: RecommendedWatcher,
+}
+
+impl WorkspaceFileWatcher {
+    pub fn start(root: &Path, debounce: Duration) -> Result<(Self, UnboundedReceiver<FileChangeBatch>)> {
+        let (event_tx, mut event_rx) = mpsc::unbounded_channel::<Event>();
+        let (batch_tx, batch_rx) = mpsc::unbounded_channel();
+
+        let mut watcher = notify::recommended_watcher(move |result: notify::Result<Event>| {
+            match result {
+                Ok(event) => {
+                    let _ = event_tx.send(event);
+                },
+                Err(e) => logger::warn("FileWatcher", &format!("File watcher error: {}", e)),
+            }
+        })?;
+        watcher.watch(root, RecursiveMode::Recursive)?;
+
+        logger::info("FileWatcher", &format!("Watching {} for external changes", root.display()));
+
+        let root = root.to_path_buf();
+        tokio::spawn(async move {
+            while let Some(first) = event_rx.recv().await {
+                let mut changes: HashMap<PathBuf, FileChangeType> = HashMap::new();
+                Self::collect_event(&root, &mut changes, first);
+
+                // Keep collecting until the tree has been quiet for the debounce window (e.g. a branch switch)
+                while let Ok(Some(event)) = tokio::time::timeout(debounce, event_rx.recv()).await {
+                    Self::collect_event(&root, &mut changes, event);
+                }
+
+                if !changes.is_empty() && batch_tx.send(changes.into_iter().collect()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok((Self { _watcher: watcher }, batch_rx))
+    }
+
+    fn collect_event(root: &Path, changes: &mut HashMap<PathBuf, FileChangeType>, event: Event) {
+        for path in event.paths {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            if relative.components().any(|c| IGNORED_DIRECTORIES.iter().any(|dir| c.as_os_str() == *dir)) {
+                continue;
+            }
+
+            let change = match event.kind {
+                EventKind::Create(_) => FileChangeType::CREATED,
+                EventKind::Remove(_) => FileChangeType::DELETED,
+                EventKind::Modify(ModifyKind::Name(_)) => {
+                    // Renames report both ends; whichever side still exists was created
+                    if path.exists() { FileChangeType::CREATED } else { FileChangeType::DELETED }
+                },
+                EventKind::Modify(_) => FileChangeType::CHANGED,
+                _ => continue,
+            };
+
+            match changes.get(&path) {
+                // A file created in this batch is still new to the server however often it was written
+                Some(&FileChangeType::CREATED) if change == FileChangeType::CHANGED => {},
+                _ => {
+                    changes.insert(path, change);
+                }
+            }
+        }
+    }
+}
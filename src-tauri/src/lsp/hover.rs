@@ -1,6 +1,8 @@
 use serde::Serialize;
 use crate::lsp::markdown::{MarkdownSections, extract_markdown_sections};
 use crate::lsp::types::ContentType;
+use tower_lsp::lsp_types::MarkupKind;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
 
 #[derive(Debug, Clone, Serialize)]
 pub struct EnhancedHoverData {
@@ -21,11 +23,15 @@ pub struct DocumentationMetadata {
     pub warning_messages: Vec<String>,
 }
 
-pub fn format_hover_data_enhanced(contents: String) -> Result<EnhancedHoverData, String> {
+pub fn format_hover_data_enhanced(contents: String, kind: MarkupKind) -> Result<EnhancedHoverData, String> {
     if contents.is_empty() {
         return Err("Empty hover contents".to_string());
     }
 
+    if kind == MarkupKind::PlainText {
+        return Ok(format_plaintext_hover(contents));
+    }
+
     let mut metadata = DocumentationMetadata {
         has_code_blocks: false,
         has_tables: false,
@@ -63,6 +69,31 @@ pub fn format_hover_data_enhanced(contents: String) -> Result<EnhancedHoverData,
     })
 }
 
+fn format_plaintext_hover(contents: String) -> EnhancedHoverData {
+    // Plain text is shown verbatim: markdown parsing would mangle literal `*` and backticks
+    let title = contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| line.chars().take(100).collect::<String>())
+        .unwrap_or_else(|| "Documentation".to_string());
+
+    EnhancedHoverData {
+        title,
+        signature: None,
+        documentation: Some(contents.clone()),
+        source_code: None,
+        raw: contents,
+        metadata: DocumentationMetadata {
+            has_code_blocks: false,
+            has_tables: false,
+            has_lists: false,
+            content_type: ContentType::Generic,
+            warning_messages: Vec::new(),
+        },
+    }
+}
+
 fn determine_content_type(sections: &MarkdownSections) -> ContentType {
     if sections.signatures.iter().any(|s| s.contains("fn ") || s.contains("function")) {
         ContentType::Function
@@ -150,4 +181,114 @@ fn extract_source_code(sections: &MarkdownSections, metadata: &mut Documentation
 
 fn extract_signature(sections: &MarkdownSections) -> Option<String> {
     sections.signatures.first().cloned()
-}
\ No newline at end of file
+}
+
+/**
+ * Link schemes that are allowed through to the rendered HTML
+ */
+const SAFE_LINK_SCHEMES: &[&str] = &["http:", "https:", "mailto:", "file:"];
+
+/**
+ * Renders hover markdown to sanitized HTML
+ *
+ * The code block holding the symbol's signature is rendered into a
+ * `hover-signature` section, the rest into `hover-documentation`.
+ * Raw HTML from doc comments is escaped and links with unsafe schemes are dropped.
+ */
+pub fn render_hover_html(contents: &str) -> String {
+    let sections = extract_markdown_sections(contents);
+    // Signature patterns may run past the end of the code block; its first line identifies it
+    let signature = extract_signature(&sections)
+        .and_then(|signature| signature.lines().next().map(|line| line.trim().to_string()))
+        .filter(|signature| !signature.is_empty());
+
+    let mut signature_events: Vec<Event> = Vec::new();
+    let mut documentation_events: Vec<Event> = Vec::new();
+    let mut code_block: Option<Vec<Event>> = None;
+
+    for event in Parser::new_ext(contents, Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH) {
+        let event = sanitize_event(event);
+
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                code_block = Some(vec![event]);
+            },
+            Event::End(TagEnd::CodeBlock) => {
+                let mut block = code_block.take().unwrap_or_default();
+                block.push(event);
+
+                let is_signature = signature_events.is_empty() && signature.as_ref().is_some_and(|signature| {
+                    block.iter().any(|e| matches!(e, Event::Text(text) if text.contains(signature.as_str())))
+                });
+
+                if is_signature {
+                    signature_events = block;
+                } else {
+                    documentation_events.extend(block);
+                }
+            },
+            event => match code_block.as_mut() {
+                Some(block) => block.push(event),
+                None => documentation_events.push(event),
+            },
+        }
+    }
+
+    let mut output = String::new();
+
+    if !signature_events.is_empty() {
+        output.push_str("<div class=\"hover-signature\">");
+        html::push_html(&mut output, signature_events.into_iter());
+        output.push_str("</div>");
+    }
+
+    if !documentation_events.is_empty() {
+        output.push_str("<div class=\"hover-documentation\">");
+        html::push_html(&mut output, documentation_events.into_iter());
+        output.push_str("</div>");
+    }
+
+    output
+}
+
+fn sanitize_event(event: Event) -> Event {
+    match event {
+        // Doc comments are untrusted: show embedded HTML as text instead of rendering it
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        // Indented blocks carry no language; keep the class convention for the highlighter
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Indented)) => {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::Borrowed(""))))
+        },
+        Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang))) => {
+            let lang: String = lang
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .next()
+                .unwrap_or("")
+                .chars()
+                .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_' || *c == '+')
+                .collect();
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(lang.into())))
+        },
+        Event::Start(Tag::Link { link_type, dest_url, title, id }) => {
+            let dest_url = if is_safe_link(&dest_url) { dest_url } else { CowStr::Borrowed("") };
+            Event::Start(Tag::Link { link_type, dest_url, title, id })
+        },
+        Event::Start(Tag::Image { link_type, dest_url, title, id }) => {
+            let dest_url = if is_safe_link(&dest_url) { dest_url } else { CowStr::Borrowed("") };
+            Event::Start(Tag::Image { link_type, dest_url, title, id })
+        },
+        event => event,
+    }
+}
+
+fn is_safe_link(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+
+    match url.find(':') {
+        // A colon before any path separator marks a scheme; relative links and anchors have none
+        Some(colon) if !url[..colon].contains(['/', '?', '#']) => {
+            SAFE_LINK_SCHEMES.iter().any(|scheme| url.starts_with(scheme))
+        },
+        _ => true,
+    }
+}
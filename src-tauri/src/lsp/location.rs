@@ -0,0 +1,84 @@
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use tower_lsp::lsp_types::{Location, LocationLink, Range};
+
+use crate::lsp::protocol::file_uri_to_path;
+
+/// Lines of context shown above and below the target line in a peek view
+const PREVIEW_CONTEXT_LINES: u32 = 3;
+
+/**
+ * A `Location` or a `LocationLink`, as returned by definition-like requests
+ */
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum AnyLocation {
+    Link(LocationLink),
+    Location(Location),
+}
+
+/**
+ * A location resolved to a file on disk, ready for the editor to open or peek
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedLocation {
+    pub path: String,
+    /// Where to put the cursor: the start of the link's selection range or the location's range
+    pub line: u32,
+    pub character: u32,
+    /// Range to highlight; for a link this is the whole target (e.g. the full function)
+    pub range: Range,
+    /// For a link, the span in the source document the request was made from
+    pub origin_range: Option<Range>,
+    /// Line number of the first preview line
+    pub preview_start_line: u32,
+    pub preview: Vec<String>,
+}
+
+/**
+ * Read lines `start..=end` of a file, stopping early at the end of the file
+ */
+fn read_lines(path: &Path, start: u32, end: u32) -> Result<Vec<String>> {
+    let reader = BufReader::new(fs::File::open(path)?);
+    let mut lines = Vec::new();
+
+    for line in reader.split(b'\n').skip(start as usize).take((end - start + 1) as usize) {
+        let line = line?;
+        lines.push(String::from_utf8_lossy(&line).trim_end_matches('\r').to_string());
+    }
+
+    Ok(lines)
+}
+
+/**
+ * Convert the URI of a location to a path, check the file exists and read a few
+ * lines of context around the target for a peek view
+ */
+pub fn resolve_location(location: AnyLocation) -> Result<ResolvedLocation> {
+    let (uri, range, selection_range, origin_range) = match location {
+        AnyLocation::Link(link) => (link.target_uri, link.target_range, link.target_selection_range, link.origin_selection_range),
+        AnyLocation::Location(location) => (location.uri, location.range, location.range, None),
+    };
+
+    let path = file_uri_to_path(uri.as_str())?;
+    if !Path::new(&path).is_file() {
+        return Err(anyhow!("File does not exist: {}", path));
+    }
+
+    let target = selection_range.start;
+    let preview_start_line = target.line.saturating_sub(PREVIEW_CONTEXT_LINES);
+    let preview = read_lines(Path::new(&path), preview_start_line, target.line.saturating_add(PREVIEW_CONTEXT_LINES))?;
+
+    Ok(ResolvedLocation {
+        path,
+        line: target.line,
+        character: target.character,
+        range,
+        origin_range,
+        preview_start_line,
+        preview,
+    })
+}
@@ -1,10 +1,12 @@
 use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::Path;
+use std::sync::Mutex;
 use std::sync::atomic::{AtomicU8, Ordering};
 use std::time::SystemTime;
 use chrono::{DateTime, Local};
 use anyhow::Result;
+use once_cell::sync::Lazy;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
@@ -25,43 +27,94 @@ impl LogLevel {
             LogLevel::Trace => "TRACE",
         }
     }
+    
+    pub fn parse(level: &str) -> Option<Self> {
+        match level.trim().to_lowercase().as_str() {
+            "error" => Some(LogLevel::Error),
+            "warn" | "warning" => Some(LogLevel::Warn),
+            "info" => Some(LogLevel::Info),
+            "debug" => Some(LogLevel::Debug),
+            "trace" => Some(LogLevel::Trace),
+            _ => None,
+        }
+    }
+    
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => LogLevel::Error,
+            1 => LogLevel::Warn,
+            2 => LogLevel::Info,
+            3 => LogLevel::Debug,
+            _ => LogLevel::Trace,
+        }
+    }
 }
 
+/// Size at which the log file is rotated
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Number of rotated files kept next to the active one (`.1` is the newest)
+const MAX_LOG_BACKUPS: u32 = 3;
+
 static LOG_LEVEL: AtomicU8 = AtomicU8::new(2);
-static mut LOG_FILE_PATH: Option<String> = None;
+static LOG_FILE_PATH: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
 
 pub fn safe_init(log_file_path: &str, level: LogLevel) {
     LOG_LEVEL.store(level as u8, Ordering::Relaxed);
     
-    unsafe {
-        LOG_FILE_PATH = Some(log_file_path.to_string());
-    }
+    set_log_file(Some(log_file_path));
     
-    if let Some(parent) = Path::new(log_file_path).parent() {
+    info("LSP", &format!("Logger initialized with level: {}", level.as_str()));
+}
+
+pub fn init(log_file_path: &str, level: LogLevel) -> Result<()> {
+    safe_init(log_file_path, level);
+    Ok(())
+}
+
+pub fn set_level(level: LogLevel) {
+    LOG_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn level() -> LogLevel {
+    LogLevel::from_u8(LOG_LEVEL.load(Ordering::Relaxed))
+}
+
+/// Enables (Some) or disables (None) tee-ing log entries to a file
+pub fn set_log_file(log_file_path: Option<&str>) {
+    if let Some(parent) = log_file_path.and_then(|path| Path::new(path).parent()) {
         if !parent.exists() {
             let _ = fs::create_dir_all(parent);
         }
     }
     
-    info("LSP", &format!("Logger initialized with level: {}", level.as_str()));
+    *LOG_FILE_PATH.lock().unwrap() = log_file_path.map(|path| path.to_string());
 }
 
-pub fn init(log_file_path: &str, level: LogLevel) -> Result<()> {
-    safe_init(log_file_path, level);
-    Ok(())
+pub fn log_file() -> Option<String> {
+    LOG_FILE_PATH.lock().unwrap().clone()
+}
+
+fn rotate_log_files(path: &str) {
+    for index in (1..MAX_LOG_BACKUPS).rev() {
+        let _ = fs::rename(format!("{}.{}", path, index), format!("{}.{}", path, index + 1));
+    }
+    let _ = fs::rename(path, format!("{}.1", path));
 }
 
 fn log_to_file(log_entry: &str) {
-    unsafe {
-        let log_file_ptr = &raw const LOG_FILE_PATH;
-        if let Some(path) = &*log_file_ptr {
-            if let Ok(mut file) = OpenOptions::new()
-                .create(true)
-                .append(true)
-                .open(path) {
-                
-                let _ = file.write_all(log_entry.as_bytes());
-            }
+    // Holding the lock across the write keeps rotation and appends from interleaving
+    let log_file_path = LOG_FILE_PATH.lock().unwrap();
+    if let Some(path) = log_file_path.as_ref() {
+        if fs::metadata(path).is_ok_and(|metadata| metadata.len() >= MAX_LOG_FILE_BYTES) {
+            rotate_log_files(path);
+        }
+        
+        if let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path) {
+            
+            let _ = file.write_all(log_entry.as_bytes());
         }
     }
 }
@@ -78,7 +131,10 @@ pub fn log(level: LogLevel, component: &str, message: &str) {
     
     log_to_file(&log_entry);
     
-    eprintln!("{}", log_entry);
+    // Only echo to the console in debug builds; release builds rely on the log file
+    if cfg!(debug_assertions) {
+        eprint!("{}", log_entry);
+    }
 }
 
 pub fn is_available() -> bool {
@@ -87,9 +143,7 @@ pub fn is_available() -> bool {
 
 pub fn reset() {
     LOG_LEVEL.store(LogLevel::Info as u8, Ordering::Relaxed);
-    unsafe {
-        LOG_FILE_PATH = None;
-    }
+    set_log_file(None);
 }
 
 pub fn error(component: &str, message: &str) {
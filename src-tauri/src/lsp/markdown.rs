@@ -1,5 +1,6 @@
 use regex::Regex;
 use once_cell::sync::Lazy;
+use serde::Serialize;
 
 
 pub static CODE_BLOCK_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"```(\w+)?\n?([\s\S]*?)```").unwrap());
@@ -30,10 +31,26 @@ pub struct MarkdownSections {
     pub links: Vec<(String, String)>,
     pub emphasized_text: Vec<String>,
     pub italic_text: Vec<String>,
-    pub list_items: Vec<(u32, String)>,
+    pub list_items: Vec<ListItem>,
     pub tables: Vec<Vec<Vec<String>>>,
 }
 
+/**
+ * A list item with enough structure to rebuild `<ol>`/`<ul>` nesting
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ListItem {
+    /// Nesting depth, 0 for top-level items
+    pub level: u32,
+    /// Whether the marker is a number (`1.`) rather than a bullet (`-`, `*`, `+`)
+    pub ordered: bool,
+    /// The marker as written, e.g. `-` or `3.`
+    pub marker: String,
+    /// The number of an ordered item, which need not start at 1
+    pub number: Option<u32>,
+    pub content: String,
+}
+
 impl MarkdownSections {
     pub fn new() -> Self {
         MarkdownSections::default()
@@ -90,11 +107,32 @@ pub fn extract_markdown_sections(content: &str) -> MarkdownSections {
     }
     
     
-    for (_idx, line) in content.lines().enumerate() {
+    // Indents of the list items enclosing the current line, outermost first
+    let mut list_indents: Vec<usize> = Vec::new();
+    
+    for line in content.lines() {
         if let Some(cap) = LIST_PATTERN.captures(line) {
-            if let Some(content) = cap.get(3) {
-                sections.list_items.push((0, content.as_str().to_string()));
+            let indent = cap.get(1).map_or(0, |m| m.as_str().chars().map(|c| if c == '\t' { 4 } else { 1 }).sum());
+            
+            while list_indents.last().is_some_and(|&top| top > indent) {
+                list_indents.pop();
             }
+            if list_indents.last() != Some(&indent) {
+                list_indents.push(indent);
+            }
+            
+            let marker = cap[2].to_string();
+            let ordered = marker.ends_with('.');
+            sections.list_items.push(ListItem {
+                level: list_indents.len() as u32 - 1,
+                ordered,
+                number: if ordered { marker.trim_end_matches('.').parse().ok() } else { None },
+                marker,
+                content: cap[3].to_string(),
+            });
+        } else if !line.is_empty() && !line.starts_with(char::is_whitespace) {
+            // Unindented text ends the list
+            list_indents.clear();
         }
     }
     
@@ -206,22 +244,18 @@ pub fn extract_markdown_sections(content: &str) -> MarkdownSections {
 
 
 pub fn sanitize_markdown_comprehensive(text: &str) -> String {
-    let mut result = text.to_string();
+    let mut result = String::with_capacity(text.len() * 2);
     
     
-    let special_chars = vec!['*', '_', '#', '>', '+', '-', '.', '!', 
-                             '[', ']', '(', ')', '{', '}', '\\', '|'];
+    let special_chars = ['*', '_', '#', '>', '+', '-', '.', '!', 
+                         '[', ']', '(', ')', '{', '}', '\\', '|'];
     
-    let mut i = 0;
-    while i < result.len() {
-        for &ch in &special_chars {
-            if result[i..].starts_with(ch) && !is_in_code_context(&result, i) {
-                result.replace_range(i..i+1, &format!("\\{}", ch));
-                i += 1; 
-                break;
-            }
+    // Walk char boundaries so multibyte characters are never split
+    for (i, ch) in text.char_indices() {
+        if special_chars.contains(&ch) && !is_in_code_context(text, i) {
+            result.push('\\');
         }
-        i += 1;
+        result.push(ch);
     }
     
     result
@@ -318,22 +352,117 @@ pub fn format_code_safely(code: &str) -> String {
 }
 
 pub fn clean_title(title: &str) -> String {
-    let title = title.trim();
+    // Drop a leading header marker of any depth, even without a following space
+    let title = title.trim().trim_start_matches('#').trim_start();
     
     
     let cleaned = title
-        .replace("# ", "")
-        .replace("## ", "")
-        .replace("### ", "")
-        .replace("#### ", "")
         .replace("**", "")
         .replace("*", "")
         .replace("`", "");
     
     
-    if cleaned.len() > 100 {
-        format!("{}...", &cleaned[..97])
+    if cleaned.chars().count() > 100 {
+        format!("{}...", cleaned.chars().take(97).collect::<String>())
     } else {
         cleaned
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list_summary(content: &str) -> Vec<(u32, bool, String, Option<u32>, String)> {
+        extract_markdown_sections(content).list_items.into_iter()
+            .map(|item| (item.level, item.ordered, item.marker, item.number, item.content))
+            .collect()
+    }
+
+    fn item(level: u32, marker: &str, content: &str) -> (u32, bool, String, Option<u32>, String) {
+        let number = marker.strip_suffix('.').and_then(|number| number.parse().ok());
+        (level, number.is_some(), marker.to_string(), number, content.to_string())
+    }
+
+    #[test]
+    fn list_items_keep_markers_and_nesting() {
+        let content = "\
+- first
+  1. step one
+  2. step two
+     * detail
+- second
+3. third, numbered from 3
+";
+
+        assert_eq!(list_summary(content), vec![
+            item(0, "-", "first"),
+            item(1, "1.", "step one"),
+            item(1, "2.", "step two"),
+            item(2, "*", "detail"),
+            item(0, "-", "second"),
+            item(0, "3.", "third, numbered from 3"),
+        ]);
+    }
+
+    #[test]
+    fn list_items_count_tabs_as_indentation() {
+        let content = "1. outer\n\t- tabbed\n\t\t+ twice tabbed\n\t- tabbed again\n";
+
+        assert_eq!(list_summary(content), vec![
+            item(0, "1.", "outer"),
+            item(1, "-", "tabbed"),
+            item(2, "+", "twice tabbed"),
+            item(1, "-", "tabbed again"),
+        ]);
+    }
+
+    #[test]
+    fn unindented_text_ends_a_list() {
+        let content = "- outer\n  - inner\n\nParagraph\n\n  - indented start of a new list\n    1. nested\n";
+
+        assert_eq!(list_summary(content), vec![
+            item(0, "-", "outer"),
+            item(1, "-", "inner"),
+            item(0, "-", "indented start of a new list"),
+            item(1, "1.", "nested"),
+        ]);
+    }
+
+    #[test]
+    fn sanitize_escapes_specials_next_to_multibyte_characters() {
+        assert_eq!(
+            sanitize_markdown_comprehensive("日本*語* — 😀[link](ü) #ß"),
+            "日本\\*語\\* — 😀\\[link\\]\\(ü\\) \\#ß",
+        );
+    }
+
+    #[test]
+    fn sanitize_leaves_code_with_multibyte_characters_alone() {
+        assert_eq!(
+            sanitize_markdown_comprehensive("é_x `ñ*_😀` ö_y"),
+            "é\\_x `ñ*_😀` ö\\_y",
+        );
+        assert_eq!(
+            sanitize_markdown_comprehensive("```\n中*文\n``` 中*"),
+            "```\n中*文\n``` 中\\*",
+        );
+    }
+
+    #[test]
+    fn clean_title_truncates_long_emoji_titles_on_char_boundaries() {
+        // 4 bytes per emoji: byte 97 falls in the middle of the 25th one
+        let title = "😀".repeat(120);
+        let cleaned = clean_title(&title);
+
+        assert_eq!(cleaned, format!("{}...", "😀".repeat(97)));
+        assert_eq!(clean_title(&"😀".repeat(100)), "😀".repeat(100));
+    }
+
+    #[test]
+    fn clean_title_strips_header_markers() {
+        assert_eq!(clean_title("#####"), "");
+        assert_eq!(clean_title("  ##### "), "");
+        assert_eq!(clean_title("### **Vec**<`T`>"), "Vec<T>");
+        assert_eq!(clean_title("#Title"), "Title");
+    }
+}
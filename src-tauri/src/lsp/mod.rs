@@ -9,6 +9,12 @@ pub mod hover;
 pub mod types;
 pub mod server_management;
 pub mod websocket_manager;
+pub mod file_watcher;
+pub mod workspace_edit;
+pub mod basic_format;
+pub mod location;
+pub mod completion_edits;
+pub mod diagnostics;
 
 pub use server_management::{
     get_supported_languages,
@@ -19,9 +25,36 @@ pub use server_management::{
 
 pub use websocket_manager::cleanup_on_exit;
 
+static APP_HANDLE: once_cell::sync::OnceCell<tauri::AppHandle> = once_cell::sync::OnceCell::new();
+
+/**
+ * Hand the LSP layer the app handle so background tasks can emit events.
+ * Called once from the app's setup hook
+ */
+pub fn set_app_handle(app: tauri::AppHandle) {
+    let _ = APP_HANDLE.set(app);
+}
+
+/**
+ * Emit a frontend event from code that has no AppHandle of its own,
+ * e.g. the threads reading a server process's output
+ */
+pub(crate) fn emit_event<S: serde::Serialize + Clone>(event: &str, payload: S) {
+    use tauri::Emitter;
+    
+    match APP_HANDLE.get() {
+        Some(app) => {
+            if let Err(e) = app.emit(event, payload) {
+                logger::warn("LSP", &format!("Failed to emit {}: {}", event, e));
+            }
+        },
+        None => logger::warn("LSP", &format!("Dropping {} event: app handle not set", event)),
+    }
+}
+
 #[tauri::command]
-pub async fn start_lsp_server(language: String, file_path: String) -> Result<String, String> {
-    server_management::start_lsp_server(language, file_path).await
+pub async fn start_lsp_server(language: String, file_path: String, toolchain: Option<String>) -> Result<String, String> {
+    server_management::start_lsp_server(language, file_path, toolchain).await
 }
 
 #[tauri::command]
@@ -29,6 +62,11 @@ pub async fn find_project_root(file_path: String, language: Option<String>) -> R
     server_management::find_project_root(file_path, language).await
 }
 
+#[tauri::command]
+pub fn detect_project_type(path: String) -> Result<Vec<server_management::ProjectType>, String> {
+    server_management::detect_project_type(&path)
+}
+
 #[tauri::command]
 pub async fn start_lsp_websocket_server(port: u16) -> Result<String, String> {
     websocket_manager::start_lsp_websocket_server(port).await
@@ -45,6 +83,214 @@ pub async fn stop_lsp_websocket_server() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub fn format_hover_data_enhanced(contents: String) -> Result<hover::EnhancedHoverData, String> {
-    hover::format_hover_data_enhanced(contents)
-}
\ No newline at end of file
+pub async fn restart_lsp_websocket_server(port: u16) -> Result<u16, String> {
+    websocket_manager::restart_lsp_websocket_server(port).await
+}
+
+#[tauri::command]
+pub fn cancel_lsp_request(connection_id: u64, language: String, request_id: serde_json::Value) -> bool {
+    server_factory::cancel_request(connection_id, &language, &request_id)
+}
+
+#[tauri::command]
+pub fn get_lsp_semantic_token_legend(language: String) -> Result<tower_lsp::lsp_types::SemanticTokensLegend, String> {
+    server_factory::get_semantic_token_legend(&language).map_err(|e| e.to_string())
+}
+
+fn parse_diagnostic_severity(level: Option<String>) -> Result<Option<tower_lsp::lsp_types::DiagnosticSeverity>, String> {
+    use tower_lsp::lsp_types::DiagnosticSeverity;
+    
+    match level.as_deref().map(str::to_lowercase).as_deref() {
+        None | Some("all") => Ok(None),
+        Some("error") => Ok(Some(DiagnosticSeverity::ERROR)),
+        Some("warning") => Ok(Some(DiagnosticSeverity::WARNING)),
+        Some("information") | Some("info") => Ok(Some(DiagnosticSeverity::INFORMATION)),
+        Some("hint") => Ok(Some(DiagnosticSeverity::HINT)),
+        Some(other) => Err(format!("Unknown diagnostic severity: {}", other)),
+    }
+}
+
+#[tauri::command]
+pub async fn get_all_diagnostics(min_severity: Option<String>, use_session_filter: Option<bool>) -> Result<std::collections::HashMap<String, Vec<tower_lsp::lsp_types::Diagnostic>>, String> {
+    let min_severity = parse_diagnostic_severity(min_severity)?;
+    
+    Ok(diagnostics::collect_diagnostics(min_severity, use_session_filter.unwrap_or(false)))
+}
+
+#[tauri::command]
+pub async fn set_diagnostic_severity_filter(level: Option<String>) -> Result<(), String> {
+    let min_severity = parse_diagnostic_severity(level)?;
+    
+    diagnostics::set_diagnostic_severity_filter(min_severity).await;
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_lsp_log_level(level: String) -> Result<(), String> {
+    let level = logger::LogLevel::parse(&level)
+        .ok_or_else(|| format!("Unknown log level: {}", level))?;
+    logger::set_level(level);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn set_lsp_log_file(enabled: bool, app: tauri::AppHandle) -> Result<Option<String>, String> {
+    use tauri::Manager;
+    
+    if !enabled {
+        logger::set_log_file(None);
+        return Ok(None);
+    }
+    
+    let log_file = app.path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("logs")
+        .join("lsp.log");
+    let log_file = log_file.to_string_lossy().to_string();
+    
+    logger::set_log_file(Some(&log_file));
+    Ok(Some(log_file))
+}
+
+#[tauri::command]
+pub fn format_hover_data_enhanced(contents: String, kind: Option<tower_lsp::lsp_types::MarkupKind>) -> Result<hover::EnhancedHoverData, String> {
+    hover::format_hover_data_enhanced(contents, kind.unwrap_or(tower_lsp::lsp_types::MarkupKind::Markdown))
+}
+
+#[tauri::command]
+pub fn render_hover_html(contents: String) -> String {
+    hover::render_hover_html(&contents)
+}
+
+#[tauri::command]
+pub fn get_hover_list_items(contents: String) -> Vec<markdown::ListItem> {
+    markdown::extract_markdown_sections(&contents).list_items
+}
+
+#[tauri::command]
+pub async fn add_workspace_folder(folder_path: String, language: Option<String>) -> Result<usize, String> {
+    let folder = server_factory::workspace_folder(&folder_path).map_err(|e| e.to_string())?;
+    
+    Ok(server_factory::change_workspace_folders(language.as_deref(), vec![folder], Vec::new()).await)
+}
+
+#[tauri::command]
+pub async fn remove_workspace_folder(folder_path: String, language: Option<String>) -> Result<usize, String> {
+    let folder = server_factory::workspace_folder(&folder_path).map_err(|e| e.to_string())?;
+    
+    Ok(server_factory::change_workspace_folders(language.as_deref(), Vec::new(), vec![folder]).await)
+}
+
+#[tauri::command]
+pub async fn get_rust_runnables(file_path: String, line: Option<u32>, character: Option<u32>) -> Result<Vec<servers::rust::RunnableCommand>, String> {
+    let position = line.map(|line| tower_lsp::lsp_types::Position::new(line, character.unwrap_or(0)));
+    
+    servers::rust::get_runnables(&file_path, position).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn expand_macro(file_path: String, line: u32, character: u32) -> Result<Option<servers::rust::ExpandedMacro>, String> {
+    let position = tower_lsp::lsp_types::Position::new(line, character);
+    
+    servers::rust::expand_macro(&file_path, position).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn goto_parent_module(file_path: String, line: u32, character: u32) -> Result<Option<tower_lsp::lsp_types::GotoDefinitionResponse>, String> {
+    let position = tower_lsp::lsp_types::Position::new(line, character);
+    
+    servers::rust::parent_module(&file_path, position).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn find_references(file_path: String, line: u32, character: u32, include_declaration: bool) -> Result<Vec<servers::rust::ReferenceLocation>, String> {
+    let position = tower_lsp::lsp_types::Position::new(line, character);
+    
+    servers::rust::find_references(&file_path, position, include_declaration).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn hover_and_definition(file_path: String, line: u32, character: u32) -> Result<servers::rust::HoverAndDefinition, String> {
+    let position = tower_lsp::lsp_types::Position::new(line, character);
+    
+    servers::rust::hover_and_definition(&file_path, position).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn set_check_on_save(language: String, command: String) -> Result<usize, String> {
+    if !language.eq_ignore_ascii_case("rust") {
+        return Err(format!("Check on save is not supported for {}", language));
+    }
+    let command = servers::rust::CheckOnSaveCommand::parse(&command).map_err(|e| e.to_string())?;
+    
+    servers::rust::set_check_on_save(&command).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_effective_lsp_config(language: String, file_path: String) -> Result<config::ServerConfig, String> {
+    server_factory::ServerFactory::new()
+        .get_effective_config(&language, &file_path)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn apply_workspace_edit(edit: tower_lsp::lsp_types::WorkspaceEdit, app: tauri::AppHandle) -> workspace_edit::WorkspaceEditResult {
+    use tauri::Emitter;
+    
+    let result = workspace_edit::apply_workspace_edit(edit);
+    
+    // Let the editor reload any open documents that were rewritten on disk
+    for path in &result.changed_files {
+        let _ = app.emit("fs://file-changed", path);
+    }
+    
+    result
+}
+
+#[tauri::command]
+pub async fn apply_completion_edits(file_path: String, completion_item: tower_lsp::lsp_types::CompletionItem, content: Option<String>) -> Result<completion_edits::CompletionEditsResult, String> {
+    let mut item = completion_item;
+    
+    // rust-analyzer only computes the import edits of a completion when it is resolved
+    if item.additional_text_edits.is_none() && item.data.is_some() {
+        match servers::rust::resolve_completion_item(&file_path, item.clone()).await {
+            Ok(resolved) => item = resolved,
+            Err(e) => logger::warn("LSP", &format!("Failed to resolve completion item '{}': {}", item.label, e)),
+        }
+    }
+    
+    // Unsaved editor content takes precedence over the file on disk
+    let content = match content {
+        Some(content) => content,
+        None => std::fs::read_to_string(&file_path).map_err(|e| format!("Failed to read {}: {}", file_path, e))?,
+    };
+    
+    completion_edits::apply_completion_edits(&content, &item).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn format_range_basic(content: String, range: tower_lsp::lsp_types::Range, options: Option<tower_lsp::lsp_types::FormattingOptions>) -> Vec<tower_lsp::lsp_types::TextEdit> {
+    let options = options.unwrap_or(tower_lsp::lsp_types::FormattingOptions {
+        tab_size: 4,
+        insert_spaces: true,
+        ..Default::default()
+    });
+    
+    basic_format::format_range_basic(&content, range, &options)
+}
+
+#[tauri::command]
+pub fn uri_to_path(uri: String) -> Result<String, String> {
+    protocol::file_uri_to_path(&uri).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn path_to_uri(path: String) -> Result<String, String> {
+    protocol::path_to_file_uri(&path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn resolve_location(location: location::AnyLocation) -> Result<location::ResolvedLocation, String> {
+    location::resolve_location(location).map_err(|e| e.to_string())
+}
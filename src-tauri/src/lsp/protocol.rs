@@ -1,4 +1,4 @@
-use lsp_types::{CompletionItem, CompletionItemKind, Documentation, MarkupContent, MarkupKind};
+use lsp_types::{CompletionItem, CompletionItemKind, Documentation, MarkupContent, MarkupKind, Position};
 use tower_lsp::lsp_types::Url;
 use anyhow::Result;
 use std::path::Path;
@@ -9,6 +9,7 @@ use serde_json::{Value, json};
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use crate::lsp::logger;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -46,6 +47,55 @@ pub struct JsonRpcNotification {
     pub params: Option<Value>,
 }
 
+#[derive(Debug)]
+pub struct RequestTimeoutError {
+    pub method: String,
+    pub timeout: Duration,
+}
+
+impl std::fmt::Display for RequestTimeoutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LSP request '{}' timed out after {}ms", self.method, self.timeout.as_millis())
+    }
+}
+
+impl std::error::Error for RequestTimeoutError {}
+
+/**
+ * Convert a `file://` URI to a filesystem path, percent-decoding it and
+ * handling Windows drive letters (`file:///C:/...`)
+ */
+pub fn file_uri_to_path(uri: &str) -> Result<String> {
+    let url = Url::parse(uri).map_err(|e| anyhow::anyhow!("Invalid URI '{}': {}", uri, e))?;
+    if url.scheme() != "file" {
+        return Err(anyhow::anyhow!("Not a file URI: {}", uri));
+    }
+    
+    let path = url.to_file_path()
+        .map_err(|_| anyhow::anyhow!("URI does not point to a local file: {}", uri))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/**
+ * Convert an absolute filesystem path to a percent-encoded `file://` URI
+ */
+pub fn path_to_file_uri(path: &str) -> Result<String> {
+    let url = Url::from_file_path(path)
+        .map_err(|_| anyhow::anyhow!("Path must be absolute: {}", path))?;
+    Ok(url.to_string())
+}
+
+/**
+ * Path of a value that may be either a `file://` URI or already a plain path
+ */
+pub fn path_from_uri_or_path(value: &str) -> String {
+    if value.starts_with("file:") {
+        file_uri_to_path(value).unwrap_or_else(|_| value.trim_start_matches("file://").to_string())
+    } else {
+        value.to_string()
+    }
+}
+
 pub trait LSPUtils {
     fn path_to_uri(path: &str) -> Result<Url> {
         let path = Path::new(path).canonicalize()?;
@@ -73,20 +123,78 @@ pub trait LSPUtils {
             ..Default::default()
         }
     }
+    
+    /**
+     * Convert an LSP position (line + UTF-16 code units) to a byte offset into `content`.
+     * A character past the end of the line is clamped to the line end, as the spec requires.
+     * Returns None when the line does not exist.
+     */
+    fn position_to_offset(content: &str, position: Position) -> Option<usize> {
+        let mut line_start = 0;
+        
+        for _ in 0..position.line {
+            line_start += content[line_start..].find('\n')? + 1;
+        }
+        
+        let line_end = content[line_start..].find('\n')
+            .map(|i| line_start + i)
+            .unwrap_or(content.len());
+        let line = content[line_start..line_end].strip_suffix('\r')
+            .unwrap_or(&content[line_start..line_end]);
+        
+        let mut utf16_units = 0;
+        for (index, ch) in line.char_indices() {
+            if utf16_units >= position.character as usize {
+                return Some(line_start + index);
+            }
+            utf16_units += ch.len_utf16();
+        }
+        
+        Some(line_start + line.len())
+    }
+    
+    /**
+     * Convert a byte offset into `content` to an LSP position (line + UTF-16 code units).
+     * Offsets past the end or inside a multibyte character are clamped back to a char boundary.
+     */
+    fn offset_to_position(content: &str, offset: usize) -> Position {
+        let mut offset = offset.min(content.len());
+        while !content.is_char_boundary(offset) {
+            offset -= 1;
+        }
+        
+        let before = &content[..offset];
+        let line = before.matches('\n').count() as u32;
+        let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let character = before[line_start..].encode_utf16().count() as u32;
+        
+        Position::new(line, character)
+    }
 }
 
+type NotificationSender = Arc<Mutex<Option<tokio::sync::mpsc::UnboundedSender<JsonRpcNotification>>>>;
+
 pub struct LspProcessConnection {
     stdin: Arc<Mutex<ChildStdin>>,
-    next_id: AtomicU64,
+    /// Shared by clones, so requests sent through any of them get distinct ids
+    next_id: Arc<AtomicU64>,
     response_handlers: Arc<Mutex<HashMap<u64, tokio::sync::oneshot::Sender<JsonRpcResponse>>>>,
+    notification_tx: NotificationSender,
+    /// Client settings keyed by section, served to the server's `workspace/configuration` requests
+    settings: Arc<Mutex<Value>>,
+    /// Number of responses read from the server, including ones for cancelled requests
+    responses_received: Arc<AtomicU64>,
 }
 
 impl Clone for LspProcessConnection {
     fn clone(&self) -> Self {
         Self {
             stdin: self.stdin.clone(),
-            next_id: AtomicU64::new(self.next_id.load(Ordering::SeqCst)),
+            next_id: self.next_id.clone(),
             response_handlers: self.response_handlers.clone(),
+            notification_tx: self.notification_tx.clone(),
+            settings: self.settings.clone(),
+            responses_received: self.responses_received.clone(),
         }
     }
 }
@@ -100,22 +208,62 @@ impl LspProcessConnection {
         
         let stdin = Arc::new(Mutex::new(stdin));
         let response_handlers = Arc::new(Mutex::new(HashMap::new()));
-        let next_id = AtomicU64::new(1);
+        let next_id = Arc::new(AtomicU64::new(1));
         
         let connection = Self {
             stdin,
             next_id,
             response_handlers,
+            notification_tx: Arc::new(Mutex::new(None)),
+            settings: Arc::new(Mutex::new(json!({}))),
+            responses_received: Arc::new(AtomicU64::new(0)),
         };
         
         let response_handlers_clone = connection.response_handlers.clone();
+        let notification_tx_clone = connection.notification_tx.clone();
+        let stdin_clone = connection.stdin.clone();
+        let settings_clone = connection.settings.clone();
+        let responses_received_clone = connection.responses_received.clone();
         std::thread::spawn(move || {
-            Self::read_responses(stdout, response_handlers_clone);
+            Self::read_responses(stdout, response_handlers_clone, notification_tx_clone, stdin_clone, settings_clone, responses_received_clone);
         });
         
         Ok(connection)
     }
     
+    /**
+     * Counter of responses read from the server so far. It keeps counting after the
+     * connection is dropped, so watchers can tell whether the server is still answering
+     */
+    pub fn responses_received(&self) -> Arc<AtomicU64> {
+        self.responses_received.clone()
+    }
+    
+    /**
+     * Receive server-initiated notifications and requests (as method + params).
+     * Requests are answered by the connection itself before being forwarded.
+     */
+    pub fn set_notification_sender(&self, tx: tokio::sync::mpsc::UnboundedSender<JsonRpcNotification>) {
+        *self.notification_tx.lock().unwrap() = Some(tx);
+    }
+    
+    /**
+     * Settings of one configuration section (e.g. `rust-analyzer`), or null if never set
+     */
+    pub fn settings(&self, section: &str) -> Value {
+        self.settings.lock().unwrap().get(section).cloned().unwrap_or(Value::Null)
+    }
+    
+    /**
+     * Replace the settings of a configuration section. The server isn't told;
+     * send `workspace/didChangeConfiguration` to make it pull them again
+     */
+    pub fn set_settings(&self, section: &str, settings: Value) {
+        if let Value::Object(sections) = &mut *self.settings.lock().unwrap() {
+            sections.insert(section.to_string(), settings);
+        }
+    }
+    
     pub async fn send_request<T: Serialize>(&self, method: &str, params: Option<T>) -> Result<JsonRpcResponse> {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
         let id_value = json!(id);
@@ -132,6 +280,13 @@ impl LspProcessConnection {
         let (tx, rx) = tokio::sync::oneshot::channel();
         self.response_handlers.lock().unwrap().insert(id, tx);
         
+        // Cancels the request on the server if this future is dropped before the response arrives
+        let mut pending = PendingRequest {
+            connection: self,
+            id,
+            completed: false,
+        };
+        
         let message = format!("Content-Length: {}\r\n\r\n{}", request_json.len(), request_json);
         
         self.stdin.lock().unwrap().write_all(message.as_bytes())?;
@@ -139,15 +294,47 @@ impl LspProcessConnection {
         
         logger::info("LspProcessConnection", &format!("Sent LSP request: {}", request_json));
         
-        match rx.await {
+        let result = rx.await;
+        pending.completed = true;
+        
+        match result {
             Ok(response) => {
                 logger::info("LspProcessConnection", "Received LSP response");
                 Ok(response)
             },
-            Err(_) => Err(anyhow::anyhow!("Failed to receive response from LSP server"))
+            Err(_) => Err(anyhow::anyhow!("Request {} was cancelled or the LSP server closed the connection", id))
+        }
+    }
+    
+    pub async fn send_request_with_timeout<T: Serialize>(&self, method: &str, params: Option<T>, timeout: Duration) -> Result<JsonRpcResponse> {
+        // On timeout the request future is dropped, which cancels it and removes its pending handler
+        match tokio::time::timeout(timeout, self.send_request(method, params)).await {
+            Ok(result) => result,
+            Err(_) => {
+                logger::warn("LspProcessConnection", &format!("LSP request '{}' timed out after {}ms", method, timeout.as_millis()));
+                Err(RequestTimeoutError {
+                    method: method.to_string(),
+                    timeout,
+                }.into())
+            }
         }
     }
     
+    pub fn pending_request_ids(&self) -> Vec<u64> {
+        self.response_handlers.lock().unwrap().keys().copied().collect()
+    }
+    
+    pub fn cancel_request(&self, id: u64) -> Result<bool> {
+        if self.response_handlers.lock().unwrap().remove(&id).is_none() {
+            return Ok(false);
+        }
+        
+        logger::info("LspProcessConnection", &format!("Cancelling LSP request {}", id));
+        self.send_notification("$/cancelRequest", Some(json!({ "id": id })))?;
+        
+        Ok(true)
+    }
+    
     pub fn send_notification<T: Serialize>(&self, method: &str, params: Option<T>) -> Result<()> {
         let notification = JsonRpcNotification {
             jsonrpc: "2.0".to_string(),
@@ -167,7 +354,76 @@ impl LspProcessConnection {
         Ok(())
     }
     
-    fn read_responses(stdout: ChildStdout, response_handlers: Arc<Mutex<HashMap<u64, tokio::sync::oneshot::Sender<JsonRpcResponse>>>>) {
+    fn forward_to_notification_sender(notification_tx: &NotificationSender, json_value: Value) {
+        let notification = JsonRpcNotification {
+            jsonrpc: "2.0".to_string(),
+            method: json_value.get("method").and_then(|m| m.as_str()).unwrap_or("").to_string(),
+            params: json_value.get("params").cloned(),
+        };
+        
+        if let Some(tx) = notification_tx.lock().unwrap().as_ref() {
+            let _ = tx.send(notification);
+        }
+    }
+    
+    fn reply_to_server_request(stdin: &Mutex<ChildStdin>, settings: &Mutex<Value>, request: &Value) {
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        
+        let response = match method {
+            "client/registerCapability" | "client/unregisterCapability" | "window/workDoneProgress/create" => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": null
+            }),
+            "workspace/configuration" => {
+                // One result per requested item; dotted sections address nested settings
+                let settings = settings.lock().unwrap();
+                let items = request.pointer("/params/items").and_then(|items| items.as_array()).cloned().unwrap_or_default();
+                let result: Vec<Value> = items.iter()
+                    .map(|item| {
+                        let section = item.get("section").and_then(|section| section.as_str()).unwrap_or("");
+                        section.split('.')
+                            .filter(|key| !key.is_empty())
+                            .try_fold(&*settings, |value, key| value.get(key))
+                            .cloned()
+                            .unwrap_or(Value::Null)
+                    })
+                    .collect();
+                
+                json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "result": result
+                })
+            },
+            _ => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": -32601,
+                    "message": format!("Unsupported server request: {}", method)
+                }
+            }),
+        };
+        
+        let response_json = response.to_string();
+        let message = format!("Content-Length: {}\r\n\r\n{}", response_json.len(), response_json);
+        
+        let mut stdin = stdin.lock().unwrap();
+        if let Err(e) = stdin.write_all(message.as_bytes()).and_then(|_| stdin.flush()) {
+            logger::error("LspProcessConnection", &format!("Failed to reply to server request {}: {}", method, e));
+        }
+    }
+    
+    fn read_responses(
+        stdout: ChildStdout,
+        response_handlers: Arc<Mutex<HashMap<u64, tokio::sync::oneshot::Sender<JsonRpcResponse>>>>,
+        notification_tx: NotificationSender,
+        stdin: Arc<Mutex<ChildStdin>>,
+        settings: Arc<Mutex<Value>>,
+        responses_received: Arc<AtomicU64>
+    ) {
         let mut reader = BufReader::new(stdout);
         let mut buffer = String::new();
         let mut content_length = 0;
@@ -200,9 +456,16 @@ impl LspProcessConnection {
                                 Ok(content_str) => {
                                     
                                     if let Ok(json_value) = serde_json::from_str::<serde_json::Value>(&content_str) {
-                                        if json_value.get("method").is_some() && json_value.get("id").is_none() {
+                                        if json_value.get("method").is_some() {
+                                            if json_value.get("id").is_some() {
+                                                // Server-to-client request: its id is unrelated to our pending requests
+                                                logger::info("LspProcessConnection", &format!("Received LSP server request: {}", content_str));
+                                                Self::reply_to_server_request(&stdin, &settings, &json_value);
+                                            } else {
+                                                logger::info("LspProcessConnection", &format!("Received LSP notification: {}", content_str));
+                                            }
                                             
-                                            logger::info("LspProcessConnection", &format!("Received LSP notification: {}", content_str));
+                                            Self::forward_to_notification_sender(&notification_tx, json_value);
                                             
                                             reading_headers = true;
                                             content_length = 0;
@@ -213,9 +476,12 @@ impl LspProcessConnection {
                                     
                                     match serde_json::from_str::<JsonRpcResponse>(&content_str) {
                                         Ok(response) => {
+                                            responses_received.fetch_add(1, Ordering::SeqCst);
                                             if let Some(id) = response.id.as_u64() {
                                                 if let Some(handler) = response_handlers.lock().unwrap().remove(&id) {
                                                     let _ = handler.send(response);
+                                                } else {
+                                                    logger::info("LspProcessConnection", &format!("Discarding response for cancelled request {}", id));
                                                 }
                                             }
                                         },
@@ -248,4 +514,96 @@ impl LspProcessConnection {
             }
         }
     }
-} 
\ No newline at end of file
+}
+
+struct PendingRequest<'a> {
+    connection: &'a LspProcessConnection,
+    id: u64,
+    completed: bool,
+}
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        if !self.completed {
+            if let Err(e) = self.connection.cancel_request(self.id) {
+                logger::error("LspProcessConnection", &format!("Failed to cancel request {}: {}", self.id, e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Utils;
+
+    impl LSPUtils for Utils {}
+
+    // "😀" and "𝄞" are outside the BMP: 4 bytes in UTF-8, a surrogate pair (2 units) in UTF-16.
+    // "中" and "日" are 3 bytes in UTF-8 and 1 unit in UTF-16
+    const MIXED: &str = "a😀b中\r\n𝄞日本x\n\nlast";
+
+    fn round_trip(content: &str) {
+        // The offset between \r and \n has no position of its own; it maps to the line end
+        let offsets = content.char_indices()
+            .filter(|&(offset, ch)| !(ch == '\n' && content[..offset].ends_with('\r')))
+            .map(|(offset, _)| offset)
+            .chain([content.len()]);
+        for offset in offsets {
+            let position = Utils::offset_to_position(content, offset);
+            assert_eq!(Utils::position_to_offset(content, position), Some(offset), "offset {} -> {:?}", offset, position);
+        }
+    }
+
+    #[test]
+    fn positions_count_utf16_units() {
+        assert_eq!(Utils::offset_to_position(MIXED, 1), Position::new(0, 1));
+        // After the emoji: 1 + 2 units, 1 + 4 bytes
+        assert_eq!(Utils::offset_to_position(MIXED, 5), Position::new(0, 3));
+        assert_eq!(Utils::position_to_offset(MIXED, Position::new(0, 3)), Some(5));
+        // After "中": 4 units, 9 bytes
+        assert_eq!(Utils::position_to_offset(MIXED, Position::new(0, 5)), Some(9));
+
+        let second_line = MIXED.find('𝄞').unwrap();
+        assert_eq!(Utils::offset_to_position(MIXED, second_line + 4), Position::new(1, 2));
+        assert_eq!(Utils::position_to_offset(MIXED, Position::new(1, 3)), Some(second_line + 7));
+    }
+
+    #[test]
+    fn round_trips_astral_and_cjk_text() {
+        round_trip(MIXED);
+        round_trip("😀😀😀");
+        round_trip("中文字符\n日本語\n");
+        round_trip("");
+    }
+
+    #[test]
+    fn position_inside_a_surrogate_pair_lands_on_a_char_boundary() {
+        let offset = Utils::position_to_offset(MIXED, Position::new(0, 2)).unwrap();
+        assert!(MIXED.is_char_boundary(offset));
+        assert_eq!(offset, 5);
+    }
+
+    #[test]
+    fn offset_inside_a_multibyte_char_is_clamped_back() {
+        // Byte 2 is inside the emoji, which starts at byte 1
+        assert_eq!(Utils::offset_to_position(MIXED, 2), Position::new(0, 1));
+        // Byte 7 is inside "中", which starts at byte 6
+        assert_eq!(Utils::offset_to_position(MIXED, 7), Position::new(0, 4));
+    }
+
+    #[test]
+    fn out_of_range_positions() {
+        // A character past the end of the line is clamped to the line end, before the \r\n
+        assert_eq!(Utils::position_to_offset(MIXED, Position::new(0, 100)), Some(MIXED.find('\r').unwrap()));
+        assert_eq!(Utils::position_to_offset(MIXED, Position::new(2, 5)), Some(MIXED.rfind("\n\n").unwrap() + 1));
+        assert_eq!(Utils::position_to_offset(MIXED, Position::new(3, 100)), Some(MIXED.len()));
+        // A line that doesn't exist
+        assert_eq!(Utils::position_to_offset(MIXED, Position::new(4, 0)), None);
+        assert_eq!(Utils::position_to_offset("", Position::new(1, 0)), None);
+
+        // Offsets past the end clamp to the end of the document
+        assert_eq!(Utils::offset_to_position(MIXED, MIXED.len() + 10), Position::new(3, 4));
+    }
+}
@@ -9,18 +9,33 @@ use tower_lsp::jsonrpc::Result as LspResult;
 use tower_lsp::lsp_types::*;
 use url;
 use dashmap::DashMap;
+use once_cell::sync::Lazy;
 
 use crate::lsp::servers::rust::RustLanguageServer;
+use crate::lsp::servers::toml::TomlLanguageServer;
+use crate::lsp::servers::json::JsonLanguageServer;
+use crate::lsp::servers::yaml::YamlLanguageServer;
+use crate::lsp::servers::generic::GenericLanguageServer;
 use crate::lsp::logger;
+use crate::lsp::config::ServerConfig;
 
 pub enum LanguageServerInstance {
     Rust(RustLanguageServer),
+    Toml(TomlLanguageServer),
+    Json(JsonLanguageServer),
+    Yaml(YamlLanguageServer),
+    /// Fallback for languages without a dedicated server
+    Generic(GenericLanguageServer),
 }
 
 impl LanguageServerInstance {
     pub fn with_client(self, client: Client) -> Self {
         match self {
             LanguageServerInstance::Rust(server) => LanguageServerInstance::Rust(server.with_client(client)),
+            LanguageServerInstance::Toml(server) => LanguageServerInstance::Toml(server.with_client(client)),
+            LanguageServerInstance::Json(server) => LanguageServerInstance::Json(server.with_client(client)),
+            LanguageServerInstance::Yaml(server) => LanguageServerInstance::Yaml(server.with_client(client)),
+            LanguageServerInstance::Generic(server) => LanguageServerInstance::Generic(server.with_client(client)),
         }
     }
 }
@@ -32,6 +47,117 @@ pub trait ManagedLanguageServer: Send + Sync {
     async fn shutdown(&self) -> Result<()>;
     
     fn get_capabilities(&self) -> Value;
+    
+    fn language(&self) -> &str;
+    
+    /**
+     * Capabilities from this server's initialize response, `None` until it has been initialized
+     */
+    fn initialized_capabilities(&self) -> Option<ServerCapabilities>;
+    
+    async fn change_workspace_folders(&self, added: Vec<WorkspaceFolder>, removed: Vec<WorkspaceFolder>);
+}
+
+/**
+ * Servers created by any factory, so Tauri commands can reach them without a connection handle
+ */
+static RUNNING_SERVERS: Lazy<DashMap<String, std::sync::Weak<dyn ManagedLanguageServer>>> = Lazy::new(DashMap::new);
+
+/**
+ * Build a workspace folder entry for a directory, named after its last path component
+ */
+pub fn workspace_folder(path: &str) -> Result<WorkspaceFolder> {
+    let uri = url::Url::from_directory_path(path)
+        .map_err(|_| anyhow!("Cannot create URI from path: {}", path))?;
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+    
+    Ok(WorkspaceFolder { uri, name })
+}
+
+/**
+ * Send a `workspace/didChangeWorkspaceFolders` change to every running server,
+ * or only those of `language` when given. Returns how many servers were notified
+ */
+pub async fn change_workspace_folders(language: Option<&str>, added: Vec<WorkspaceFolder>, removed: Vec<WorkspaceFolder>) -> usize {
+    RUNNING_SERVERS.retain(|_, server| server.strong_count() > 0);
+    
+    let servers: Vec<Arc<dyn ManagedLanguageServer>> = RUNNING_SERVERS.iter()
+        .filter_map(|entry| entry.value().upgrade())
+        .filter(|server| language.is_none_or(|language| server.language().eq_ignore_ascii_case(language)))
+        .collect();
+    
+    for server in &servers {
+        server.change_workspace_folders(added.clone(), removed.clone()).await;
+    }
+    
+    servers.len()
+}
+
+/**
+ * Websocket connection, language and the client's JSON-RPC id of a request.
+ * Clients number their requests independently, so the id alone is not unique
+ */
+type RequestKey = (u64, String, String);
+
+/**
+ * Requests currently being processed
+ */
+static IN_FLIGHT_REQUESTS: Lazy<DashMap<RequestKey, tokio::sync::oneshot::Sender<()>>> = Lazy::new(DashMap::new);
+
+/**
+ * Cancel an in-flight request sent over websocket connection `connection_id` to the server for `language`
+ */
+pub fn cancel_request(connection_id: u64, language: &str, request_id: &Value) -> bool {
+    match IN_FLIGHT_REQUESTS.remove(&(connection_id, language.to_lowercase(), request_id.to_string())) {
+        Some((_, cancel_tx)) => {
+            logger::info("ServerFactory", &format!("Cancelling request {}", request_id));
+            let _ = cancel_tx.send(());
+            true
+        },
+        None => false
+    }
+}
+
+/**
+ * Capabilities from the latest initialize response, keyed by language
+ */
+static SERVER_CAPABILITIES: Lazy<DashMap<String, ServerCapabilities>> = Lazy::new(DashMap::new);
+
+/**
+ * Snapshot of the capabilities advertised by every initialized language server
+ */
+pub fn cached_server_capabilities() -> HashMap<String, ServerCapabilities> {
+    SERVER_CAPABILITIES.iter()
+        .map(|entry| (entry.key().clone(), entry.value().clone()))
+        .collect()
+}
+
+/**
+ * Get the semantic token legend captured when a running server for the language was initialized
+ */
+pub fn get_semantic_token_legend(language: &str) -> Result<SemanticTokensLegend> {
+    let capabilities = RUNNING_SERVERS.iter()
+        .filter_map(|entry| entry.value().upgrade())
+        .filter(|server| server.language().eq_ignore_ascii_case(language))
+        .find_map(|server| server.initialized_capabilities());
+    
+    match capabilities {
+        Some(capabilities) => semantic_tokens_legend(&capabilities)
+            .ok_or_else(|| anyhow!("Language server for {} does not support semantic tokens", language)),
+        None => Err(anyhow!("No initialized language server for {}", language))
+    }
+}
+
+fn semantic_tokens_legend(capabilities: &ServerCapabilities) -> Option<SemanticTokensLegend> {
+    match capabilities.semantic_tokens_provider.as_ref()? {
+        SemanticTokensServerCapabilities::SemanticTokensOptions(options) => Some(options.legend.clone()),
+        SemanticTokensServerCapabilities::SemanticTokensRegistrationOptions(options) => {
+            Some(options.semantic_tokens_options.legend.clone())
+        }
+    }
 }
 
 /**
@@ -102,22 +228,33 @@ impl ServerFactory {
         let server: Arc<dyn ManagedLanguageServer> = match normalized_language.as_str() {
             "rust" => {
                 logger::info("ServerFactory", &format!("Creating RUST adapter for language: '{}'", normalized_language));
-                let rust_server = RustLspAdapter::new(normalized_language.to_string(), root_path)?;
+                let rust_server = LspAdapter::new(normalized_language.to_string(), root_path.clone(), RustLanguageServer::new(root_path)?);
                 Arc::new(rust_server)
             },
-            "typescript" | "javascript" => {
-                logger::info("ServerFactory", &format!("Creating TS/JS adapter for language: '{}'", normalized_language));
-                return Err(anyhow!("Adapter for language '{}' is not yet implemented", normalized_language));
+            "toml" => {
+                logger::info("ServerFactory", &format!("Creating TOML adapter for language: '{}'", normalized_language));
+                let toml_server = LspAdapter::new(normalized_language.to_string(), root_path.clone(), TomlLanguageServer::new(root_path)?);
+                Arc::new(toml_server)
             },
-            "python" => {
-                logger::info("ServerFactory", &format!("Creating Python adapter for language: '{}'", normalized_language));
-                return Err(anyhow!("Adapter for language '{}' is not yet implemented", normalized_language));
+            "json" => {
+                logger::info("ServerFactory", &format!("Creating JSON adapter for language: '{}'", normalized_language));
+                let json_server = LspAdapter::new(normalized_language.to_string(), root_path.clone(), JsonLanguageServer::new(root_path)?);
+                Arc::new(json_server)
             },
+            "yaml" => {
+                logger::info("ServerFactory", &format!("Creating YAML adapter for language: '{}'", normalized_language));
+                let yaml_server = LspAdapter::new(normalized_language.to_string(), root_path.clone(), YamlLanguageServer::new(root_path)?);
+                Arc::new(yaml_server)
+            },
+            // Includes languages whose adapters are planned (typescript, javascript, python)
             _ => {
-                return Err(anyhow!("Language '{}' is not supported. No LSP server for this language.", normalized_language));
+                logger::info("ServerFactory", &format!("No dedicated server for language '{}', creating generic adapter", normalized_language));
+                let generic_server = LspAdapter::new(normalized_language.to_string(), root_path.clone(), GenericLanguageServer::new(root_path)?);
+                Arc::new(generic_server)
             }
         };
         
+        RUNNING_SERVERS.insert(server_id.clone(), Arc::downgrade(&server));
         self.servers.lock().unwrap().insert(server_id.clone(), server);
         
         Ok(server_id)
@@ -128,6 +265,7 @@ impl ServerFactory {
             let mut servers = self.servers.lock().unwrap();
             servers.remove(&server_id)
         };
+        RUNNING_SERVERS.remove(&server_id);
         
         if let Some(server) = server {
             server.shutdown().await?;
@@ -137,17 +275,55 @@ impl ServerFactory {
         }
     }
     
-    pub async fn forward_request(&self, server_id: &str, request_text: &str) -> Result<String> {
+    pub async fn forward_request(&self, connection_id: u64, server_id: &str, request_text: &str) -> Result<String> {
         let server = {
             let servers = self.servers.lock().unwrap();
             servers.get(server_id).cloned()
         };
         
-        if let Some(server) = server {
-            server.handle_request(request_text).await
-        } else {
-            Err(anyhow!("Server not found: {}", server_id))
+        let server = match server {
+            Some(server) => server,
+            None => return Err(anyhow!("Server not found: {}", server_id)),
+        };
+        
+        let json_rpc = serde_json::from_str::<Value>(request_text).unwrap_or(Value::Null);
+        let method = json_rpc.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let id = json_rpc.get("id").cloned().unwrap_or(Value::Null);
+        
+        if method == "$/cancelRequest" {
+            if let Some(cancel_id) = json_rpc.get("params").and_then(|p| p.get("id")) {
+                cancel_request(connection_id, server.language(), cancel_id);
+            }
+            return Ok("".to_string());
+        }
+        
+        if id.is_null() {
+            return server.handle_request(request_text).await;
         }
+        
+        let request_key = (connection_id, server.language().to_string(), id.to_string());
+        let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel();
+        IN_FLIGHT_REQUESTS.insert(request_key.clone(), cancel_tx);
+        
+        // Dropping the server future cancels the underlying request on the language server
+        let result = tokio::select! {
+            result = server.handle_request(request_text) => result,
+            _ = cancel_rx => {
+                logger::info("ServerFactory", &format!("Request {} ({}) was cancelled", id, method));
+                Ok(json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": {
+                        "code": -32800,
+                        "message": "Request cancelled"
+                    }
+                }).to_string())
+            }
+        };
+        
+        IN_FLIGHT_REQUESTS.remove(&request_key);
+        
+        result
     }
     
     pub fn get_server_capabilities(&self, language: &str) -> Value {
@@ -162,9 +338,9 @@ impl ServerFactory {
             
         match normalized_language.as_str() {
             "rust" => {
-                match RustLspAdapter::new(normalized_language.to_string(), current_dir.clone()) {
-                    Ok(adapter) => {
-                        return adapter.get_capabilities();
+                match RustLanguageServer::new(current_dir.clone()) {
+                    Ok(server) => {
+                        return LspAdapter::new(normalized_language.to_string(), current_dir, server).get_capabilities();
                     },
                     Err(e) => {
                         let error_msg = format!("Cannot create Rust LSP adapter: {}", e);
@@ -177,23 +353,69 @@ impl ServerFactory {
                     }
                 }
             },
-            "typescript" | "javascript" | "python" => {
-                let error_msg = format!("Adapter for language '{}' is not yet implemented", normalized_language);
-                logger::info("ServerFactory", &error_msg);
-                return json!({
-                    "error": error_msg,
-                    "_type": "capabilities_error",
-                    "source": "server_factory_planned_language"
-                });
+            "toml" => {
+                match TomlLanguageServer::new(current_dir.clone()) {
+                    Ok(server) => {
+                        return LspAdapter::new(normalized_language.to_string(), current_dir, server).get_capabilities();
+                    },
+                    Err(e) => {
+                        let error_msg = format!("Cannot create TOML LSP adapter: {}", e);
+                        logger::error("ServerFactory", &error_msg);
+                        return json!({
+                            "error": error_msg,
+                            "_type": "capabilities_error",
+                            "source": "toml_lsp_adapter_creation"
+                        });
+                    }
+                }
+            },
+            "json" => {
+                match JsonLanguageServer::new(current_dir.clone()) {
+                    Ok(server) => {
+                        LspAdapter::new(normalized_language.to_string(), current_dir, server).get_capabilities()
+                    },
+                    Err(e) => {
+                        let error_msg = format!("Cannot create JSON LSP adapter: {}", e);
+                        logger::error("ServerFactory", &error_msg);
+                        json!({
+                            "error": error_msg,
+                            "_type": "capabilities_error",
+                            "source": "json_lsp_adapter_creation"
+                        })
+                    }
+                }
+            },
+            "yaml" => {
+                match YamlLanguageServer::new(current_dir.clone()) {
+                    Ok(server) => {
+                        LspAdapter::new(normalized_language.to_string(), current_dir, server).get_capabilities()
+                    },
+                    Err(e) => {
+                        let error_msg = format!("Cannot create YAML LSP adapter: {}", e);
+                        logger::error("ServerFactory", &error_msg);
+                        json!({
+                            "error": error_msg,
+                            "_type": "capabilities_error",
+                            "source": "yaml_lsp_adapter_creation"
+                        })
+                    }
+                }
             },
             _ => {
-                let error_msg = format!("Language '{}' is not supported. No LSP server for this language.", normalized_language);
-                logger::info("ServerFactory", &error_msg);
-                return json!({
-                    "error": error_msg,
-                    "_type": "capabilities_error",
-                    "source": "server_factory_unsupported_language"
-                });
+                match GenericLanguageServer::new(current_dir.clone()) {
+                    Ok(server) => {
+                        LspAdapter::new(normalized_language.to_string(), current_dir, server).get_capabilities()
+                    },
+                    Err(e) => {
+                        let error_msg = format!("Cannot create generic LSP adapter: {}", e);
+                        logger::error("ServerFactory", &error_msg);
+                        json!({
+                            "error": error_msg,
+                            "_type": "capabilities_error",
+                            "source": "generic_lsp_adapter_creation"
+                        })
+                    }
+                }
             }
         }
     }
@@ -213,6 +435,9 @@ impl ServerFactory {
                     "py" => "python".to_string(),
                     "js" => "javascript".to_string(),
                     "ts" => "typescript".to_string(),
+                    "toml" => "toml".to_string(),
+                    "json" => "json".to_string(),
+                    "yaml" | "yml" => "yaml".to_string(),
                     _ => normalized_language.clone()
                 }
             } else {
@@ -248,12 +473,42 @@ impl ServerFactory {
                 let server = RustLanguageServer::new(root_path)?;
                 Ok(LanguageServerInstance::Rust(server))
             },
+            "toml" => {
+                let server = TomlLanguageServer::new(root_path)?;
+                Ok(LanguageServerInstance::Toml(server))
+            },
+            "json" => {
+                let server = JsonLanguageServer::new(root_path)?;
+                Ok(LanguageServerInstance::Json(server))
+            },
+            "yaml" => {
+                let server = YamlLanguageServer::new(root_path)?;
+                Ok(LanguageServerInstance::Yaml(server))
+            },
             _ => {
-                Err(anyhow!("Language '{}' is not supported. No LSP server for this language.", final_language))
+                let server = GenericLanguageServer::new(root_path)?;
+                Ok(LanguageServerInstance::Generic(server))
             }
         }
     }
 
+    /**
+     * Configuration a server for `language` would be started with for `file_path`,
+     * including the project's `.horizon/lsp.json` overrides
+     */
+    pub fn get_effective_config(&self, language: &str, file_path: &str) -> Result<ServerConfig> {
+        let normalized_language = language.to_lowercase();
+        let root_path = self.find_project_root(&normalized_language, file_path)?;
+        
+        match normalized_language.as_str() {
+            "rust" => RustLanguageServer::default_config(&root_path),
+            "toml" => TomlLanguageServer::default_config(&root_path),
+            "json" => JsonLanguageServer::default_config(&root_path),
+            "yaml" => YamlLanguageServer::default_config(&root_path),
+            _ => GenericLanguageServer::default_config(&root_path),
+        }
+    }
+    
     pub fn find_project_root(&self, language: &str, file_path: &str) -> Result<String> {
         let path = Path::new(file_path);
         
@@ -280,6 +535,7 @@ impl ServerFactory {
         
         let config_files = match language.to_lowercase().as_str() {
             "rust" => vec!["Cargo.toml"],
+            "toml" | "json" | "yaml" => vec![".git"],
             "javascript" | "typescript" => vec!["package.json", "tsconfig.json"],
             "python" => vec!["pyproject.toml", "setup.py", "requirements.txt"],
             "go" => vec!["go.mod"],
@@ -347,6 +603,7 @@ impl ServerFactory {
         
         let config_files = match language.to_lowercase().as_str() {
             "rust" => vec!["Cargo.toml"],
+            "toml" | "json" | "yaml" => vec![".git"],
             "javascript" | "typescript" => vec!["package.json", "tsconfig.json"],
             "python" => vec!["pyproject.toml", "setup.py", "requirements.txt"],
             "go" => vec!["go.mod"],
@@ -429,25 +686,28 @@ impl ServerFactory {
     }
 }
 
-struct RustLspAdapter {
+struct LspAdapter<S> {
     language: String,
     root_path: String,
-    server: Arc<RustLanguageServer>,
+    server: Arc<S>,
+    workspace_folders: Mutex<Vec<WorkspaceFolder>>,
+    capabilities: Mutex<Option<ServerCapabilities>>,
 }
 
-impl RustLspAdapter {
-    fn new(language: String, root_path: String) -> Result<Self> {
-        let server = RustLanguageServer::new(root_path.clone())?;
-        Ok(Self {
+impl<S: LanguageServer> LspAdapter<S> {
+    fn new(language: String, root_path: String, server: S) -> Self {
+        Self {
             language,
             root_path,
             server: Arc::new(server),
-        })
+            workspace_folders: Mutex::new(Vec::new()),
+            capabilities: Mutex::new(None),
+        }
     }
 }
 
 #[async_trait]
-impl ManagedLanguageServer for RustLspAdapter {
+impl<S: LanguageServer> ManagedLanguageServer for LspAdapter<S> {
     async fn handle_request(&self, request_text: &str) -> Result<String> {
         match serde_json::from_str::<serde_json::Value>(request_text) {
             Ok(json_rpc) => {
@@ -467,8 +727,19 @@ impl ManagedLanguageServer for RustLspAdapter {
                             
                             params_value.root_uri = Some(root_uri.clone());
                             
+                            // A single folder for the project root unless the client sent its own set
+                            let folders = match params_value.workspace_folders.take() {
+                                Some(folders) if !folders.is_empty() => folders,
+                                _ => workspace_folder(&root_path_str).map(|folder| vec![folder]).unwrap_or_default(),
+                            };
+                            *self.workspace_folders.lock().unwrap() = folders.clone();
+                            params_value.workspace_folders = Some(folders);
+                            
                             match self.server.initialize(params_value).await {
                                 Ok(result) => {
+                                    SERVER_CAPABILITIES.insert(self.language.to_lowercase(), result.capabilities.clone());
+                                    *self.capabilities.lock().unwrap() = Some(result.capabilities.clone());
+                                    
                                     let response = json!({
                                         "jsonrpc": "2.0",
                                         "id": id,
@@ -505,7 +776,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                         }
                     },
                     "initialized" => {
-                        logger::info("ServerFactory", &format!("Received 'initialized' notification for Rust server"));
+                        logger::info("ServerFactory", &format!("Received 'initialized' notification for {} server", self.language));
                         
                         let initialized_params = InitializedParams {};
                         self.server.initialized(initialized_params).await;
@@ -513,7 +784,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                         return Ok("".to_string());
                     },
                     "textDocument/didOpen" => {
-                        logger::info("ServerFactory", &format!("Opening document in Rust server"));
+                        logger::info("ServerFactory", &format!("Opening document in {} server", self.language));
                         
                         if let Ok(open_params) = serde_json::from_value::<DidOpenTextDocumentParams>(params.clone()) {
                             self.server.did_open(open_params).await;
@@ -525,7 +796,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                         }
                     },
                     "textDocument/didChange" => {
-                        logger::info("ServerFactory", &format!("Document change notification in Rust server"));
+                        logger::info("ServerFactory", &format!("Document change notification in {} server", self.language));
                         
                         if let Ok(change_params) = serde_json::from_value::<DidChangeTextDocumentParams>(params.clone()) {
                             // Log the contents of the change to assist debugging
@@ -549,7 +820,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                         }
                     },
                     "textDocument/didSave" => {
-                        logger::info("ServerFactory", &format!("Document save notification in Rust server"));
+                        logger::info("ServerFactory", &format!("Document save notification in {} server", self.language));
                         
                         if let Ok(save_params) = serde_json::from_value::<DidSaveTextDocumentParams>(params.clone()) {
                             self.server.did_save(save_params).await;
@@ -561,7 +832,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                         }
                     },
                     "textDocument/didClose" => {
-                        logger::info("ServerFactory", &format!("Document close notification in Rust server"));
+                        logger::info("ServerFactory", &format!("Document close notification in {} server", self.language));
                         
                         if let Ok(close_params) = serde_json::from_value::<DidCloseTextDocumentParams>(params.clone()) {
                             self.server.did_close(close_params).await;
@@ -572,41 +843,27 @@ impl ManagedLanguageServer for RustLspAdapter {
                             return Ok("".to_string());
                         }
                     },
+                    "workspace/didChangeWorkspaceFolders" => {
+                        if let Ok(folders_params) = serde_json::from_value::<DidChangeWorkspaceFoldersParams>(params.clone()) {
+                            self.change_workspace_folders(folders_params.event.added, folders_params.event.removed).await;
+                        } else {
+                            logger::error("ServerFactory", "Failed to parse didChangeWorkspaceFolders parameters");
+                        }
+                        
+                        return Ok("".to_string());
+                    },
                     "textDocument/completion" => {
                         if let Ok(completion_params) = serde_json::from_value::<CompletionParams>(params) {
-                            logger::info("ServerFactory", &format!("Completion request for Rust server: {}", self.language));
+                            logger::info("ServerFactory", &format!("Completion request for {} server", self.language));
                             
-                            let runtime = match tokio::runtime::Runtime::new() {
-                                Ok(rt) => rt,
+                            let completion_result = match self.server.completion(completion_params).await {
+                                Ok(result) => result,
                                 Err(e) => {
-                                    let error_msg = format!("Failed to create tokio runtime: {}", e);
-                                    logger::error("ServerFactory", &error_msg);
-                                    
-                                    let response = json!({
-                                        "jsonrpc": "2.0",
-                                        "id": id,
-                                        "error": {
-                                            "code": -32603,
-                                            "message": error_msg
-                                        }
-                                    });
-                                    
-                                    return Ok(response.to_string());
+                                    logger::error("ServerFactory", &format!("Error during completion execution: {:?}", e));
+                                    None
                                 }
                             };
                             
-                            let server_clone = self.server.clone();
-                            
-                            let completion_result = runtime.block_on(async move {
-                                match server_clone.completion(completion_params).await {
-                                    Ok(result) => result,
-                                    Err(e) => {
-                                        logger::error("ServerFactory", &format!("Error during completion execution: {:?}", e));
-                                        None
-                                    }
-                                }
-                            });
-                            
                             let result = match completion_result {
                                 Some(completion) => {
                                     match serde_json::to_value(completion) {
@@ -691,6 +948,96 @@ impl ManagedLanguageServer for RustLspAdapter {
                             return Ok(response.to_string());
                         }
                     },
+                    "textDocument/documentHighlight" => {
+                        if let Ok(highlight_params) = serde_json::from_value::<DocumentHighlightParams>(params.clone()) {
+                            let result = match self.server.document_highlight(highlight_params).await {
+                                Ok(highlights) => highlights.unwrap_or_default(),
+                                Err(e) => {
+                                    logger::error("ServerFactory", &format!("Error during documentHighlight execution: {:?}", e));
+                                    Vec::new()
+                                }
+                            };
+                            
+                            let response = json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "result": result
+                            });
+                            
+                            return Ok(response.to_string());
+                        } else {
+                            let response = json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "error": {
+                                    "code": -32602,
+                                    "message": "Invalid params for documentHighlight method"
+                                }
+                            });
+                            
+                            return Ok(response.to_string());
+                        }
+                    },
+                    "textDocument/codeLens" => {
+                        if let Ok(code_lens_params) = serde_json::from_value::<CodeLensParams>(params.clone()) {
+                            let result = match self.server.code_lens(code_lens_params).await {
+                                Ok(lenses) => lenses,
+                                Err(e) => {
+                                    logger::error("ServerFactory", &format!("Error during codeLens execution: {:?}", e));
+                                    None
+                                }
+                            };
+                            
+                            let response = json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "result": result
+                            });
+                            
+                            return Ok(response.to_string());
+                        } else {
+                            let response = json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "error": {
+                                    "code": -32602,
+                                    "message": "Invalid params for codeLens method"
+                                }
+                            });
+                            
+                            return Ok(response.to_string());
+                        }
+                    },
+                    "codeLens/resolve" => {
+                        if let Ok(code_lens) = serde_json::from_value::<CodeLens>(params.clone()) {
+                            let result = match self.server.code_lens_resolve(code_lens.clone()).await {
+                                Ok(resolved) => resolved,
+                                Err(e) => {
+                                    logger::error("ServerFactory", &format!("Error during codeLens/resolve execution: {:?}", e));
+                                    code_lens
+                                }
+                            };
+                            
+                            let response = json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "result": result
+                            });
+                            
+                            return Ok(response.to_string());
+                        } else {
+                            let response = json!({
+                                "jsonrpc": "2.0",
+                                "id": id,
+                                "error": {
+                                    "code": -32602,
+                                    "message": "Invalid params for codeLens/resolve method"
+                                }
+                            });
+                            
+                            return Ok(response.to_string());
+                        }
+                    },
                     _ => {
                         logger::info("ServerFactory", &format!("Unsupported LSP method: {}", method));
                         let result = json!({});
@@ -717,8 +1064,33 @@ impl ManagedLanguageServer for RustLspAdapter {
         Ok(())
     }
     
+    fn language(&self) -> &str {
+        &self.language
+    }
+    
+    fn initialized_capabilities(&self) -> Option<ServerCapabilities> {
+        self.capabilities.lock().unwrap().clone()
+    }
+    
+    async fn change_workspace_folders(&self, added: Vec<WorkspaceFolder>, removed: Vec<WorkspaceFolder>) {
+        {
+            let mut folders = self.workspace_folders.lock().unwrap();
+            folders.retain(|folder| !removed.iter().any(|r| r.uri == folder.uri));
+            for folder in &added {
+                if !folders.iter().any(|f| f.uri == folder.uri) {
+                    folders.push(folder.clone());
+                }
+            }
+            logger::info("ServerFactory", &format!("{} server for {} now has {} workspace folder(s)", self.language, self.root_path, folders.len()));
+        }
+        
+        self.server.did_change_workspace_folders(DidChangeWorkspaceFoldersParams {
+            event: WorkspaceFoldersChangeEvent { added, removed },
+        }).await;
+    }
+    
     fn get_capabilities(&self) -> Value {
-        logger::info("ServerFactory", &format!("Getting capabilities for Rust server in project: {}", self.root_path));
+        logger::info("ServerFactory", &format!("Getting capabilities for {} server in project: {}", self.language, self.root_path));
         
         let root_uri = match url::Url::from_file_path(&self.root_path) {
             Ok(uri) => uri,
@@ -728,7 +1100,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                 return json!({
                     "error": error_msg,
                     "_type": "capabilities_error",
-                    "source": "rust_lsp_adapter_uri_creation"
+                    "source": "lsp_adapter_uri_creation"
                 });
             }
         };
@@ -745,18 +1117,19 @@ impl ManagedLanguageServer for RustLspAdapter {
                 return json!({
                     "error": error_msg,
                     "_type": "capabilities_error",
-                    "source": "rust_lsp_adapter_runtime_creation"
+                    "source": "lsp_adapter_runtime_creation"
                 });
             }
         };
         
         let server_clone = self.server.clone();
+        let language = self.language.clone();
         
         let init_result = rt.block_on(async move {
             match server_clone.initialize(params).await {
                 Ok(result) => Ok(result),
                 Err(e) => {
-                    let error_msg = format!("Error initializing Rust server: {:?}", e);
+                    let error_msg = format!("Error initializing {} server: {:?}", language, e);
                     logger::error("ServerFactory", &error_msg);
                     Err(error_msg)
                 }
@@ -773,7 +1146,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                         json!({
                             "error": error_msg,
                             "_type": "capabilities_error",
-                            "source": "rust_lsp_adapter_serialization"
+                            "source": "lsp_adapter_serialization"
                         })
                     }
                 }
@@ -782,7 +1155,7 @@ impl ManagedLanguageServer for RustLspAdapter {
                 json!({
                     "error": e,
                     "_type": "capabilities_error",
-                    "source": "rust_lsp_adapter_initialization"
+                    "source": "lsp_adapter_initialization"
                 })
             }
         }
@@ -794,42 +1167,70 @@ impl LanguageServer for LanguageServerInstance {
     async fn initialize(&self, params: InitializeParams) -> LspResult<InitializeResult> {
         match self {
             LanguageServerInstance::Rust(server) => server.initialize(params).await,
+            LanguageServerInstance::Toml(server) => server.initialize(params).await,
+            LanguageServerInstance::Json(server) => server.initialize(params).await,
+            LanguageServerInstance::Yaml(server) => server.initialize(params).await,
+            LanguageServerInstance::Generic(server) => server.initialize(params).await,
         }
     }
     
     async fn initialized(&self, params: InitializedParams) {
         match self {
             LanguageServerInstance::Rust(server) => server.initialized(params).await,
+            LanguageServerInstance::Toml(server) => server.initialized(params).await,
+            LanguageServerInstance::Json(server) => server.initialized(params).await,
+            LanguageServerInstance::Yaml(server) => server.initialized(params).await,
+            LanguageServerInstance::Generic(server) => server.initialized(params).await,
         }
     }
     
     async fn shutdown(&self) -> LspResult<()> {
         match self {
             LanguageServerInstance::Rust(server) => server.shutdown().await,
+            LanguageServerInstance::Toml(server) => server.shutdown().await,
+            LanguageServerInstance::Json(server) => server.shutdown().await,
+            LanguageServerInstance::Yaml(server) => server.shutdown().await,
+            LanguageServerInstance::Generic(server) => server.shutdown().await,
         }
     }
     
     async fn did_open(&self, params: DidOpenTextDocumentParams) {
         match self {
             LanguageServerInstance::Rust(server) => server.did_open(params).await,
+            LanguageServerInstance::Toml(server) => server.did_open(params).await,
+            LanguageServerInstance::Json(server) => server.did_open(params).await,
+            LanguageServerInstance::Yaml(server) => server.did_open(params).await,
+            LanguageServerInstance::Generic(server) => server.did_open(params).await,
         }
     }
     
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         match self {
             LanguageServerInstance::Rust(server) => server.did_change(params).await,
+            LanguageServerInstance::Toml(server) => server.did_change(params).await,
+            LanguageServerInstance::Json(server) => server.did_change(params).await,
+            LanguageServerInstance::Yaml(server) => server.did_change(params).await,
+            LanguageServerInstance::Generic(server) => server.did_change(params).await,
         }
     }
     
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
         match self {
             LanguageServerInstance::Rust(server) => server.did_save(params).await,
+            LanguageServerInstance::Toml(server) => server.did_save(params).await,
+            LanguageServerInstance::Json(server) => server.did_save(params).await,
+            LanguageServerInstance::Yaml(server) => server.did_save(params).await,
+            LanguageServerInstance::Generic(server) => server.did_save(params).await,
         }
     }
     
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         match self {
             LanguageServerInstance::Rust(server) => server.did_close(params).await,
+            LanguageServerInstance::Toml(server) => server.did_close(params).await,
+            LanguageServerInstance::Json(server) => server.did_close(params).await,
+            LanguageServerInstance::Yaml(server) => server.did_close(params).await,
+            LanguageServerInstance::Generic(server) => server.did_close(params).await,
         }
     }
     
@@ -837,30 +1238,60 @@ impl LanguageServer for LanguageServerInstance {
     async fn completion(&self, params: CompletionParams) -> LspResult<Option<CompletionResponse>> {
         match self {
             LanguageServerInstance::Rust(server) => server.completion(params).await,
+            LanguageServerInstance::Toml(server) => server.completion(params).await,
+            LanguageServerInstance::Json(server) => server.completion(params).await,
+            LanguageServerInstance::Yaml(server) => server.completion(params).await,
+            LanguageServerInstance::Generic(server) => server.completion(params).await,
         }
     }
     
     async fn hover(&self, params: HoverParams) -> LspResult<Option<Hover>> {
         match self {
             LanguageServerInstance::Rust(server) => server.hover(params).await,
+            LanguageServerInstance::Toml(server) => server.hover(params).await,
+            LanguageServerInstance::Json(server) => server.hover(params).await,
+            LanguageServerInstance::Yaml(server) => server.hover(params).await,
+            LanguageServerInstance::Generic(server) => server.hover(params).await,
         }
     }
     
     async fn goto_definition(&self, params: GotoDefinitionParams) -> LspResult<Option<GotoDefinitionResponse>> {
         match self {
             LanguageServerInstance::Rust(server) => server.goto_definition(params).await,
+            LanguageServerInstance::Toml(server) => server.goto_definition(params).await,
+            LanguageServerInstance::Json(server) => server.goto_definition(params).await,
+            LanguageServerInstance::Yaml(server) => server.goto_definition(params).await,
+            LanguageServerInstance::Generic(server) => server.goto_definition(params).await,
         }
     }
     
     async fn references(&self, params: ReferenceParams) -> LspResult<Option<Vec<Location>>> {
         match self {
             LanguageServerInstance::Rust(server) => server.references(params).await,
+            LanguageServerInstance::Toml(server) => server.references(params).await,
+            LanguageServerInstance::Json(server) => server.references(params).await,
+            LanguageServerInstance::Yaml(server) => server.references(params).await,
+            LanguageServerInstance::Generic(server) => server.references(params).await,
+        }
+    }
+    
+    async fn document_highlight(&self, params: DocumentHighlightParams) -> LspResult<Option<Vec<DocumentHighlight>>> {
+        match self {
+            LanguageServerInstance::Rust(server) => server.document_highlight(params).await,
+            LanguageServerInstance::Toml(server) => server.document_highlight(params).await,
+            LanguageServerInstance::Json(server) => server.document_highlight(params).await,
+            LanguageServerInstance::Yaml(server) => server.document_highlight(params).await,
+            LanguageServerInstance::Generic(server) => server.document_highlight(params).await,
         }
     }
     
     async fn formatting(&self, params: DocumentFormattingParams) -> LspResult<Option<Vec<TextEdit>>> {
         match self {
             LanguageServerInstance::Rust(server) => server.formatting(params).await,
+            LanguageServerInstance::Toml(server) => server.formatting(params).await,
+            LanguageServerInstance::Json(server) => server.formatting(params).await,
+            LanguageServerInstance::Yaml(server) => server.formatting(params).await,
+            LanguageServerInstance::Generic(server) => server.formatting(params).await,
         }
     }
 } 
\ No newline at end of file
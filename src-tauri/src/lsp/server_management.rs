@@ -1,6 +1,8 @@
 use std::thread;
 use std::sync::{RwLock, OnceLock};
 use std::collections::HashMap;
+use std::path::Path;
+use serde::Serialize;
 use tower_lsp::LspService;
 use tower_lsp::Server;
 use anyhow::Result;
@@ -15,11 +17,11 @@ fn get_active_servers() -> &'static RwLock<HashMap<String, bool>> {
 }
 
 pub fn get_supported_languages() -> Vec<&'static str> {
-    vec!["rust"]
+    vec!["rust", "toml", "json", "yaml"]
 }
 
 pub fn get_recognized_languages() -> Vec<&'static str> {
-    vec!["rust", "javascript", "typescript", "python"]
+    vec!["rust", "toml", "json", "yaml", "javascript", "typescript", "python"]
 }
 
 pub async fn start_language_server(language: String, file_path: String) -> Result<()> {
@@ -33,8 +35,8 @@ pub async fn start_language_server(language: String, file_path: String) -> Resul
     Ok(())
 }
 
-pub async fn start_lsp_server(language: String, file_path: String) -> Result<String, String> {
-    let _server_factory = ServerFactory::new();
+pub async fn start_lsp_server(language: String, file_path: String, toolchain: Option<String>) -> Result<String, String> {
+    let server_factory = ServerFactory::new();
     
     let path = std::path::Path::new(&file_path);
     if !path.exists() {
@@ -52,6 +54,9 @@ pub async fn start_lsp_server(language: String, file_path: String) -> Result<Str
                 "py" => "python".to_string(),
                 "js" => "javascript".to_string(),
                 "ts" => "typescript".to_string(),
+                "toml" => "toml".to_string(),
+                "json" => "json".to_string(),
+                "yaml" | "yml" => "yaml".to_string(),
                 _ => normalized_language
             };
             log("start_lsp_server", &format!("Automatically detected language: {} based on file extension", normalized_language));
@@ -68,6 +73,13 @@ pub async fn start_lsp_server(language: String, file_path: String) -> Result<Str
         ));
     }
     
+    if let (Some(toolchain), "rust") = (toolchain.as_deref(), normalized_language.as_str()) {
+        // Registered per project, before the running check, so websocket servers pick it up too
+        let root_path = server_factory.find_project_root(&normalized_language, &file_path)
+            .map_err(|e| format!("Failed to find project root: {}", e))?;
+        crate::lsp::servers::rust::set_toolchain_override(Path::new(&root_path), toolchain);
+    }
+    
     let is_server_running = {
         let active_servers = get_active_servers();
         let servers_read = active_servers.read().unwrap();
@@ -140,6 +152,84 @@ pub async fn find_project_root(file_path: String, language: Option<String>) -> R
     }
 }
 
+/**
+ * A kind of project recognized from a marker file, e.g. `Cargo.toml` for Rust
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectType {
+    /// "rust", "node", "python", "go", "cmake", "make", "maven", "gradle"
+    pub kind: String,
+    /// Language server language for the project, when one applies
+    pub language: Option<String>,
+    /// Path of the file that identified the project
+    pub marker_file: String,
+    /// Directory containing the marker file
+    pub root: String,
+}
+
+const PROJECT_MARKERS: &[(&str, &[&str])] = &[
+    ("rust", &["Cargo.toml"]),
+    ("node", &["package.json"]),
+    ("python", &["pyproject.toml", "requirements.txt", "setup.py", "Pipfile"]),
+    ("go", &["go.mod"]),
+    ("cmake", &["CMakeLists.txt"]),
+    ("make", &["Makefile", "makefile", "GNUmakefile"]),
+    ("maven", &["pom.xml"]),
+    ("gradle", &["build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"]),
+];
+
+/// How many directories above the starting point are searched for markers
+const PROJECT_DETECTION_DEPTH: usize = 4;
+
+/**
+ * Detect every kind of project `path` belongs to, looking in the directory itself and a few
+ * levels up. For each kind the nearest marker wins, so nested workspaces report their own root
+ */
+pub fn detect_project_type(path: &str) -> Result<Vec<ProjectType>, String> {
+    let path = Path::new(path);
+    if !path.exists() {
+        return Err(format!("Specified path does not exist: {}", path.display()));
+    }
+    
+    let start_dir = if path.is_dir() { path } else { path.parent().unwrap_or(path) };
+    let mut project_types: Vec<ProjectType> = Vec::new();
+    
+    for dir in start_dir.ancestors().take(PROJECT_DETECTION_DEPTH + 1) {
+        for (kind, markers) in PROJECT_MARKERS {
+            if project_types.iter().any(|project| project.kind == *kind) {
+                continue;
+            }
+            
+            let Some(marker) = markers.iter().map(|marker| dir.join(marker)).find(|marker| marker.is_file()) else {
+                continue;
+            };
+            
+            let language = match *kind {
+                "rust" => Some("rust"),
+                "node" if dir.join("tsconfig.json").exists() => Some("typescript"),
+                "node" => Some("javascript"),
+                "python" => Some("python"),
+                "go" => Some("go"),
+                "cmake" => Some("cpp"),
+                "maven" | "gradle" => Some("java"),
+                _ => None,
+            };
+            
+            project_types.push(ProjectType {
+                kind: kind.to_string(),
+                language: language.map(|language| language.to_string()),
+                marker_file: marker.to_string_lossy().to_string(),
+                root: dir.to_string_lossy().to_string(),
+            });
+        }
+    }
+    
+    log("detect_project_type", &format!("Detected project types for {}: {:?}", start_dir.display(), 
+        project_types.iter().map(|project| project.kind.as_str()).collect::<Vec<_>>()));
+    
+    Ok(project_types)
+}
+
 pub fn log(component: &str, message: &str) {
     logger::info(component, message);
 }
@@ -0,0 +1,310 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex as StdMutex};
+use anyhow::Result;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tower_lsp::jsonrpc::Result as LspResult;
+use tower_lsp::{LanguageServer, Client};
+use tower_lsp::lsp_types::{
+    InitializeParams, InitializeResult, InitializedParams, MessageType, ServerCapabilities, ServerInfo, TextDocumentSyncCapability,
+    TextDocumentSyncKind, CompletionOptions, OneOf, DidOpenTextDocumentParams, DidChangeTextDocumentParams,
+    DidSaveTextDocumentParams, DidCloseTextDocumentParams, CompletionParams, CompletionResponse, CompletionList,
+    CompletionItem, CompletionItemKind, HoverParams, Hover, GotoDefinitionParams, GotoDefinitionResponse, ReferenceParams, Location,
+    DocumentFormattingParams, TextEdit, DocumentHighlightParams, DocumentHighlight, DocumentHighlightKind, Position, Range,
+};
+
+use crate::lsp::config::ServerConfig;
+use crate::lsp::protocol::LSPUtils;
+use crate::lsp::servers::BaseLanguageServer;
+use crate::lsp::logger;
+
+/**
+ * Most completion items returned for one request
+ */
+const MAX_COMPLETION_ITEMS: usize = 100;
+
+/**
+ * Words shorter than this are quicker to type than to pick from a list
+ */
+const MIN_WORD_LENGTH: usize = 3;
+
+const BRACKET_PAIRS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}')];
+
+/**
+ * Identifier-like words in any language: letters, digits and underscores, not starting with a digit
+ */
+static WORD_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"[\p{L}_][\p{L}\p{N}_]*").unwrap());
+
+/**
+ * Fallback for languages without a dedicated server. Instead of spawning an external
+ * process it keeps the open documents in memory and offers completion from each
+ * document's own words and bracket matching; other requests get empty results
+ */
+#[derive(Clone)]
+pub struct GenericLanguageServer {
+    client: Option<Client>,
+    config: ServerConfig,
+    /// Content of the open documents, keyed by URI
+    document_data: Arc<DashMap<String, String>>,
+    is_initialized: Arc<StdMutex<bool>>,
+}
+
+impl LSPUtils for GenericLanguageServer {}
+
+impl BaseLanguageServer for GenericLanguageServer {
+    fn id(&self) -> &str {
+        "generic"
+    }
+
+    fn name(&self) -> &str {
+        "Generic Language Server"
+    }
+
+    fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    fn initialize(&self) -> Result<()> {
+        *self.is_initialized.lock().unwrap() = true;
+        logger::info("GenericServer", &format!("Serving {:?} without an external language server", self.config.root_path));
+        Ok(())
+    }
+
+    fn shutdown(&self) -> Result<()> {
+        self.document_data.clear();
+        *self.is_initialized.lock().unwrap() = false;
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        *self.is_initialized.lock().unwrap()
+    }
+}
+
+impl GenericLanguageServer {
+    pub fn default_config(root_path: &str) -> Result<ServerConfig> {
+        ServerConfig::new(root_path)
+    }
+
+    pub fn new(root_path: String) -> Result<Self> {
+        Ok(Self {
+            client: None,
+            config: Self::default_config(&root_path)?,
+            document_data: Arc::new(DashMap::new()),
+            is_initialized: Arc::new(StdMutex::new(false)),
+        })
+    }
+
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    fn to_offset(content: &str, position: Position) -> usize {
+        Self::position_to_offset(content, lsp_types::Position::new(position.line, position.character))
+            .unwrap_or(content.len())
+    }
+
+    fn to_position(content: &str, offset: usize) -> Position {
+        let position = Self::offset_to_position(content, offset);
+        Position::new(position.line, position.character)
+    }
+
+    /**
+     * Words of `content` starting with the word being typed at `offset`, case-insensitively,
+     * in the order they first appear. Returns the items and whether the list was cut short
+     */
+    fn word_completions(content: &str, offset: usize) -> (Vec<CompletionItem>, bool) {
+        let prefix_start = content[..offset].char_indices()
+            .rev()
+            .take_while(|(_, c)| c.is_alphanumeric() || *c == '_')
+            .last()
+            .map(|(index, _)| index)
+            .unwrap_or(offset);
+        let prefix = content[prefix_start..offset].to_lowercase();
+
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+
+        for word in WORD_PATTERN.find_iter(content) {
+            // The word under the cursor is the one being typed
+            if word.start() == prefix_start {
+                continue;
+            }
+
+            let text = word.as_str();
+            if text.chars().count() < MIN_WORD_LENGTH || !text.to_lowercase().starts_with(&prefix) || !seen.insert(text) {
+                continue;
+            }
+
+            if items.len() == MAX_COMPLETION_ITEMS {
+                return (items, true);
+            }
+            items.push(CompletionItem {
+                label: text.to_string(),
+                kind: Some(CompletionItemKind::TEXT),
+                ..Default::default()
+            });
+        }
+
+        (items, false)
+    }
+
+    /**
+     * Offsets of the bracket at (or just before) `offset` and its partner. Brackets
+     * inside strings and comments are counted too, since the language is unknown
+     */
+    fn matching_brackets(content: &str, offset: usize) -> Option<(usize, usize)> {
+        let bracket_at = |offset: usize| {
+            let c = content.get(offset..)?.chars().next()?;
+            BRACKET_PAIRS.iter()
+                .find(|(open, close)| c == *open || c == *close)
+                .map(|pair| (offset, c, *pair))
+        };
+
+        let before = content[..offset].char_indices().next_back().map(|(index, _)| index);
+        let (start, c, (open, close)) = bracket_at(offset).or_else(|| before.and_then(bracket_at))?;
+
+        let mut depth = 0;
+        if c == open {
+            for (index, c) in content[start..].char_indices() {
+                if c == open {
+                    depth += 1;
+                } else if c == close {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some((start, start + index));
+                    }
+                }
+            }
+        } else {
+            for (index, c) in content[..=start].char_indices().rev() {
+                if c == close {
+                    depth += 1;
+                } else if c == open {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some((index, start));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[async_trait]
+impl LanguageServer for GenericLanguageServer {
+    async fn initialize(&self, _params: InitializeParams) -> LspResult<InitializeResult> {
+        let _ = <Self as BaseLanguageServer>::initialize(self);
+
+        if let Some(client) = &self.client {
+            client.log_message(MessageType::INFO, "No language server for this file; using word-based completion").await;
+        }
+
+        Ok(InitializeResult {
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+                completion_provider: Some(CompletionOptions::default()),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                ..Default::default()
+            },
+            server_info: Some(ServerInfo {
+                name: self.name().to_string(),
+                version: None,
+            }),
+        })
+    }
+
+    async fn initialized(&self, _params: InitializedParams) {}
+
+    async fn shutdown(&self) -> LspResult<()> {
+        let _ = <Self as BaseLanguageServer>::shutdown(self);
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        self.document_data.insert(params.text_document.uri.to_string(), params.text_document.text);
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        let uri = params.text_document.uri.to_string();
+        let mut content = self.document_data.get(&uri).map(|content| content.clone()).unwrap_or_default();
+
+        // Full sync is advertised, but ranged changes are applied in order as well
+        for change in params.content_changes {
+            match change.range {
+                Some(range) => {
+                    let start = Self::to_offset(&content, range.start);
+                    let end = Self::to_offset(&content, range.end).max(start);
+                    content.replace_range(start..end, &change.text);
+                },
+                None => content = change.text,
+            }
+        }
+
+        self.document_data.insert(uri, content);
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        if let Some(text) = params.text {
+            self.document_data.insert(params.text_document.uri.to_string(), text);
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.document_data.remove(params.text_document.uri.as_str());
+    }
+
+    async fn completion(&self, params: CompletionParams) -> LspResult<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let Some(content) = self.document_data.get(position.text_document.uri.as_str()) else {
+            return Ok(None);
+        };
+
+        let offset = Self::to_offset(&content, position.position);
+        let (items, is_incomplete) = Self::word_completions(&content, offset);
+
+        Ok(Some(CompletionResponse::List(CompletionList { is_incomplete, items })))
+    }
+
+    async fn hover(&self, _params: HoverParams) -> LspResult<Option<Hover>> {
+        Ok(None)
+    }
+
+    async fn goto_definition(&self, _params: GotoDefinitionParams) -> LspResult<Option<GotoDefinitionResponse>> {
+        Ok(None)
+    }
+
+    async fn references(&self, _params: ReferenceParams) -> LspResult<Option<Vec<Location>>> {
+        Ok(None)
+    }
+
+    async fn document_highlight(&self, params: DocumentHighlightParams) -> LspResult<Option<Vec<DocumentHighlight>>> {
+        let position = params.text_document_position_params;
+        let Some(content) = self.document_data.get(position.text_document.uri.as_str()) else {
+            return Ok(Some(Vec::new()));
+        };
+
+        let offset = Self::to_offset(&content, position.position);
+        let highlights = Self::matching_brackets(&content, offset)
+            .map(|(open, close)| {
+                [open, close].into_iter()
+                    .map(|offset| DocumentHighlight {
+                        range: Range::new(Self::to_position(&content, offset), Self::to_position(&content, offset + 1)),
+                        kind: Some(DocumentHighlightKind::TEXT),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Some(highlights))
+    }
+
+    async fn formatting(&self, _params: DocumentFormattingParams) -> LspResult<Option<Vec<TextEdit>>> {
+        Ok(None)
+    }
+}
@@ -0,0 +1,17 @@
+use crate::lsp::servers::stdio::{StdioLanguageServer, StdioServerSpec};
+
+#[derive(Clone)]
+pub struct VscodeJson;
+
+impl StdioServerSpec for VscodeJson {
+    const ID: &'static str = "vscode-json-languageserver";
+    const LANGUAGE: &'static str = "json";
+    const NAME: &'static str = "JSON Language Server";
+    const EXECUTABLE: &'static str = "vscode-json-languageserver";
+    const ARGS: &'static [&'static str] = &["--stdio"];
+}
+
+/**
+ * JSON support (package.json, tsconfig.json, ...) backed by `vscode-json-languageserver --stdio`
+ */
+pub type JsonLanguageServer = StdioLanguageServer<VscodeJson>;
@@ -1,4 +1,9 @@
+pub mod generic;
+pub mod json;
 pub mod rust;
+pub mod stdio;
+pub mod toml;
+pub mod yaml;
 
 use anyhow::Result;
 use tower_lsp::LanguageServer;
@@ -1,5 +1,10 @@
-use std::sync::{Arc, Mutex as StdMutex};
-use std::process::{Command, Stdio, Child};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak, Mutex as StdMutex};
+use std::process::{Command, Stdio, Child, ChildStderr, ExitStatus};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 use anyhow::Result;
 use tower_lsp::jsonrpc::Result as LspResult;
 use tower_lsp::{LanguageServer, Client};
@@ -12,20 +17,683 @@ use tower_lsp::lsp_types::{
     ServerInfo, InitializedParams, MessageType, DidOpenTextDocumentParams, DidChangeTextDocumentParams,
     DidSaveTextDocumentParams, DidCloseTextDocumentParams, CompletionParams, CompletionResponse,
     HoverParams, Hover, GotoDefinitionParams, GotoDefinitionResponse, ReferenceParams, Location,
-    DocumentFormattingParams, TextEdit, Diagnostic, PublishDiagnosticsParams
+    DocumentFormattingParams, TextEdit, PublishDiagnosticsParams,
+    RegistrationParams, UnregistrationParams, DidChangeWatchedFilesRegistrationOptions,
+    DidChangeWatchedFilesParams, DidChangeWatchedFilesClientCapabilities, FileEvent, Url,
+    DidChangeWorkspaceFoldersParams, CodeLens, CodeLensParams, LocationLink, Position, TextDocumentIdentifier,
+    TextDocumentPositionParams, DocumentHighlightParams, DocumentHighlight, ReferenceContext, Range,
+    CompletionItem
 };
 use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
 use dashmap::DashMap;
+use once_cell::sync::Lazy;
 use tokio::sync::mpsc::{self, UnboundedSender, UnboundedReceiver};
 use tokio::sync::{RwLock, Mutex};
 
-use crate::lsp::config::ServerConfig;
-use crate::lsp::protocol::{LSPUtils, LspProcessConnection, JsonRpcNotification};
+use crate::lsp::config::{ServerConfig, merge_json};
+use crate::lsp::protocol::{LSPUtils, LspProcessConnection, JsonRpcNotification, JsonRpcResponse, file_uri_to_path};
 use crate::lsp::servers::BaseLanguageServer;
+use crate::lsp::logger;
+use crate::lsp::diagnostics::DiagnosticSession;
+use crate::lsp::file_watcher::{FileChangeBatch, WatchedGlob, WorkspaceFileWatcher};
 
 struct DocumentData {
     content: String,
-    diagnostics: Vec<Diagnostic>,
+}
+
+type DocumentStore = Arc<RwLock<DashMap<String, DocumentData>>>;
+
+type ConnectionSlot = Arc<Mutex<Option<LspProcessConnection>>>;
+type WeakConnectionSlot = Weak<Mutex<Option<LspProcessConnection>>>;
+type PendingChanges = Arc<DashMap<String, DidChangeTextDocumentParams>>;
+
+/**
+ * What requests sent on behalf of a server need: its connection, the didChange
+ * notifications it hasn't sent yet, and its request timeout
+ */
+#[derive(Clone)]
+struct ServerHandle {
+    connection: ConnectionSlot,
+    pending_changes: PendingChanges,
+    change_flush_lock: Arc<Mutex<()>>,
+    request_timeout: Duration,
+}
+
+impl ServerHandle {
+    async fn connection(&self) -> Result<LspProcessConnection> {
+        self.connection.lock().await.as_ref().cloned()
+            .ok_or_else(|| anyhow::anyhow!("No connection to rust-analyzer"))
+    }
+    
+    /**
+     * Send a request once queued changes are flushed, so it sees the latest document
+     * content, giving up after the configured request timeout
+     */
+    async fn send_request<T: serde::Serialize>(&self, method: &str, params: Option<T>) -> Result<JsonRpcResponse> {
+        self.flush_all_pending_changes().await;
+        
+        let connection = self.connection().await?;
+        connection.send_request_with_timeout(method, params, self.request_timeout).await
+    }
+    
+    async fn flush_pending_change(&self, uri: &str) {
+        let _guard = self.change_flush_lock.lock().await;
+        
+        if let Some((_, params)) = self.pending_changes.remove(uri) {
+            logger::debug("RustAnalyzer", &format!("Sending {} batched change(s) for {} to rust-analyzer", params.content_changes.len(), uri));
+            let sent = match self.connection().await {
+                Ok(connection) => connection.send_notification("textDocument/didChange", Some(params)),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = sent {
+                logger::error("RustAnalyzer", &format!("Failed to send didChange notification: {}", e));
+            }
+        }
+    }
+    
+    async fn flush_all_pending_changes(&self) {
+        let uris: Vec<String> = self.pending_changes.iter().map(|entry| entry.key().clone()).collect();
+        
+        for uri in uris {
+            self.flush_pending_change(&uri).await;
+        }
+    }
+}
+
+/**
+ * A `ServerHandle` that doesn't keep its server alive
+ */
+struct WeakServerHandle {
+    connection: WeakConnectionSlot,
+    pending_changes: Weak<DashMap<String, DidChangeTextDocumentParams>>,
+    change_flush_lock: Weak<Mutex<()>>,
+    request_timeout: Duration,
+}
+
+impl WeakServerHandle {
+    fn new(handle: &ServerHandle) -> Self {
+        Self {
+            connection: Arc::downgrade(&handle.connection),
+            pending_changes: Arc::downgrade(&handle.pending_changes),
+            change_flush_lock: Arc::downgrade(&handle.change_flush_lock),
+            request_timeout: handle.request_timeout,
+        }
+    }
+    
+    fn upgrade(&self) -> Option<ServerHandle> {
+        Some(ServerHandle {
+            connection: self.connection.upgrade()?,
+            pending_changes: self.pending_changes.upgrade()?,
+            change_flush_lock: self.change_flush_lock.upgrade()?,
+            request_timeout: self.request_timeout,
+        })
+    }
+}
+
+/**
+ * Every live server instance with its workspace root,
+ * for requests that don't come from an editor session (e.g. runnables)
+ */
+static RUNNING_SERVERS: Lazy<StdMutex<Vec<(PathBuf, WeakServerHandle)>>> = Lazy::new(|| StdMutex::new(Vec::new()));
+
+fn register_server(root_path: &Path, handle: &ServerHandle) {
+    let mut servers = RUNNING_SERVERS.lock().unwrap();
+    servers.retain(|(_, weak)| weak.connection.strong_count() > 0);
+    servers.push((root_path.to_path_buf(), WeakServerHandle::new(handle)));
+}
+
+/**
+ * Started server whose workspace root most closely encloses `file_path`
+ */
+async fn server_for_file(file_path: &Path) -> Option<ServerHandle> {
+    let candidates: Vec<(PathBuf, ServerHandle)> = RUNNING_SERVERS.lock().unwrap()
+        .iter()
+        .filter(|(root, _)| file_path.starts_with(root))
+        .filter_map(|(root, weak)| weak.upgrade().map(|handle| (root.clone(), handle)))
+        .collect();
+    
+    let (_, handle) = candidates.into_iter().max_by_key(|(root, _)| root.components().count())?;
+    let started = handle.connection.lock().await.is_some();
+    started.then_some(handle)
+}
+
+/**
+ * A cargo or shell invocation reported by rust-analyzer for a test, binary or benchmark
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct RunnableCommand {
+    pub label: String,
+    /// "cargo" or "shell"
+    pub kind: String,
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: Option<String>,
+    pub environment: HashMap<String, String>,
+    pub location: Option<LocationLink>,
+}
+
+impl RunnableCommand {
+    fn from_runnable(runnable: &serde_json::Value) -> Option<Self> {
+        let string_list = |value: Option<&serde_json::Value>| -> Vec<String> {
+            value.and_then(|v| v.as_array())
+                .map(|items| items.iter().filter_map(|item| item.as_str().map(|s| s.to_string())).collect())
+                .unwrap_or_default()
+        };
+        
+        let label = runnable.get("label")?.as_str()?.to_string();
+        let kind = runnable.get("kind").and_then(|k| k.as_str()).unwrap_or("cargo").to_string();
+        let args = runnable.get("args")?;
+        
+        let (program, command_args) = if kind == "shell" {
+            (args.get("program")?.as_str()?.to_string(), string_list(args.get("args")))
+        } else {
+            let program = args.get("overrideCargo").and_then(|p| p.as_str()).unwrap_or("cargo").to_string();
+            let mut command_args = string_list(args.get("cargoArgs"));
+            command_args.extend(string_list(args.get("cargoExtraArgs")));
+            
+            let executable_args = string_list(args.get("executableArgs"));
+            if !executable_args.is_empty() {
+                command_args.push("--".to_string());
+                command_args.extend(executable_args);
+            }
+            (program, command_args)
+        };
+        
+        // Older rust-analyzer versions only report the workspace root
+        let cwd = args.get("cwd")
+            .or_else(|| args.get("workspaceRoot"))
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string());
+        
+        let environment = args.get("environment")
+            .and_then(|e| serde_json::from_value::<HashMap<String, String>>(e.clone()).ok())
+            .unwrap_or_default();
+        
+        let location = runnable.get("location")
+            .and_then(|l| serde_json::from_value::<LocationLink>(l.clone()).ok());
+        
+        Some(RunnableCommand { label, kind, program, args: command_args, cwd, environment, location })
+    }
+}
+
+/**
+ * Ask rust-analyzer for the runnables of a file (or only those at `position`)
+ * through the experimental `experimental/runnables` request.
+ * Servers that don't support the request yield an empty list
+ */
+pub async fn get_runnables(file_path: &str, position: Option<Position>) -> Result<Vec<RunnableCommand>> {
+    let path = Path::new(file_path);
+    let server = server_for_file(path).await
+        .ok_or_else(|| anyhow::anyhow!("No running rust-analyzer for {}", file_path))?;
+    
+    let uri = Url::from_file_path(path)
+        .map_err(|_| anyhow::anyhow!("Cannot create URI from path: {}", file_path))?;
+    let params = serde_json::json!({
+        "textDocument": TextDocumentIdentifier::new(uri),
+        "position": position,
+    });
+    
+    let response = server.send_request("experimental/runnables", Some(params)).await?;
+    
+    if let Some(error) = response.error {
+        logger::warn("RustAnalyzer", &format!("experimental/runnables is not available: {} (code: {})", error.message, error.code));
+        return Ok(Vec::new());
+    }
+    
+    let runnables = response.result
+        .as_ref()
+        .and_then(|result| result.as_array())
+        .map(|items| items.iter().filter_map(RunnableCommand::from_runnable).collect())
+        .unwrap_or_default();
+    
+    Ok(runnables)
+}
+
+/**
+ * Result of rust-analyzer's `rust-analyzer/expandMacro` request
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpandedMacro {
+    pub name: String,
+    pub expansion: String,
+}
+
+/**
+ * Recursively expand the macro call under `position`.
+ * Returns None when the cursor is not on a macro call
+ */
+pub async fn expand_macro(file_path: &str, position: Position) -> Result<Option<ExpandedMacro>> {
+    let path = Path::new(file_path);
+    let server = server_for_file(path).await
+        .ok_or_else(|| anyhow::anyhow!("No running rust-analyzer for {}", file_path))?;
+    
+    let uri = Url::from_file_path(path)
+        .map_err(|_| anyhow::anyhow!("Cannot create URI from path: {}", file_path))?;
+    let params = TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri), position);
+    
+    let response = server.send_request("rust-analyzer/expandMacro", Some(params)).await?;
+    
+    if let Some(error) = response.error {
+        return Err(anyhow::anyhow!("LSP error: {} (code: {})", error.message, error.code));
+    }
+    
+    match response.result {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(result) => Ok(Some(serde_json::from_value(result)?)),
+    }
+}
+
+/**
+ * Locate the `mod` declaration of the module under `position` via rust-analyzer's
+ * `experimental/parentModule`. Returns None when there is no parent module (e.g. a crate root)
+ */
+pub async fn parent_module(file_path: &str, position: Position) -> Result<Option<GotoDefinitionResponse>> {
+    let path = Path::new(file_path);
+    let server = server_for_file(path).await
+        .ok_or_else(|| anyhow::anyhow!("No running rust-analyzer for {}", file_path))?;
+    
+    let uri = Url::from_file_path(path)
+        .map_err(|_| anyhow::anyhow!("Cannot create URI from path: {}", file_path))?;
+    let params = TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri), position);
+    
+    let response = server.send_request("experimental/parentModule", Some(params)).await?;
+    
+    if let Some(error) = response.error {
+        return Err(anyhow::anyhow!("LSP error: {} (code: {})", error.message, error.code));
+    }
+    
+    match response.result {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Array(items)) if items.is_empty() => Ok(None),
+        Some(result) => Ok(Some(serde_json::from_value(result)?)),
+    }
+}
+
+/**
+ * A location returned by `find_references`, with its URI resolved to a file system path
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ReferenceLocation {
+    pub path: String,
+    pub range: Range,
+}
+
+/**
+ * Find usages of the symbol under `position`, with or without its declaration.
+ * Locations that aren't files on disk are skipped
+ */
+pub async fn find_references(file_path: &str, position: Position, include_declaration: bool) -> Result<Vec<ReferenceLocation>> {
+    let path = Path::new(file_path);
+    let server = server_for_file(path).await
+        .ok_or_else(|| anyhow::anyhow!("No running rust-analyzer for {}", file_path))?;
+    
+    let uri = Url::from_file_path(path)
+        .map_err(|_| anyhow::anyhow!("Cannot create URI from path: {}", file_path))?;
+    let params = ReferenceParams {
+        text_document_position: TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri), position),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+        context: ReferenceContext { include_declaration },
+    };
+    
+    let response = server.send_request("textDocument/references", Some(params)).await?;
+    
+    if let Some(error) = response.error {
+        return Err(anyhow::anyhow!("LSP error: {} (code: {})", error.message, error.code));
+    }
+    
+    let locations: Vec<Location> = match response.result {
+        None | Some(serde_json::Value::Null) => Vec::new(),
+        Some(result) => serde_json::from_value(result)?,
+    };
+    
+    Ok(locations.into_iter()
+        .filter_map(|location| {
+            let path = file_uri_to_path(location.uri.as_str()).ok()?;
+            Some(ReferenceLocation { path, range: location.range })
+        })
+        .collect())
+}
+
+/**
+ * Hover contents and definition of the symbol under a position, fetched together
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct HoverAndDefinition {
+    pub hover: Option<Hover>,
+    pub definition: Option<GotoDefinitionResponse>,
+}
+
+/**
+ * Result of a request whose answer may be null, logging instead of failing so
+ * requests sent together don't hide each other's results
+ */
+fn optional_result<T: serde::de::DeserializeOwned>(method: &str, response: Result<JsonRpcResponse>) -> Option<T> {
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            logger::warn("RustAnalyzer", &format!("{} request failed: {}", method, e));
+            return None;
+        }
+    };
+    
+    if let Some(error) = response.error {
+        logger::warn("RustAnalyzer", &format!("{} returned LSP error: {} (code: {})", method, error.message, error.code));
+        return None;
+    }
+    
+    match response.result {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::Array(items)) if items.is_empty() => None,
+        Some(result) => serde_json::from_value(result)
+            .map_err(|e| logger::warn("RustAnalyzer", &format!("Failed to parse {} response: {}", method, e)))
+            .ok(),
+    }
+}
+
+/**
+ * Send `textDocument/hover` and `textDocument/definition` for the same position
+ * concurrently, for "peek" interactions that need both. Either half is None when
+ * the server has nothing for it or its request fails
+ */
+pub async fn hover_and_definition(file_path: &str, position: Position) -> Result<HoverAndDefinition> {
+    let path = Path::new(file_path);
+    let server = server_for_file(path).await
+        .ok_or_else(|| anyhow::anyhow!("No running rust-analyzer for {}", file_path))?;
+    
+    let uri = Url::from_file_path(path)
+        .map_err(|_| anyhow::anyhow!("Cannot create URI from path: {}", file_path))?;
+    let params = TextDocumentPositionParams::new(TextDocumentIdentifier::new(uri), position);
+    
+    let (hover, definition) = tokio::join!(
+        server.send_request("textDocument/hover", Some(&params)),
+        server.send_request("textDocument/definition", Some(&params))
+    );
+    
+    Ok(HoverAndDefinition {
+        hover: optional_result("textDocument/hover", hover),
+        definition: optional_result("textDocument/definition", definition),
+    })
+}
+
+/**
+ * Fill in the lazily computed parts of a completion item, such as the
+ * `additionalTextEdits` of an auto-import, through `completionItem/resolve`
+ */
+pub async fn resolve_completion_item(file_path: &str, item: CompletionItem) -> Result<CompletionItem> {
+    let server = server_for_file(Path::new(file_path)).await
+        .ok_or_else(|| anyhow::anyhow!("No running rust-analyzer for {}", file_path))?;
+    
+    let response = server.send_request("completionItem/resolve", Some(&item)).await?;
+    
+    if let Some(error) = response.error {
+        return Err(anyhow::anyhow!("LSP error: {} (code: {})", error.message, error.code));
+    }
+    
+    match response.result {
+        None | Some(serde_json::Value::Null) => Ok(item),
+        Some(result) => Ok(serde_json::from_value(result)?),
+    }
+}
+
+/**
+ * Command rust-analyzer runs on save to produce diagnostics
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckOnSaveCommand {
+    Check,
+    Clippy,
+    /// Another cargo subcommand (e.g. `hack`), or a full command line that replaces
+    /// `cargo` entirely and must print diagnostics with `--message-format=json`
+    Custom(String),
+}
+
+impl CheckOnSaveCommand {
+    pub fn parse(command: &str) -> Result<Self> {
+        match command.trim() {
+            "" => Err(anyhow::anyhow!("Check-on-save command can't be empty")),
+            "check" => Ok(Self::Check),
+            "clippy" => Ok(Self::Clippy),
+            custom => Ok(Self::Custom(custom.to_string())),
+        }
+    }
+    
+    /**
+     * The `rust-analyzer` settings selecting this command
+     */
+    fn settings(&self) -> serde_json::Value {
+        let (command, override_command) = match self {
+            Self::Check => ("check", None),
+            Self::Clippy => ("clippy", None),
+            Self::Custom(custom) if !custom.contains(char::is_whitespace) => (custom.as_str(), None),
+            Self::Custom(custom) => ("check", Some(custom.split_whitespace().collect::<Vec<_>>())),
+        };
+        
+        serde_json::json!({
+            "checkOnSave": true,
+            "check": {
+                "command": command,
+                "overrideCommand": override_command,
+            },
+        })
+    }
+}
+
+/**
+ * Switch the check-on-save command of every running rust-analyzer without restarting it.
+ * rust-analyzer answers `workspace/didChangeConfiguration` by pulling its settings back
+ * through `workspace/configuration`, so the connection's settings are updated first.
+ * Returns the number of servers notified
+ */
+pub async fn set_check_on_save(command: &CheckOnSaveCommand) -> Result<usize> {
+    let slots: Vec<ConnectionSlot> = RUNNING_SERVERS.lock().unwrap()
+        .iter()
+        .filter_map(|(_, weak)| weak.connection.upgrade())
+        .collect();
+    
+    let mut notified = 0;
+    for slot in slots {
+        let connection = slot.lock().await.as_ref().cloned();
+        let Some(connection) = connection else {
+            continue;
+        };
+        
+        let mut settings = connection.settings("rust-analyzer");
+        if !settings.is_object() {
+            settings = serde_json::json!({});
+        }
+        merge_json(&mut settings, command.settings());
+        connection.set_settings("rust-analyzer", settings.clone());
+        
+        connection.send_notification("workspace/didChangeConfiguration", Some(serde_json::json!({
+            "settings": { "rust-analyzer": settings },
+        })))?;
+        notified += 1;
+    }
+    
+    logger::info("RustAnalyzer", &format!("Check on save set to {:?} for {} server(s)", command, notified));
+    Ok(notified)
+}
+
+/// Number of trailing stderr lines attached to a crash report
+const STDERR_TAIL_LINES: usize = 100;
+
+/// Substrings in rust-analyzer's stderr that mean the process panicked or died
+const CRASH_PATTERNS: &[&str] = &[
+    "panicked at",
+    "fatal runtime error",
+    "has overflowed its stack",
+    "Segmentation fault",
+];
+
+/**
+ * Payload of the `lsp://crashed` event
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerCrashEvent {
+    pub server: String,
+    pub root_path: String,
+    /// First stderr line that matched a crash pattern
+    pub reason: String,
+    /// Last stderr lines before the process exited, oldest first
+    pub stderr_tail: Vec<String>,
+}
+
+/**
+ * Drain rust-analyzer's stderr on a background thread. Nothing else reads the pipe,
+ * and a full pipe buffer blocks the server on its next write.
+ *
+ * Every line is forwarded to the logger and the last `STDERR_TAIL_LINES` are kept.
+ * rust-analyzer catches panics in request handlers and keeps running, so a crash
+ * pattern alone is not a crash: `lsp://crashed` is only emitted when the stream closes
+ * because the process exited on its own with a failure status. A panic is forgotten
+ * once the server answers another request
+ */
+fn spawn_stderr_reader(
+    stderr: ChildStderr,
+    root_path: PathBuf,
+    process: Arc<StdMutex<Option<Child>>>,
+    responses_received: Arc<AtomicU64>,
+) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stderr);
+        let mut buffer = Vec::new();
+        let mut tail: VecDeque<String> = VecDeque::with_capacity(STDERR_TAIL_LINES);
+        // Panic line, with the response count at the time it was printed
+        let mut crash_reason: Option<(String, u64)> = None;
+    
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buffer).trim_end().to_string();
+                    let responses = responses_received.load(Ordering::SeqCst);
+    
+                    if crash_reason.as_ref().is_some_and(|(_, at)| responses > *at) {
+                        logger::info("RustAnalyzer", "rust-analyzer recovered from a panic");
+                        crash_reason = None;
+                    }
+    
+                    if crash_reason.is_none() && CRASH_PATTERNS.iter().any(|pattern| line.contains(pattern)) {
+                        logger::error("RustAnalyzer", &format!("stderr: {}", line));
+                        crash_reason = Some((line.clone(), responses));
+                    } else {
+                        logger::debug("RustAnalyzer", &format!("stderr: {}", line));
+                    }
+    
+                    if tail.len() == STDERR_TAIL_LINES {
+                        tail.pop_front();
+                    }
+                    tail.push_back(line);
+                },
+                Err(e) => {
+                    logger::warn("RustAnalyzer", &format!("Failed to read stderr: {}", e));
+                    break;
+                }
+            }
+        }
+    
+        logger::info("RustAnalyzer", "rust-analyzer stderr closed");
+    
+        let Some(status) = wait_for_unexpected_exit(&process) else {
+            return;
+        };
+        
+        let reason = match crash_reason {
+            Some((line, at)) if responses_received.load(Ordering::SeqCst) == at => line,
+            _ => format!("rust-analyzer exited unexpectedly ({})", status),
+        };
+        
+        crate::lsp::emit_event("lsp://crashed", ServerCrashEvent {
+            server: "rust-analyzer".to_string(),
+            root_path: root_path.to_string_lossy().to_string(),
+            reason,
+            stderr_tail: tail.into_iter().collect(),
+        });
+    });
+}
+
+/// How long to wait for rust-analyzer to exit once its stderr has closed
+const EXIT_WAIT: Duration = Duration::from_secs(2);
+
+/**
+ * Exit status of the rust-analyzer process in `process`, if it exited on its own with a
+ * failure status. `None` when `shutdown` took the process out of the slot (an intentional
+ * stop), when it exited successfully, or when it's still running after `EXIT_WAIT`
+ */
+fn wait_for_unexpected_exit(process: &StdMutex<Option<Child>>) -> Option<ExitStatus> {
+    let deadline = Instant::now() + EXIT_WAIT;
+    
+    loop {
+        match process.lock().unwrap().as_mut().map(|child| child.try_wait()) {
+            None => return None,
+            Some(Ok(Some(status))) => return (!status.success()).then_some(status),
+            Some(Ok(None)) => {},
+            Some(Err(e)) => {
+                logger::warn("RustAnalyzer", &format!("Failed to check rust-analyzer exit status: {}", e));
+                return None;
+            }
+        }
+        
+        if Instant::now() >= deadline {
+            return None;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/**
+ * Toolchains chosen by the user, keyed by project root. Kept here rather than on a
+ * server so every Rust server created for the project uses it, including the
+ * ones the websocket factory starts
+ */
+static TOOLCHAIN_OVERRIDES: Lazy<DashMap<PathBuf, String>> = Lazy::new(DashMap::new);
+
+/**
+ * Run rust-analyzer (and the cargo commands it spawns) for servers created for
+ * `root_path` from now on with a specific toolchain, taking precedence over the
+ * project's toolchain file
+ */
+pub fn set_toolchain_override(root_path: &Path, toolchain: &str) {
+    logger::info("RustAnalyzer", &format!("Using toolchain {} for {}", toolchain, root_path.display()));
+    TOOLCHAIN_OVERRIDES.insert(root_path.to_path_buf(), toolchain.to_string());
+}
+
+/// Files pinning a project's toolchain, in the order rustup prefers them
+const TOOLCHAIN_FILES: &[&str] = &["rust-toolchain", "rust-toolchain.toml"];
+
+/**
+ * Toolchain pinned by a `rust-toolchain` or `rust-toolchain.toml` file in `root_path`.
+ * Both the TOML form (`[toolchain] channel = "nightly"`) and the legacy
+ * single-line form holding only the channel name are understood
+ */
+pub fn detect_toolchain(root_path: &Path) -> Option<String> {
+    for file_name in TOOLCHAIN_FILES {
+        let Ok(content) = std::fs::read_to_string(root_path.join(file_name)) else {
+            continue;
+        };
+        
+        let mut lines = content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+        
+        let channel = lines.clone()
+            .find_map(|line| {
+                let value = line.strip_prefix("channel")?.trim_start().strip_prefix('=')?.trim_start();
+                let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+                value[1..].split(quote).next()
+            })
+            .or_else(|| lines.next().filter(|line| !line.starts_with('[') && !line.contains('=')));
+        
+        // rustup only reads the first file it finds, even when it names no channel (e.g. a `path` toolchain)
+        let channel = channel.filter(|channel| !channel.is_empty())?;
+        logger::info("RustAnalyzer", &format!("Using toolchain {} from {}", channel, file_name));
+        return Some(channel.to_string());
+    }
+    
+    None
 }
 
 #[derive(Clone)]
@@ -35,9 +703,14 @@ pub struct RustLanguageServer {
     rust_analyzer_process: Arc<StdMutex<Option<Child>>>,
     document_states: Arc<DashMap<String, String>>,
     is_initialized: Arc<StdMutex<bool>>,
-    lsp_connection: Arc<Mutex<Option<LspProcessConnection>>>,
-    document_data: Arc<RwLock<DashMap<String, DocumentData>>>,
+    lsp_connection: ConnectionSlot,
+    document_data: DocumentStore,
+    diagnostic_session: Arc<DiagnosticSession>,
     notification_tx: Arc<StdMutex<Option<UnboundedSender<JsonRpcNotification>>>>,
+    pending_changes: PendingChanges,
+    change_flush_lock: Arc<Mutex<()>>,
+    watched_globs: Arc<StdMutex<HashMap<String, Vec<WatchedGlob>>>>,
+    file_watcher: Arc<StdMutex<Option<WorkspaceFileWatcher>>>,
 }
 
 impl LSPUtils for RustLanguageServer {}
@@ -72,7 +745,7 @@ impl BaseLanguageServer for RustLanguageServer {
             command.env(key, value);
         }
         
-        println!("Starting rust-analyzer process in root directory: {:?}", self.config.root_path);
+        logger::info("RustAnalyzer", &format!("Starting rust-analyzer process in root directory: {:?}", self.config.root_path));
         
         command.current_dir(&self.config.root_path);
         
@@ -82,12 +755,15 @@ impl BaseLanguageServer for RustLanguageServer {
             .stderr(Stdio::piped())
             .spawn()?;
         
+        let stderr = process.stderr.take();
         let connection = LspProcessConnection::new(&mut process)?;
         
         let (notification_tx, notification_rx) = mpsc::unbounded_channel();
+        connection.set_notification_sender(notification_tx.clone());
         *self.notification_tx.lock().unwrap() = Some(notification_tx);
         
         self.start_notification_handling(notification_rx);
+        let responses_received = connection.responses_received();
         
         tokio::task::block_in_place(|| {
             let rt = tokio::runtime::Handle::current();
@@ -96,9 +772,14 @@ impl BaseLanguageServer for RustLanguageServer {
             });
         });
         *self.rust_analyzer_process.lock().unwrap() = Some(process);
+        
+        // Started only once the process is in its slot: an empty slot means it was shut down
+        if let Some(stderr) = stderr {
+            spawn_stderr_reader(stderr, self.config.root_path.clone(), Arc::clone(&self.rust_analyzer_process), responses_received);
+        }
         *self.is_initialized.lock().unwrap() = true;
         
-        println!("Successfully started rust-analyzer process");
+        logger::info("RustAnalyzer", "Successfully started rust-analyzer process");
         
         Ok(())
     }
@@ -126,6 +807,8 @@ impl BaseLanguageServer for RustLanguageServer {
             });
             
             *self.notification_tx.lock().unwrap() = None;
+            *self.file_watcher.lock().unwrap() = None;
+            self.watched_globs.lock().unwrap().clear();
         }
         
         Ok(())
@@ -137,12 +820,33 @@ impl BaseLanguageServer for RustLanguageServer {
 }
 
 impl RustLanguageServer {
-    pub fn new(root_path: String) -> Result<Self> {
-        let config = ServerConfig::new(&root_path)?
+    /**
+     * Built-in configuration with the project's `.horizon/lsp.json` applied on top.
+     * A toolchain pinned by the project is passed on through `RUSTUP_TOOLCHAIN`,
+     * unless one was chosen through `set_toolchain_override`
+     */
+    pub fn default_config(root_path: &str) -> Result<ServerConfig> {
+        let mut config = ServerConfig::new(root_path)?
             .with_executable("rust-analyzer")
             .with_env_var("RUST_BACKTRACE", "1");
         
-        Ok(Self {
+        if let Some(toolchain) = detect_toolchain(Path::new(root_path)) {
+            config = config.with_env_var("RUSTUP_TOOLCHAIN", &toolchain);
+        }
+        
+        config = config.with_project_settings("rust");
+        
+        if let Some(toolchain) = TOOLCHAIN_OVERRIDES.get(Path::new(root_path)) {
+            config = config.with_env_var("RUSTUP_TOOLCHAIN", toolchain.value());
+        }
+        
+        Ok(config)
+    }
+    
+    pub fn new(root_path: String) -> Result<Self> {
+        let config = Self::default_config(&root_path)?;
+        
+        let server = Self {
             client: None,
             config,
             rust_analyzer_process: Arc::new(StdMutex::new(None)),
@@ -150,11 +854,20 @@ impl RustLanguageServer {
             is_initialized: Arc::new(StdMutex::new(false)),
             lsp_connection: Arc::new(Mutex::new(None)),
             document_data: Arc::new(RwLock::new(DashMap::new())),
+            diagnostic_session: DiagnosticSession::register(),
             notification_tx: Arc::new(StdMutex::new(None)),
-        })
+            pending_changes: Arc::new(DashMap::new()),
+            change_flush_lock: Arc::new(Mutex::new(())),
+            watched_globs: Arc::new(StdMutex::new(HashMap::new())),
+            file_watcher: Arc::new(StdMutex::new(None)),
+        };
+        register_server(&server.config.root_path, &server.handle());
+        
+        Ok(server)
     }
     
     pub fn with_client(mut self, client: Client) -> Self {
+        self.diagnostic_session.set_client(client.clone());
         self.client = Some(client);
         self
     }
@@ -178,44 +891,102 @@ impl RustLanguageServer {
                     }
                 }
             },
+            "client/registerCapability" => {
+                if let Some(params) = notification.params {
+                    if let Ok(registration_params) = serde_json::from_value::<RegistrationParams>(params) {
+                        self.handle_register_capability(registration_params);
+                    }
+                }
+            },
+            "client/unregisterCapability" => {
+                if let Some(params) = notification.params {
+                    if let Ok(unregistration_params) = serde_json::from_value::<UnregistrationParams>(params) {
+                        let mut watched_globs = self.watched_globs.lock().unwrap();
+                        for unregistration in unregistration_params.unregisterations {
+                            watched_globs.remove(&unregistration.id);
+                        }
+                    }
+                }
+            },
             _ => {
-                println!("Received unhandled notification: {}", notification.method);
+                logger::debug("RustAnalyzer", &format!("Received unhandled notification: {}", notification.method));
             }
         }
     }
     
-    async fn handle_diagnostics(&self, params: PublishDiagnosticsParams) {
-        let uri = params.uri.to_string();
-        let diagnostics = params.diagnostics.clone();
-        
-        let document_data = self.document_data.write().await;
+    fn handle_register_capability(&self, params: RegistrationParams) {
+        for registration in params.registrations {
+            if registration.method != "workspace/didChangeWatchedFiles" {
+                continue;
+            }
+            
+            let options = registration.register_options
+                .and_then(|options| serde_json::from_value::<DidChangeWatchedFilesRegistrationOptions>(options).ok());
+            
+            if let Some(options) = options {
+                logger::info("RustAnalyzer", &format!("Registered {} file watcher glob(s)", options.watchers.len()));
+                self.watched_globs.lock().unwrap()
+                    .insert(registration.id, WatchedGlob::from_watchers(&options.watchers));
+                self.ensure_file_watcher();
+            }
+        }
+    }
+    
+    fn ensure_file_watcher(&self) {
+        let mut file_watcher = self.file_watcher.lock().unwrap();
+        if file_watcher.is_some() {
+            return;
+        }
         
-        if !document_data.contains_key(&uri) {
-            document_data.insert(uri.clone(), DocumentData {
-                content: String::new(),
-                diagnostics: diagnostics.clone(),
-            });
-        } else {
-            if let Some(mut data_ref) = document_data.get_mut(&uri) {
-                data_ref.diagnostics = diagnostics.clone();
+        match WorkspaceFileWatcher::start(&self.config.root_path, self.config.file_watch_debounce) {
+            Ok((watcher, mut batches)) => {
+                *file_watcher = Some(watcher);
+                
+                let server = self.clone();
+                tokio::spawn(async move {
+                    while let Some(batch) = batches.recv().await {
+                        server.send_watched_file_changes(batch).await;
+                    }
+                });
+            },
+            Err(e) => {
+                logger::error("RustAnalyzer", &format!("Failed to start file watcher: {}", e));
             }
         }
+    }
+    
+    async fn send_watched_file_changes(&self, batch: FileChangeBatch) {
+        let changes: Vec<FileEvent> = {
+            let watched_globs = self.watched_globs.lock().unwrap();
+            
+            batch.into_iter()
+                .filter(|(path, change)| watched_globs.values().flatten().any(|glob| glob.matches(path, *change)))
+                .filter_map(|(path, change)| Url::from_file_path(&path).ok().map(|uri| FileEvent::new(uri, change)))
+                .collect()
+        };
         
-        if let Some(client) = &self.client {
-            client.publish_diagnostics(params.uri, params.diagnostics, params.version).await;
+        if changes.is_empty() {
+            return;
         }
         
-        println!("Received {} diagnostics for {}", diagnostics.len(), uri);
+        logger::debug("RustAnalyzer", &format!("Sending {} watched file change(s) to rust-analyzer", changes.len()));
+        
+        if let Err(e) = self.send_notification("workspace/didChangeWatchedFiles", DidChangeWatchedFilesParams { changes }).await {
+            logger::error("RustAnalyzer", &format!("Failed to send didChangeWatchedFiles notification: {}", e));
+        }
     }
     
-    async fn send_request<T: serde::Serialize>(&self, method: &str, params: T) -> Result<serde_json::Value> {
-        let connection = {
-            let guard = self.lsp_connection.lock().await;
-            guard.as_ref().cloned()
-                .ok_or_else(|| anyhow::anyhow!("No connection to rust-analyzer"))?
-        };
+    async fn handle_diagnostics(&self, params: PublishDiagnosticsParams) {
+        let uri = params.uri.to_string();
+        
+        // The session caches everything; only what passes its filter is forwarded
+        let (received, forwarded) = self.diagnostic_session.publish(params).await;
         
-        let response = connection.send_request(method, Some(params)).await?;
+        logger::debug("RustAnalyzer", &format!("Received {} diagnostics for {}, forwarding {}", received, uri, forwarded));
+    }
+    
+    async fn send_request<T: serde::Serialize>(&self, method: &str, params: T) -> Result<serde_json::Value> {
+        let response = self.handle().send_request(method, Some(params)).await?;
         
         if let Some(error) = response.error {
             Err(anyhow::anyhow!("LSP error: {} (code: {})", error.message, error.code))
@@ -235,11 +1006,58 @@ impl RustLanguageServer {
         
         connection.send_notification(method, Some(params))
     }
+    
+    /**
+     * Queue a didChange, merging it with changes already waiting for the same document.
+     * Content changes are concatenated in order, so incremental edits stay valid.
+     */
+    async fn queue_did_change(&self, params: DidChangeTextDocumentParams) {
+        let window = self.config.did_change_debounce;
+        
+        if window.is_zero() {
+            if let Err(e) = self.send_notification("textDocument/didChange", params).await {
+                logger::error("RustAnalyzer", &format!("Failed to send didChange notification: {}", e));
+            }
+            return;
+        }
+        
+        let uri = params.text_document.uri.to_string();
+        
+        match self.pending_changes.entry(uri.clone()) {
+            dashmap::mapref::entry::Entry::Occupied(mut entry) => {
+                let pending = entry.get_mut();
+                pending.text_document.version = params.text_document.version;
+                pending.content_changes.extend(params.content_changes);
+            },
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                entry.insert(params);
+                
+                let server = self.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(window).await;
+                    server.flush_pending_change(&uri).await;
+                });
+            }
+        }
+    }
+    
+    fn handle(&self) -> ServerHandle {
+        ServerHandle {
+            connection: self.lsp_connection.clone(),
+            pending_changes: self.pending_changes.clone(),
+            change_flush_lock: self.change_flush_lock.clone(),
+            request_timeout: self.config.request_timeout,
+        }
+    }
+    
+    async fn flush_pending_change(&self, uri: &str) {
+        self.handle().flush_pending_change(uri).await;
+    }
 }
 
 #[async_trait]
 impl LanguageServer for RustLanguageServer {
-    async fn initialize(&self, params: InitializeParams) -> LspResult<InitializeResult> {
+    async fn initialize(&self, mut params: InitializeParams) -> LspResult<InitializeResult> {
         if let Err(e) = <Self as BaseLanguageServer>::initialize(self) {
             if let Some(client) = &self.client {
                 let message = format!("Failed to initialize Rust Analyzer process: {}", e);
@@ -248,12 +1066,26 @@ impl LanguageServer for RustLanguageServer {
             return Err(tower_lsp::jsonrpc::Error::internal_error());
         }
         
+        params.initialization_options = self.config.apply_initialization_options(params.initialization_options.take());
+        
+        // Later configuration changes are served from these, so a reload doesn't drop the startup settings
+        if let Some(connection) = self.lsp_connection.lock().await.as_ref() {
+            connection.set_settings("rust-analyzer", params.initialization_options.clone().unwrap_or_else(|| serde_json::json!({})));
+        }
+        
+        // We watch the workspace ourselves, so let rust-analyzer register the globs it cares about
+        params.capabilities.workspace.get_or_insert_with(Default::default).did_change_watched_files =
+            Some(DidChangeWatchedFilesClientCapabilities {
+                dynamic_registration: Some(true),
+                relative_pattern_support: Some(true),
+            });
+        
         match self.send_request("initialize", params).await {
             Ok(result) => {
                 match serde_json::from_value::<InitializeResult>(result) {
                     Ok(initialize_result) => Ok(initialize_result),
                     Err(e) => {
-                        println!("Failed to parse initialize response: {}", e);
+                        logger::error("RustAnalyzer", &format!("Failed to parse initialize response: {}", e));
                         
                         Ok(InitializeResult {
                             capabilities: ServerCapabilities {
@@ -321,7 +1153,7 @@ impl LanguageServer for RustLanguageServer {
                 }
             },
             Err(e) => {
-                println!("Failed to send initialize request: {}", e);
+                logger::error("RustAnalyzer", &format!("Failed to send initialize request: {}", e));
                 Err(tower_lsp::jsonrpc::Error::internal_error())
             }
         }
@@ -329,7 +1161,7 @@ impl LanguageServer for RustLanguageServer {
 
     async fn initialized(&self, params: InitializedParams) {
         if let Err(e) = self.send_notification("initialized", params).await {
-            println!("Failed to send initialized notification: {}", e);
+            logger::error("RustAnalyzer", &format!("Failed to send initialized notification: {}", e));
         }
     }
 
@@ -339,7 +1171,7 @@ impl LanguageServer for RustLanguageServer {
                 let message = format!("Failed to shut down Rust Analyzer: {}", e);
                 client.log_message(MessageType::ERROR, message).await;
             } else {
-                eprintln!("Failed to shut down Rust Analyzer: {}", e);
+                logger::error("RustAnalyzer", &format!("Failed to shut down Rust Analyzer: {}", e));
             }
         }
         Ok(())
@@ -353,29 +1185,28 @@ impl LanguageServer for RustLanguageServer {
             let document_data = self.document_data.write().await;
             document_data.insert(uri.clone(), DocumentData {
                 content: text.clone(),
-                diagnostics: Vec::new(),
             });
         }
         
         self.document_states.insert(uri, text);
         
         if let Err(e) = self.send_notification("textDocument/didOpen", params).await {
-            println!("Failed to send didOpen notification: {}", e);
+            logger::error("RustAnalyzer", &format!("Failed to send didOpen notification: {}", e));
         }
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         let uri = params.text_document.uri.to_string();
         
-        println!("Received didChange for document: {}", uri);
+        logger::debug("RustAnalyzer", &format!("Received didChange for document: {}", uri));
         
         if !params.content_changes.is_empty() {
             let last_change = &params.content_changes[params.content_changes.len() - 1];
             let new_text = last_change.text.clone();
             let text_length = new_text.len();
             
-            println!("Document changes: {} items, last change size: {} bytes", 
-                   params.content_changes.len(), text_length);
+            logger::debug("RustAnalyzer", &format!("Document changes: {} items, last change size: {} bytes", 
+                   params.content_changes.len(), text_length));
             
             {
                 let document_data = self.document_data.write().await;
@@ -385,54 +1216,61 @@ impl LanguageServer for RustLanguageServer {
                 if document_data.contains_key(&uri) {
                     if let Some(mut data) = document_data.get_mut(&uri) {
                         data.content = new_content;
-                        println!("Updated existing document content in memory cache");
+                        logger::debug("RustAnalyzer", "Updated existing document content in memory cache");
                     }
                 } else {
                     document_data.insert(uri.clone(), DocumentData {
                         content: new_content,
-                        diagnostics: Vec::new(),
-                    });
-                    println!("Created new document entry in memory cache");
+                            });
+                    logger::debug("RustAnalyzer", "Created new document entry in memory cache");
                 }
             }
             
             if let Some(mut content) = self.document_states.get_mut(&uri) {
                 *content = new_text.clone();
-                println!("Updated document in document states collection");
+                logger::debug("RustAnalyzer", "Updated document in document states collection");
             } else {
                 self.document_states.insert(uri.clone(), new_text);
-                println!("Added document to document states collection");
+                logger::debug("RustAnalyzer", "Added document to document states collection");
             }
         } else {
-            println!("Warning: Received didChange with empty content changes");
+            logger::warn("RustAnalyzer", "Received didChange with empty content changes");
         }
         
-        println!("Sending didChange notification to rust-analyzer");
-        if let Err(e) = self.send_notification("textDocument/didChange", params).await {
-            println!("Failed to send didChange notification: {}", e);
-        } else {
-            println!("Successfully sent didChange notification to rust-analyzer");
-        }
+        self.queue_did_change(params).await;
     }
 
     async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        self.flush_pending_change(params.text_document.uri.as_str()).await;
+        
         if let Err(e) = self.send_notification("textDocument/didSave", params).await {
-            println!("Failed to send didSave notification: {}", e);
+            logger::error("RustAnalyzer", &format!("Failed to send didSave notification: {}", e));
         }
     }
 
     async fn did_close(&self, params: DidCloseTextDocumentParams) {
         let uri = params.text_document.uri.to_string();
         
+        // Publish an empty set first so subscribers drop markers for the closed document;
+        // reopening starts from a fresh entry that the server repopulates
+        self.handle_diagnostics(PublishDiagnosticsParams::new(params.text_document.uri.clone(), Vec::new(), None)).await;
+        
         {
             let document_data = self.document_data.write().await;
             document_data.remove(&uri);
         }
         
         self.document_states.remove(&uri);
+        self.flush_pending_change(&uri).await;
         
         if let Err(e) = self.send_notification("textDocument/didClose", params).await {
-            println!("Failed to send didClose notification: {}", e);
+            logger::error("RustAnalyzer", &format!("Failed to send didClose notification: {}", e));
+        }
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        if let Err(e) = self.send_notification("workspace/didChangeWorkspaceFolders", params).await {
+            logger::error("RustAnalyzer", &format!("Failed to send didChangeWorkspaceFolders notification: {}", e));
         }
     }
 
@@ -442,13 +1280,13 @@ impl LanguageServer for RustLanguageServer {
                 match serde_json::from_value::<CompletionResponse>(result) {
                     Ok(completion_response) => Ok(Some(completion_response)),
                     Err(e) => {
-                        println!("Failed to parse completion response: {}", e);
+                        logger::error("RustAnalyzer", &format!("Failed to parse completion response: {}", e));
                         Ok(None)
                     }
                 }
             },
             Err(e) => {
-                println!("Failed to send completion request: {}", e);
+                logger::error("RustAnalyzer", &format!("Failed to send completion request: {}", e));
                 Ok(None)
             }
         }
@@ -464,13 +1302,13 @@ impl LanguageServer for RustLanguageServer {
                 match serde_json::from_value::<Hover>(result) {
                     Ok(hover) => Ok(Some(hover)),
                     Err(e) => {
-                        println!("Failed to parse hover response: {}", e);
+                        logger::error("RustAnalyzer", &format!("Failed to parse hover response: {}", e));
                         Ok(None)
                     }
                 }
             },
             Err(e) => {
-                println!("Failed to send hover request: {}", e);
+                logger::error("RustAnalyzer", &format!("Failed to send hover request: {}", e));
                 Ok(None)
             }
         }
@@ -486,18 +1324,81 @@ impl LanguageServer for RustLanguageServer {
                 match serde_json::from_value::<GotoDefinitionResponse>(result) {
                     Ok(definition) => Ok(Some(definition)),
                     Err(e) => {
-                        println!("Failed to parse definition response: {}", e);
+                        logger::error("RustAnalyzer", &format!("Failed to parse definition response: {}", e));
                         Ok(None)
                     }
                 }
             },
             Err(e) => {
-                println!("Failed to send definition request: {}", e);
+                logger::error("RustAnalyzer", &format!("Failed to send definition request: {}", e));
                 Ok(None)
             }
         }
     }
 
+    async fn document_highlight(&self, params: DocumentHighlightParams) -> LspResult<Option<Vec<DocumentHighlight>>> {
+        match self.send_request("textDocument/documentHighlight", params).await {
+            Ok(result) => {
+                if result.is_null() {
+                    return Ok(Some(Vec::new()));
+                }
+                
+                match serde_json::from_value::<Vec<DocumentHighlight>>(result) {
+                    Ok(highlights) => Ok(Some(highlights)),
+                    Err(e) => {
+                        logger::error("RustAnalyzer", &format!("Failed to parse documentHighlight response: {}", e));
+                        Ok(Some(Vec::new()))
+                    }
+                }
+            },
+            Err(e) => {
+                logger::error("RustAnalyzer", &format!("Failed to send documentHighlight request: {}", e));
+                Ok(Some(Vec::new()))
+            }
+        }
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> LspResult<Option<Vec<CodeLens>>> {
+        match self.send_request("textDocument/codeLens", params).await {
+            Ok(result) => {
+                if result.is_null() {
+                    return Ok(None);
+                }
+                
+                match serde_json::from_value::<Vec<CodeLens>>(result) {
+                    Ok(lenses) => Ok(Some(lenses)),
+                    Err(e) => {
+                        logger::error("RustAnalyzer", &format!("Failed to parse codeLens response: {}", e));
+                        Ok(None)
+                    }
+                }
+            },
+            Err(e) => {
+                logger::error("RustAnalyzer", &format!("Failed to send codeLens request: {}", e));
+                Ok(None)
+            }
+        }
+    }
+
+    async fn code_lens_resolve(&self, params: CodeLens) -> LspResult<CodeLens> {
+        match self.send_request("codeLens/resolve", params.clone()).await {
+            Ok(result) => {
+                match serde_json::from_value::<CodeLens>(result) {
+                    Ok(lens) => Ok(lens),
+                    Err(e) => {
+                        logger::error("RustAnalyzer", &format!("Failed to parse codeLens/resolve response: {}", e));
+                        Ok(params)
+                    }
+                }
+            },
+            Err(e) => {
+                // An unresolved lens is still displayable, just without its command
+                logger::error("RustAnalyzer", &format!("Failed to send codeLens/resolve request: {}", e));
+                Ok(params)
+            }
+        }
+    }
+
     async fn references(&self, params: ReferenceParams) -> LspResult<Option<Vec<Location>>> {
         match self.send_request("textDocument/references", params).await {
             Ok(result) => {
@@ -508,13 +1409,13 @@ impl LanguageServer for RustLanguageServer {
                 match serde_json::from_value::<Vec<Location>>(result) {
                     Ok(locations) => Ok(Some(locations)),
                     Err(e) => {
-                        println!("Failed to parse references response: {}", e);
+                        logger::error("RustAnalyzer", &format!("Failed to parse references response: {}", e));
                         Ok(None)
                     }
                 }
             },
             Err(e) => {
-                println!("Failed to send references request: {}", e);
+                logger::error("RustAnalyzer", &format!("Failed to send references request: {}", e));
                 Ok(None)
             }
         }
@@ -530,13 +1431,13 @@ impl LanguageServer for RustLanguageServer {
                 match serde_json::from_value::<Vec<TextEdit>>(result) {
                     Ok(edits) => Ok(Some(edits)),
                     Err(e) => {
-                        println!("Failed to parse formatting response: {}", e);
+                        logger::error("RustAnalyzer", &format!("Failed to parse formatting response: {}", e));
                         Ok(None)
                     }
                 }
             },
             Err(e) => {
-                println!("Failed to send formatting request: {}", e);
+                logger::error("RustAnalyzer", &format!("Failed to send formatting request: {}", e));
                 Ok(None)
             }
         }
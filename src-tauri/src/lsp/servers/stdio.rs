@@ -0,0 +1,366 @@
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio, Child, ChildStderr};
+use anyhow::Result;
+use tower_lsp::jsonrpc::Result as LspResult;
+use tower_lsp::{LanguageServer, Client};
+use tower_lsp::lsp_types::{
+    InitializeParams, InitializeResult, InitializedParams, MessageType, DidOpenTextDocumentParams,
+    DidChangeTextDocumentParams, DidSaveTextDocumentParams, DidCloseTextDocumentParams, CompletionParams,
+    CompletionResponse, HoverParams, Hover, GotoDefinitionParams, GotoDefinitionResponse, ReferenceParams,
+    Location, DocumentFormattingParams, TextEdit, DidChangeWorkspaceFoldersParams, PublishDiagnosticsParams,
+    DocumentHighlightParams, DocumentHighlight
+};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::lsp::config::ServerConfig;
+use crate::lsp::protocol::{LSPUtils, LspProcessConnection, JsonRpcNotification};
+use crate::lsp::servers::BaseLanguageServer;
+use crate::lsp::logger;
+use crate::lsp::diagnostics::DiagnosticSession;
+
+/**
+ * Static description of an external language server that speaks LSP over stdio
+ */
+pub trait StdioServerSpec: Clone + Send + Sync + 'static {
+    const ID: &'static str;
+    /// Language key of the server's section in `.horizon/lsp.json`
+    const LANGUAGE: &'static str;
+    const NAME: &'static str;
+    const EXECUTABLE: &'static str;
+    const ARGS: &'static [&'static str];
+}
+
+/**
+ * Forward the server's stderr to the logger on a background thread. Nothing else
+ * reads the pipe, and a full pipe buffer blocks the server on its next write
+ */
+fn spawn_stderr_logger<S: StdioServerSpec>(stderr: ChildStderr) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(stderr);
+        let mut buffer = Vec::new();
+
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer) {
+                Ok(0) => break,
+                Ok(_) => logger::debug(S::NAME, &format!("stderr: {}", String::from_utf8_lossy(&buffer).trim_end())),
+                Err(e) => {
+                    logger::warn(S::NAME, &format!("Failed to read stderr: {}", e));
+                    break;
+                }
+            }
+        }
+
+        logger::info(S::NAME, &format!("{} stderr closed", S::ID));
+    });
+}
+
+/**
+ * Language server that forwards every request to an external process without
+ * keeping any document state of its own
+ */
+#[derive(Clone)]
+pub struct StdioLanguageServer<S: StdioServerSpec> {
+    client: Option<Client>,
+    config: ServerConfig,
+    process: Arc<StdMutex<Option<Child>>>,
+    is_initialized: Arc<StdMutex<bool>>,
+    lsp_connection: Arc<Mutex<Option<LspProcessConnection>>>,
+    diagnostic_session: Arc<DiagnosticSession>,
+    spec: PhantomData<S>,
+}
+
+impl<S: StdioServerSpec> LSPUtils for StdioLanguageServer<S> {}
+
+impl<S: StdioServerSpec> BaseLanguageServer for StdioLanguageServer<S> {
+    fn id(&self) -> &str {
+        S::ID
+    }
+
+    fn name(&self) -> &str {
+        S::NAME
+    }
+
+    fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    fn initialize(&self) -> Result<()> {
+        let exec_path = self.config.executable_path.clone()
+            .unwrap_or_else(|| S::EXECUTABLE.into());
+
+        let mut command = Command::new(exec_path);
+
+        for arg in &self.config.additional_args {
+            command.arg(arg);
+        }
+
+        for (key, value) in &self.config.env_vars {
+            command.env(key, value);
+        }
+
+        logger::info(S::NAME, &format!("Starting {} process in root directory: {:?}", S::ID, self.config.root_path));
+
+        command.current_dir(&self.config.root_path);
+
+        let mut process = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        if let Some(stderr) = process.stderr.take() {
+            spawn_stderr_logger::<S>(stderr);
+        }
+
+        let connection = LspProcessConnection::new(&mut process)?;
+
+        let (notification_tx, notification_rx) = mpsc::unbounded_channel();
+        connection.set_notification_sender(notification_tx);
+        self.start_notification_handling(notification_rx);
+
+        tokio::task::block_in_place(|| {
+            let rt = tokio::runtime::Handle::current();
+            rt.block_on(async {
+                *self.lsp_connection.lock().await = Some(connection);
+            });
+        });
+        *self.process.lock().unwrap() = Some(process);
+        *self.is_initialized.lock().unwrap() = true;
+
+        logger::info(S::NAME, &format!("Successfully started {} process", S::ID));
+
+        Ok(())
+    }
+
+    fn shutdown(&self) -> Result<()> {
+        if let Some(mut process) = self.process.lock().unwrap().take() {
+            tokio::task::block_in_place(|| {
+                let rt = tokio::runtime::Handle::current();
+                rt.block_on(async {
+                    if let Some(connection) = self.lsp_connection.lock().await.as_ref() {
+                        let _ = connection.send_notification::<()>("shutdown", None);
+                        let _ = connection.send_notification::<()>("exit", None);
+                    }
+                });
+            });
+
+            process.kill()?;
+            *self.is_initialized.lock().unwrap() = false;
+
+            tokio::task::block_in_place(|| {
+                let rt = tokio::runtime::Handle::current();
+                rt.block_on(async {
+                    *self.lsp_connection.lock().await = None;
+                });
+            });
+        }
+
+        Ok(())
+    }
+
+    fn is_running(&self) -> bool {
+        *self.is_initialized.lock().unwrap()
+    }
+}
+
+impl<S: StdioServerSpec> StdioLanguageServer<S> {
+    /**
+     * Built-in configuration with the project's `.horizon/lsp.json` applied on top
+     */
+    pub fn default_config(root_path: &str) -> Result<ServerConfig> {
+        let mut config = ServerConfig::new(root_path)?
+            .with_executable(S::EXECUTABLE);
+
+        for arg in S::ARGS {
+            config = config.with_arg(arg);
+        }
+
+        Ok(config.with_project_settings(S::LANGUAGE))
+    }
+
+    pub fn new(root_path: String) -> Result<Self> {
+        let config = Self::default_config(&root_path)?;
+
+        Ok(Self {
+            client: None,
+            config,
+            process: Arc::new(StdMutex::new(None)),
+            is_initialized: Arc::new(StdMutex::new(false)),
+            lsp_connection: Arc::new(Mutex::new(None)),
+            diagnostic_session: DiagnosticSession::register(),
+            spec: PhantomData,
+        })
+    }
+
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.diagnostic_session.set_client(client.clone());
+        self.client = Some(client);
+        self
+    }
+
+    fn start_notification_handling(&self, mut rx: UnboundedReceiver<JsonRpcNotification>) {
+        let diagnostic_session = self.diagnostic_session.clone();
+
+        tokio::spawn(async move {
+            while let Some(notification) = rx.recv().await {
+                match notification.method.as_str() {
+                    "textDocument/publishDiagnostics" => {
+                        let params = notification.params
+                            .and_then(|params| serde_json::from_value::<PublishDiagnosticsParams>(params).ok());
+
+                        if let Some(params) = params {
+                            diagnostic_session.publish(params).await;
+                        }
+                    },
+                    _ => {
+                        logger::debug(S::NAME, &format!("Received unhandled notification: {}", notification.method));
+                    }
+                }
+            }
+        });
+    }
+
+    async fn send_request<T: serde::Serialize>(&self, method: &str, params: T) -> Result<serde_json::Value> {
+        let connection = {
+            let guard = self.lsp_connection.lock().await;
+            guard.as_ref().cloned()
+                .ok_or_else(|| anyhow::anyhow!("No connection to {}", S::ID))?
+        };
+
+        let response = connection.send_request_with_timeout(method, Some(params), self.config.request_timeout).await?;
+
+        if let Some(error) = response.error {
+            Err(anyhow::anyhow!("LSP error: {} (code: {})", error.message, error.code))
+        } else {
+            Ok(response.result.unwrap_or(serde_json::Value::Null))
+        }
+    }
+
+    async fn send_notification<T: serde::Serialize>(&self, method: &str, params: T) -> Result<()> {
+        let connection = {
+            let guard = self.lsp_connection.lock().await;
+            guard.as_ref().cloned()
+                .ok_or_else(|| anyhow::anyhow!("No connection to {}", S::ID))?
+        };
+
+        connection.send_notification(method, Some(params))
+    }
+
+    async fn forward_notification<T: serde::Serialize>(&self, method: &str, params: T) {
+        if let Err(e) = self.send_notification(method, params).await {
+            logger::error(S::NAME, &format!("Failed to send {} notification: {}", method, e));
+        }
+    }
+
+    async fn forward_request<T: serde::Serialize, R: DeserializeOwned>(&self, method: &str, params: T) -> Option<R> {
+        match self.send_request(method, params).await {
+            Ok(result) => {
+                if result.is_null() {
+                    return None;
+                }
+
+                match serde_json::from_value::<R>(result) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        logger::error(S::NAME, &format!("Failed to parse {} response: {}", method, e));
+                        None
+                    }
+                }
+            },
+            Err(e) => {
+                logger::error(S::NAME, &format!("Failed to send {} request: {}", method, e));
+                None
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<S: StdioServerSpec> LanguageServer for StdioLanguageServer<S> {
+    async fn initialize(&self, mut params: InitializeParams) -> LspResult<InitializeResult> {
+        if let Err(e) = <Self as BaseLanguageServer>::initialize(self) {
+            let message = format!("Failed to initialize {} process: {}", S::NAME, e);
+            logger::error(S::NAME, &message);
+            if let Some(client) = &self.client {
+                client.log_message(MessageType::ERROR, message).await;
+            }
+            return Err(tower_lsp::jsonrpc::Error::internal_error());
+        }
+
+        params.initialization_options = self.config.apply_initialization_options(params.initialization_options.take());
+
+        match self.send_request("initialize", params).await {
+            Ok(result) => {
+                serde_json::from_value::<InitializeResult>(result).map_err(|e| {
+                    logger::error(S::NAME, &format!("Failed to parse initialize response: {}", e));
+                    tower_lsp::jsonrpc::Error::internal_error()
+                })
+            },
+            Err(e) => {
+                logger::error(S::NAME, &format!("Failed to send initialize request: {}", e));
+                Err(tower_lsp::jsonrpc::Error::internal_error())
+            }
+        }
+    }
+
+    async fn initialized(&self, params: InitializedParams) {
+        self.forward_notification("initialized", params).await;
+    }
+
+    async fn shutdown(&self) -> LspResult<()> {
+        if let Err(e) = <Self as BaseLanguageServer>::shutdown(self) {
+            logger::error(S::NAME, &format!("Failed to shut down {}: {}", S::NAME, e));
+        }
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        self.forward_notification("textDocument/didOpen", params).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        self.forward_notification("textDocument/didChange", params).await;
+    }
+
+    async fn did_save(&self, params: DidSaveTextDocumentParams) {
+        self.forward_notification("textDocument/didSave", params).await;
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        self.forward_notification("textDocument/didClose", params).await;
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        self.forward_notification("workspace/didChangeWorkspaceFolders", params).await;
+    }
+
+    async fn completion(&self, params: CompletionParams) -> LspResult<Option<CompletionResponse>> {
+        Ok(self.forward_request("textDocument/completion", params).await)
+    }
+
+    async fn hover(&self, params: HoverParams) -> LspResult<Option<Hover>> {
+        Ok(self.forward_request("textDocument/hover", params).await)
+    }
+
+    async fn goto_definition(&self, params: GotoDefinitionParams) -> LspResult<Option<GotoDefinitionResponse>> {
+        Ok(self.forward_request("textDocument/definition", params).await)
+    }
+
+    async fn references(&self, params: ReferenceParams) -> LspResult<Option<Vec<Location>>> {
+        Ok(self.forward_request("textDocument/references", params).await)
+    }
+
+    async fn document_highlight(&self, params: DocumentHighlightParams) -> LspResult<Option<Vec<DocumentHighlight>>> {
+        Ok(Some(self.forward_request("textDocument/documentHighlight", params).await.unwrap_or_default()))
+    }
+
+    async fn formatting(&self, params: DocumentFormattingParams) -> LspResult<Option<Vec<TextEdit>>> {
+        Ok(self.forward_request("textDocument/formatting", params).await)
+    }
+}
@@ -0,0 +1,17 @@
+use crate::lsp::servers::stdio::{StdioLanguageServer, StdioServerSpec};
+
+#[derive(Clone)]
+pub struct Taplo;
+
+impl StdioServerSpec for Taplo {
+    const ID: &'static str = "taplo";
+    const LANGUAGE: &'static str = "toml";
+    const NAME: &'static str = "Taplo";
+    const EXECUTABLE: &'static str = "taplo";
+    const ARGS: &'static [&'static str] = &["lsp", "stdio"];
+}
+
+/**
+ * TOML support (Cargo.toml, pyproject.toml, ...) backed by `taplo lsp stdio`
+ */
+pub type TomlLanguageServer = StdioLanguageServer<Taplo>;
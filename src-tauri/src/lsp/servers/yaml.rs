@@ -0,0 +1,17 @@
+use crate::lsp::servers::stdio::{StdioLanguageServer, StdioServerSpec};
+
+#[derive(Clone)]
+pub struct YamlLs;
+
+impl StdioServerSpec for YamlLs {
+    const ID: &'static str = "yaml-language-server";
+    const LANGUAGE: &'static str = "yaml";
+    const NAME: &'static str = "YAML Language Server";
+    const EXECUTABLE: &'static str = "yaml-language-server";
+    const ARGS: &'static [&'static str] = &["--stdio"];
+}
+
+/**
+ * YAML support (CI workflows, docker-compose, ...) backed by `yaml-language-server --stdio`
+ */
+pub type YamlLanguageServer = StdioLanguageServer<YamlLs>;
@@ -1,23 +1,60 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU16, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use std::net::SocketAddr;
 use futures_util::{SinkExt, StreamExt};
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, Mutex, Notify};
 use warp::ws::{Message, WebSocket};
 use warp::Filter;
 use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
 use tokio_stream::wrappers::TcpListenerStream;
 use dashmap::DashMap;
 
-use crate::lsp::server_factory::ServerFactory;
+use crate::lsp::server_factory::{self, ServerFactory};
 use crate::lsp::get_supported_languages;
 use crate::lsp::logger;
+use crate::lsp::protocol::{path_from_uri_or_path, path_to_file_uri};
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
 
+/**
+ * Version of the connection handshake; bump when its payload changes shape
+ */
+pub const HANDSHAKE_PROTOCOL_VERSION: u32 = 1;
+
+/**
+ * Source of the ids that tell websocket connections apart, e.g. when cancelling requests
+ */
+static NEXT_CONNECTION_ID: AtomicU64 = AtomicU64::new(1);
+
+/**
+ * How long a stopped server waits for its clients to finish the close handshake
+ */
+const CLOSE_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/**
+ * Requests that don't change the connection's state; they are handled on their own task
+ * so a slow one doesn't hold up the messages behind it, such as its `$/cancelRequest`
+ */
+fn is_concurrent_request(text: &str) -> bool {
+    let Ok(json_rpc) = serde_json::from_str::<serde_json::Value>(text) else {
+        return false;
+    };
+    let method = json_rpc.get("method").and_then(|m| m.as_str());
+    
+    json_rpc.get("id").is_some() && !matches!(method, None | Some("initialize" | "shutdown" | "horizon/handshake"))
+}
+
 pub struct WebSocketManager {
     server_factory: ServerFactory,
     clients: Arc<Mutex<Vec<mpsc::UnboundedSender<Message>>>>,
     active_diagnostics: Arc<DashMap<String, Vec<DiagnosticItem>>>,
+    listener_shutdown: Arc<Notify>,
+    /// Signalled by `stop_server` once every client was sent a close frame
+    stopped: Arc<Notify>,
+    /// Port being served, 0 before `serve` starts
+    port: Arc<AtomicU16>,
+    active_connections: Arc<AtomicUsize>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -90,45 +127,120 @@ impl WebSocketManager {
             server_factory: ServerFactory::new(),
             clients: Arc::new(Mutex::new(Vec::new())),
             active_diagnostics: Arc::new(DashMap::new()),
+            listener_shutdown: Arc::new(Notify::new()),
+            stopped: Arc::new(Notify::new()),
+            port: Arc::new(AtomicU16::new(0)),
+            active_connections: Arc::new(AtomicUsize::new(0)),
         }
     }
     
     pub async fn start_server(&self, port: u16) -> Result<()> {
+        let listener = self.bind(port).await?;
+        self.serve(listener).await;
+        
+        Ok(())
+    }
+    
+    /**
+     * Bind the listening socket, so callers learn whether the port is usable before serving
+     */
+    pub async fn bind(&self, port: u16) -> Result<tokio::net::TcpListener> {
         let socket_addr: SocketAddr = ([127, 0, 0, 1], port).into();
         
         logger::info("WebSocketManager", &format!("Attempting to start WebSocket LSP server on port {}", port));
         
-        match tokio::net::TcpListener::bind(socket_addr).await {
-            Ok(listener) => {
-                let clients = self.clients.clone();
-                let server_factory = self.server_factory.clone();
-                
-                let ws_route = warp::path("lsp")
-                    .and(warp::ws())
-                    .map(move |ws: warp::ws::Ws| {
-                        let clients = clients.clone();
-                        let server_factory = server_factory.clone();
-                        
-                        ws.on_upgrade(move |socket| {
-                            Self::handle_connection(socket, clients, server_factory)
-                        })
-                    });
-                
-                logger::info("WebSocketManager", &format!("WebSocket LSP server started on port {}", port));
-                
-                let incoming = TcpListenerStream::new(listener);
-                warp::serve(ws_route).run_incoming(incoming).await;
+        tokio::net::TcpListener::bind(socket_addr).await.map_err(|e| {
+            logger::error("WebSocketManager", &format!("Cannot start WebSocket server on port {}: {}", port, e));
+            anyhow::anyhow!("Cannot start WebSocket server: {}", e)
+        })
+    }
+    
+    /**
+     * Accept connections on `listener` until `shutdown_listener` is called
+     */
+    pub async fn serve(&self, listener: tokio::net::TcpListener) {
+        let port = listener.local_addr().map(|addr| addr.port()).unwrap_or_default();
+        self.port.store(port, Ordering::SeqCst);
+        let clients = self.clients.clone();
+        let server_factory = self.server_factory.clone();
+        let active_connections = self.active_connections.clone();
+        
+        let ws_route = warp::path("lsp")
+            .and(warp::ws())
+            .map(move |ws: warp::ws::Ws| {
+                let clients = clients.clone();
+                let server_factory = server_factory.clone();
+                let active_connections = active_connections.clone();
                 
-                Ok(())
+                ws.on_upgrade(move |socket| async move {
+                    active_connections.fetch_add(1, Ordering::SeqCst);
+                    Self::handle_connection(socket, clients, server_factory).await;
+                    active_connections.fetch_sub(1, Ordering::SeqCst);
+                })
+            });
+        
+        logger::info("WebSocketManager", &format!("WebSocket LSP server started on port {}", port));
+        
+        let incoming = TcpListenerStream::new(listener);
+        
+        // Dropping the server future closes the listening socket right away, freeing the port
+        tokio::select! {
+            _ = warp::serve(ws_route).run_incoming(incoming) => {},
+            _ = self.listener_shutdown.notified() => {
+                logger::info("WebSocketManager", &format!("WebSocket LSP listener on port {} closed", port));
             },
-            Err(e) => {
-                logger::error("WebSocketManager", &format!("Cannot start WebSocket server on port {}: {}", port, e));
-                Err(anyhow::anyhow!("Cannot start WebSocket server: {}", e))
+        }
+    }
+    
+    /**
+     * Port being served, if `serve` has started
+     */
+    pub fn port(&self) -> Option<u16> {
+        Some(self.port.load(Ordering::SeqCst)).filter(|port| *port != 0)
+    }
+    
+    /**
+     * Wait for `stop_server`, then give the clients `CLOSE_GRACE_PERIOD` to disconnect.
+     * Connections run on the runtime that serves them, so it has to outlive `serve`
+     * until they are closed
+     */
+    pub async fn wait_stopped(&self) {
+        self.stopped.notified().await;
+        
+        let deadline = Instant::now() + CLOSE_GRACE_PERIOD;
+        while self.active_connections.load(Ordering::SeqCst) > 0 && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+    
+    /**
+     * Stop accepting new connections; already connected clients stay connected
+     */
+    pub fn shutdown_listener(&self) {
+        // notify_one keeps a permit, so a shutdown requested before serving starts isn't lost
+        self.listener_shutdown.notify_one();
+    }
+    
+    /**
+     * Tell connected clients the server is moving to `port` so they reconnect there
+     */
+    pub async fn notify_reconnect(&self, port: u16) {
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "horizon/reconnect",
+            "params": { "port": port }
+        });
+        
+        for client in self.clients.lock().await.iter() {
+            if let Err(e) = client.send(Message::text(notification.to_string())) {
+                logger::error("WebSocketManager", &format!("Error sending reconnect notification: {}", e));
             }
         }
     }
     
     pub async fn stop_server(&self) -> Result<()> {
+        self.shutdown_listener();
+        
         let mut clients = self.clients.lock().await;
         
         for client in clients.iter_mut() {
@@ -140,6 +252,8 @@ impl WebSocketManager {
         }
         
         clients.clear();
+        // notify_one keeps a permit, like `shutdown_listener`
+        self.stopped.notify_one();
         
         logger::info("WebSocketManager", "WebSocket LSP server stopped");
         Ok(())
@@ -150,7 +264,8 @@ impl WebSocketManager {
         clients: Arc<Mutex<Vec<mpsc::UnboundedSender<Message>>>>,
         server_factory: ServerFactory,
     ) {
-        logger::info("WebSocketManager", "New WebSocket LSP connection");
+        let connection_id = NEXT_CONNECTION_ID.fetch_add(1, Ordering::Relaxed);
+        logger::info("WebSocketManager", &format!("New WebSocket LSP connection {}", connection_id));
         
         let (mut ws_tx, mut ws_rx) = ws.split();
         
@@ -158,6 +273,15 @@ impl WebSocketManager {
         
         clients.lock().await.push(tx.clone());
         
+        let handshake = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "horizon/handshake",
+            "params": Self::handshake_payload(connection_id)
+        });
+        if let Err(e) = tx.send(Message::text(handshake.to_string())) {
+            logger::error("WebSocketManager", &format!("Error sending handshake: {}", e));
+        }
+        
         let forward_task = tokio::task::spawn(async move {
             while let Some(msg) = rx.recv().await {
                 if let Err(e) = ws_tx.send(msg).await {
@@ -167,22 +291,27 @@ impl WebSocketManager {
             }
         });
         
-        let server_factory_clone = server_factory.clone();
+        // Shared with the tasks of concurrent requests; cloning a ServerFactory gives an empty one
+        let server_factory_clone = Arc::new(server_factory);
         let backward_task = tokio::task::spawn(async move {
             let mut active_server = None;
             
             while let Some(result) = ws_rx.next().await {
                 match result {
                     Ok(msg) => {
-                        if msg.is_text() || msg.is_binary() {
-                            let response = Self::handle_message(msg, &server_factory_clone, &mut active_server).await;
-                            if let Ok(response_msg) = response {
-                                if !response_msg.as_bytes().is_empty() {
-                                    if let Err(e) = tx.send(response_msg) {
-                                        logger::error("WebSocketManager", &format!("Error sending response: {}", e));
-                                        break;
-                                    }
-                                }
+                        if (msg.is_text() || msg.is_binary()) && active_server.is_some() && msg.to_str().is_ok_and(is_concurrent_request) {
+                            let server_factory = Arc::clone(&server_factory_clone);
+                            let tx = tx.clone();
+                            let mut request_server = active_server.clone();
+                            
+                            tokio::task::spawn(async move {
+                                let response = Self::handle_message(msg, connection_id, &server_factory, &mut request_server).await;
+                                Self::send_response(&tx, response);
+                            });
+                        } else if msg.is_text() || msg.is_binary() {
+                            let response = Self::handle_message(msg, connection_id, &server_factory_clone, &mut active_server).await;
+                            if !Self::send_response(&tx, response) {
+                                break;
                             }
                         } else if msg.is_close() {
                             if let Some(server_id) = active_server.take() {
@@ -215,8 +344,37 @@ impl WebSocketManager {
         }
     }
     
+    /**
+     * Send a non-empty response to the client; false when the connection is gone
+     */
+    fn send_response(tx: &mpsc::UnboundedSender<Message>, response: Result<Message>) -> bool {
+        match response {
+            Ok(response_msg) if !response_msg.as_bytes().is_empty() => {
+                if let Err(e) = tx.send(response_msg) {
+                    logger::error("WebSocketManager", &format!("Error sending response: {}", e));
+                    return false;
+                }
+                true
+            },
+            _ => true,
+        }
+    }
+    
+    /**
+     * Languages and capabilities the client can use to enable or disable features
+     */
+    fn handshake_payload(connection_id: u64) -> serde_json::Value {
+        serde_json::json!({
+            "protocol_version": HANDSHAKE_PROTOCOL_VERSION,
+            "connection_id": connection_id,
+            "supported_languages": get_supported_languages(),
+            "server_capabilities": server_factory::cached_server_capabilities()
+        })
+    }
+    
     async fn handle_message(
         msg: Message, 
+        connection_id: u64,
         server_factory: &ServerFactory, 
         active_server: &mut Option<String>
     ) -> Result<Message> {
@@ -261,6 +419,23 @@ impl WebSocketManager {
                             let method_name = method_value.as_str().unwrap_or("");
                             
                             match method_name {
+                                "horizon/handshake" if id.is_some() => {
+                                    let client_version = params
+                                        .and_then(|p| p.get("protocol_version"))
+                                        .and_then(|v| v.as_u64());
+                                    
+                                    let mut result = Self::handshake_payload(connection_id);
+                                    result["compatible"] = serde_json::Value::Bool(
+                                        client_version.is_none_or(|v| v >= 1 && v <= HANDSHAKE_PROTOCOL_VERSION as u64)
+                                    );
+                                    
+                                    let response = serde_json::json!({
+                                        "jsonrpc": "2.0",
+                                        "id": id_value,
+                                        "result": result
+                                    });
+                                    return Ok(Message::text(response.to_string()));
+                                },
                                 "initialize" if id.is_some() => {
                                     logger::info("WebSocketManager", "Received initialize request");
                                     
@@ -280,11 +455,9 @@ impl WebSocketManager {
                                         }
                                     };
                                     
-                                    let file_path = params_value.get("rootUri")
+                                    let file_path = path_from_uri_or_path(params_value.get("rootUri")
                                         .and_then(|v| v.as_str())
-                                        .unwrap_or("")
-                                        .trim_start_matches("file://")
-                                        .to_string();
+                                        .unwrap_or(""));
                                     
                                     let language = params_value.get("initializationOptions")
                                         .and_then(|v| v.get("language"))
@@ -305,21 +478,9 @@ impl WebSocketManager {
                                         }
                                     }
                                     
-                                    let supported_languages = get_supported_languages();
-                                    if !supported_languages.contains(&final_language.as_str()) {
-                                        logger::info("WebSocketManager", &format!("Language {} is not supported by LSP server", final_language));
-                                        
-                                        let error_response = serde_json::json!({
-                                            "jsonrpc": "2.0",
-                                            "id": id.unwrap_or(&serde_json::Value::Null).clone(),
-                                            "error": {
-                                                "code": -32601,
-                                                "message": format!("Language '{}' is not supported. Currently supported languages are: {}", 
-                                                                  final_language, supported_languages.join(", "))
-                                            }
-                                        });
-                                        
-                                        return Ok(Message::text(error_response.to_string()));
+                                    // The factory falls back to the generic server for these
+                                    if !get_supported_languages().contains(&final_language.as_str()) {
+                                        logger::info("WebSocketManager", &format!("No dedicated LSP server for language {}, using the generic server", final_language));
                                     }
                                     
                                     logger::info("WebSocketManager", &format!("Using language for initialization: {}", final_language));
@@ -330,7 +491,8 @@ impl WebSocketManager {
                                             
                                             let mut updated_params = params_value.clone();
                                             
-                                            let correct_root_uri = format!("file://{}", correct_root_path);
+                                            let correct_root_uri = path_to_file_uri(&correct_root_path)
+                                                .unwrap_or_else(|_| format!("file://{}", correct_root_path));
                                             
                                             if let Some(obj) = updated_params.as_object_mut() {
                                                 obj.insert("rootUri".to_string(), serde_json::Value::String(correct_root_uri.clone()));
@@ -370,7 +532,7 @@ impl WebSocketManager {
                                                     
                                                     let request_text = serde_json::to_string(&updated_json_rpc)?;
                                                     
-                                                    let forward_result = server_factory.forward_request(&server_id, &request_text).await;
+                                                    let forward_result = server_factory.forward_request(connection_id, &server_id, &request_text).await;
                                                     
                                                     match forward_result {
                                                         Ok(response_text) => {
@@ -419,7 +581,7 @@ impl WebSocketManager {
                                                 Ok(server_id) => {
                                                     *active_server = Some(server_id.clone());
                                                     
-                                                    let forward_result = server_factory.forward_request(&server_id, text).await;
+                                                    let forward_result = server_factory.forward_request(connection_id, &server_id, text).await;
                                                     
                                                     match forward_result {
                                                         Ok(response_text) => {
@@ -464,7 +626,7 @@ impl WebSocketManager {
                                     logger::info("WebSocketManager", "Received initialized notification");
                                     
                                     if let Some(server_id) = active_server {
-                                        let forward_result = server_factory.forward_request(server_id, text).await;
+                                        let forward_result = server_factory.forward_request(connection_id, server_id, text).await;
                                         
                                         match forward_result {
                                             Ok(_) => {
@@ -505,11 +667,7 @@ impl WebSocketManager {
                                     }
                                     
                                     if language_id == "generic" || language_id == "plaintext" || language_id.is_empty() {
-                                        let file_path = if file_uri.starts_with("file://") {
-                                            file_uri[7..].to_string()
-                                        } else {
-                                            file_uri.clone()
-                                        };
+                                        let file_path = path_from_uri_or_path(&file_uri);
                                         
                                         logger::info("WebSocketManager", &format!("Analyzing file: '{}' with declared language: '{}'", file_path, language_id));
                                         
@@ -551,7 +709,7 @@ impl WebSocketManager {
                                                     let modified_text = serde_json::to_string(&modified_json_rpc)
                                                         .unwrap_or_else(|_| text.to_string());
                                                     
-                                                    let forward_result = server_factory.forward_request(server_id, &modified_text).await;
+                                                    let forward_result = server_factory.forward_request(connection_id, server_id, &modified_text).await;
                                                     
                                                     if let Err(e) = forward_result {
                                                         logger::error("WebSocketManager", &format!("Error forwarding didOpen: {}", e));
@@ -561,11 +719,7 @@ impl WebSocketManager {
                                                 } else {
                                                     logger::info("WebSocketManager", &format!("No active LSP server, trying to create new one for: {}", language_id));
                                                     
-                                                    let file_path = if file_uri.starts_with("file://") {
-                                                        file_uri[7..].to_string()
-                                                    } else {
-                                                        file_uri.clone()
-                                                    };
+                                                    let file_path = path_from_uri_or_path(&file_uri);
                                                     
                                                     match server_factory.create_server(&language_id, &file_path).await {
                                                         Ok(server_id) => {
@@ -575,7 +729,7 @@ impl WebSocketManager {
                                                             let modified_text = serde_json::to_string(&modified_json_rpc)
                                                                 .unwrap_or_else(|_| text.to_string());
                                                             
-                                                            let forward_result = server_factory.forward_request(&server_id, &modified_text).await;
+                                                            let forward_result = server_factory.forward_request(connection_id, &server_id, &modified_text).await;
                                                             
                                                             if let Err(e) = forward_result {
                                                                 logger::error("WebSocketManager", &format!("Error forwarding didOpen to new server: {}", e));
@@ -596,7 +750,7 @@ impl WebSocketManager {
                                     }
                                     
                                     if let Some(server_id) = active_server {
-                                        let forward_result = server_factory.forward_request(server_id, text).await;
+                                        let forward_result = server_factory.forward_request(connection_id, server_id, text).await;
                                         
                                         if let Err(e) = forward_result {
                                             logger::error("WebSocketManager", &format!("Error forwarding didOpen: {}", e));
@@ -648,7 +802,7 @@ impl WebSocketManager {
                                     
                                     if let Some(server_id) = active_server {
                                         logger::info("WebSocketManager", &format!("Forwarding didChange to server: {}", server_id));
-                                        let forward_result = server_factory.forward_request(server_id, text).await;
+                                        let forward_result = server_factory.forward_request(connection_id, server_id, text).await;
                                         
                                         match forward_result {
                                             Ok(_) => {
@@ -670,7 +824,7 @@ impl WebSocketManager {
                                     logger::info("WebSocketManager", "Received didSave notification");
                                     
                                     if let Some(server_id) = active_server {
-                                        let forward_result = server_factory.forward_request(server_id, text).await;
+                                        let forward_result = server_factory.forward_request(connection_id, server_id, text).await;
                                         
                                         if let Err(e) = forward_result {
                                             logger::error("WebSocketManager", &format!("Error forwarding didSave: {}", e));
@@ -685,7 +839,7 @@ impl WebSocketManager {
                                 
                                 _ => {
                                     if let Some(server_id) = active_server {
-                                        let forward_result = server_factory.forward_request(server_id, text).await;
+                                        let forward_result = server_factory.forward_request(connection_id, server_id, text).await;
                                         
                                         match forward_result {
                                             Ok(response_text) => {
@@ -986,6 +1140,10 @@ impl Clone for WebSocketManager {
             server_factory: self.server_factory.clone(),
             clients: self.clients.clone(),
             active_diagnostics: self.active_diagnostics.clone(),
+            listener_shutdown: self.listener_shutdown.clone(),
+            stopped: self.stopped.clone(),
+            port: self.port.clone(),
+            active_connections: self.active_connections.clone(),
         }
     }
 } 
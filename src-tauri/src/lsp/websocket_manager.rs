@@ -31,7 +31,19 @@ pub async fn start_lsp_websocket_server(port: u16) -> Result<String, String> {
         WS_MANAGER = Some(ws_manager.clone());
     }
     
-    let port_clone = port;
+    // This command only reports that startup began, so the bound port isn't awaited here
+    let _ = spawn_server_thread(ws_manager, port);
+    
+    Ok(format!("Starting LSP WebSocket server on port {} (or next available)", port))
+}
+
+/**
+ * Run the WebSocket server on its own runtime, moving to the next port when binding fails.
+ * The receiver gets the port that was actually bound, or an error once every attempt failed
+ */
+fn spawn_server_thread(ws_manager: WebSocketManager, port: u16) -> std::sync::mpsc::Receiver<Result<u16, String>> {
+    let (bound_tx, bound_rx) = std::sync::mpsc::channel();
+    
     std::thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new()
             .map_err(|e| format!("Failed to create runtime: {}", e))
@@ -40,13 +52,17 @@ pub async fn start_lsp_websocket_server(port: u16) -> Result<String, String> {
         rt.block_on(async {
             WS_SERVER_RUNNING.store(true, Ordering::SeqCst);
             
-            let mut current_port = port_clone;
+            let mut current_port = port;
             let max_attempts = 5;
             
             for attempt in 0..max_attempts {
-                match ws_manager.start_server(current_port).await {
-                    Ok(_) => {
+                match ws_manager.bind(current_port).await {
+                    Ok(listener) => {
                         log("start_lsp_websocket_server", &format!("LSP WebSocket server successfully started on port {}", current_port));
+                        let _ = bound_tx.send(Ok(current_port));
+                        ws_manager.serve(listener).await;
+                        // Keeps the runtime, and with it the client connections, alive until stop_server
+                        ws_manager.wait_stopped().await;
                         break;
                     },
                     Err(e) => {
@@ -59,6 +75,7 @@ pub async fn start_lsp_websocket_server(port: u16) -> Result<String, String> {
                         } else {
                             log_error("start_lsp_websocket_server", &format!("All attempts to start WebSocket server exhausted ({} attempts)", max_attempts));
                             WS_SERVER_RUNNING.store(false, Ordering::SeqCst);
+                            let _ = bound_tx.send(Err(format!("Cannot start WebSocket server on ports {}-{}", port, current_port)));
                         }
                     }
                 }
@@ -66,7 +83,7 @@ pub async fn start_lsp_websocket_server(port: u16) -> Result<String, String> {
         });
     });
     
-    Ok(format!("Starting LSP WebSocket server on port {} (or next available)", port))
+    bound_rx
 }
 
 pub fn is_lsp_websocket_running() -> bool {
@@ -95,6 +112,58 @@ pub async fn stop_lsp_websocket_server() -> Result<String, String> {
     Ok("LSP WebSocket server stopped".to_string())
 }
 
+/**
+ * Move the WebSocket server to `port` (or the next free one) and return the port actually bound.
+ * Connected clients are told the new port before their connections are closed
+ */
+pub async fn restart_lsp_websocket_server(port: u16) -> Result<u16, String> {
+    let old_manager = unsafe { (*std::ptr::addr_of!(WS_MANAGER)).clone() };
+    
+    // Release the old listener first so the same port can be reused. Its clients stay
+    // connected until they were told about the new port
+    if let Some(old_manager) = &old_manager {
+        old_manager.shutdown_listener();
+        
+        if let Some(old_port) = old_manager.port() {
+            let addr = format!("127.0.0.1:{}", old_port);
+            for _ in 0..20 {
+                if std::net::TcpListener::bind(&addr).is_ok() {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+        }
+    }
+    
+    WS_SERVER_RUNNING.store(false, Ordering::SeqCst);
+    
+    let ws_manager = WebSocketManager::new();
+    let bound_rx = spawn_server_thread(ws_manager.clone(), port);
+    
+    let bound_port = tokio::task::spawn_blocking(move || {
+        bound_rx.recv_timeout(std::time::Duration::from_secs(10))
+            .map_err(|_| "Timed out waiting for the WebSocket server to start".to_string())?
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    
+    unsafe {
+        WS_MANAGER = Some(ws_manager);
+    }
+    
+    if let Some(old_manager) = old_manager {
+        old_manager.notify_reconnect(bound_port).await;
+        
+        if let Err(e) = old_manager.stop_server().await {
+            log_error("restart_lsp_websocket_server", &format!("Error stopping previous WebSocket server: {}", e));
+        }
+    }
+    
+    log("restart_lsp_websocket_server", &format!("LSP WebSocket server restarted on port {}", bound_port));
+    
+    Ok(bound_port)
+}
+
 pub fn cleanup_on_exit() {
     if WS_SERVER_RUNNING.load(Ordering::SeqCst) {
         let rt = match tokio::runtime::Runtime::new() {
@@ -125,4 +194,48 @@ pub fn cleanup_on_exit() {
         
         log("cleanup_on_exit", "LSP WebSocket server stopped during application shutdown");
     }
-} 
\ No newline at end of file
+} 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Next text message that isn't the connection handshake
+    async fn next_notification<S>(socket: &mut S) -> serde_json::Value
+    where
+        S: futures_util::Stream<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        loop {
+            let message = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next()).await
+                .expect("timed out waiting for a message")
+                .expect("connection ended")
+                .expect("connection failed");
+            if let Message::Text(text) = message {
+                let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if value["method"] != "horizon/handshake" {
+                    return value;
+                }
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn restart_tells_connected_clients_the_new_port() {
+        let old_port = restart_lsp_websocket_server(38931).await.unwrap();
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/lsp", old_port)).await.unwrap();
+
+        let new_port = restart_lsp_websocket_server(old_port).await.unwrap();
+        assert_eq!(new_port, old_port);
+
+        let notification = next_notification(&mut socket).await;
+        assert_eq!(notification["method"], "horizon/reconnect");
+        assert_eq!(notification["params"]["port"], new_port);
+
+        let closed = tokio::time::timeout(std::time::Duration::from_secs(5), socket.next()).await.unwrap();
+        assert!(matches!(closed, Some(Ok(Message::Close(_))) | None), "{:?}", closed);
+
+        let _ = stop_lsp_websocket_server().await;
+    }
+}
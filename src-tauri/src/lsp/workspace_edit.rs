@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use tower_lsp::lsp_types::{
+    DocumentChangeOperation, DocumentChanges, OneOf, Position, ResourceOp, TextEdit, Url, WorkspaceEdit,
+};
+
+use crate::fs::write_file_atomically;
+use crate::lsp::logger;
+use crate::lsp::protocol::LSPUtils;
+
+struct EditUtils;
+
+impl LSPUtils for EditUtils {}
+
+/**
+ * Outcome of applying a workspace edit on disk
+ */
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceEditResult {
+    pub applied: bool,
+    /// Files whose content was rewritten, created, renamed or deleted
+    pub changed_files: Vec<String>,
+    pub failure_reason: Option<String>,
+}
+
+/**
+ * A step of a workspace edit, in the order the server listed them
+ */
+enum EditStep {
+    Text(PathBuf, Vec<TextEdit>),
+    Resource(ResourceOp),
+}
+
+fn uri_to_path(uri: &Url) -> Result<PathBuf> {
+    uri.to_file_path().map_err(|_| anyhow!("Not a file URI: {}", uri))
+}
+
+fn collect_steps(edit: WorkspaceEdit) -> Result<Vec<EditStep>> {
+    // documentChanges takes precedence over changes when a server sends both
+    if let Some(document_changes) = edit.document_changes {
+        let text_step = |edit: tower_lsp::lsp_types::TextDocumentEdit| -> Result<EditStep> {
+            let edits = edit.edits.into_iter()
+                .map(|edit| match edit {
+                    OneOf::Left(edit) => edit,
+                    OneOf::Right(annotated) => annotated.text_edit,
+                })
+                .collect();
+            Ok(EditStep::Text(uri_to_path(&edit.text_document.uri)?, edits))
+        };
+
+        return match document_changes {
+            DocumentChanges::Edits(edits) => edits.into_iter().map(text_step).collect(),
+            DocumentChanges::Operations(operations) => operations.into_iter()
+                .map(|operation| match operation {
+                    DocumentChangeOperation::Edit(edit) => text_step(edit),
+                    DocumentChangeOperation::Op(op) => Ok(EditStep::Resource(op)),
+                })
+                .collect(),
+        };
+    }
+
+    edit.changes.unwrap_or_default()
+        .into_iter()
+        .map(|(uri, edits)| Ok(EditStep::Text(uri_to_path(&uri)?, edits)))
+        .collect()
+}
+
+/**
+ * Apply text edits to a document. Edits are applied from the end of the document
+ * backwards so earlier offsets stay valid; overlapping edits are rejected
+ */
+pub(crate) fn apply_text_edits(content: &str, edits: &[TextEdit]) -> Result<String> {
+    // A position on a line past the end of the document means "append"
+    let to_offset = |position: Position| {
+        EditUtils::position_to_offset(content, lsp_types::Position::new(position.line, position.character))
+            .unwrap_or(content.len())
+    };
+
+    // Keep the original index so inserts at the same position stay in the order they were sent
+    let mut ranges: Vec<(usize, usize, usize)> = edits.iter()
+        .enumerate()
+        .map(|(index, edit)| (to_offset(edit.range.start), to_offset(edit.range.end), index))
+        .collect();
+    ranges.sort();
+
+    for pair in ranges.windows(2) {
+        if pair[0].1 > pair[1].0 {
+            return Err(anyhow!("Overlapping edits at {}..{} and {}..{}", pair[0].0, pair[0].1, pair[1].0, pair[1].1));
+        }
+    }
+
+    let mut result = content.to_string();
+    for &(start, end, index) in ranges.iter().rev() {
+        if start > end {
+            return Err(anyhow!("Invalid edit range {}..{}", start, end));
+        }
+        result.replace_range(start..end, &edits[index].new_text);
+    }
+
+    Ok(result)
+}
+
+fn apply_resource_op(op: &ResourceOp) -> Result<Vec<String>> {
+    match op {
+        ResourceOp::Create(create) => {
+            let path = uri_to_path(&create.uri)?;
+            let options = create.options.as_ref();
+            let overwrite = options.and_then(|o| o.overwrite).unwrap_or(false);
+            let ignore_if_exists = options.and_then(|o| o.ignore_if_exists).unwrap_or(false);
+
+            if path.exists() && !overwrite {
+                if ignore_if_exists {
+                    return Ok(Vec::new());
+                }
+                return Err(anyhow!("{} already exists", path.display()));
+            }
+
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, "")?;
+            Ok(vec![path.to_string_lossy().to_string()])
+        },
+        ResourceOp::Rename(rename) => {
+            let old_path = uri_to_path(&rename.old_uri)?;
+            let new_path = uri_to_path(&rename.new_uri)?;
+            let options = rename.options.as_ref();
+            let overwrite = options.and_then(|o| o.overwrite).unwrap_or(false);
+            let ignore_if_exists = options.and_then(|o| o.ignore_if_exists).unwrap_or(false);
+
+            if new_path.exists() && !overwrite {
+                if ignore_if_exists {
+                    return Ok(Vec::new());
+                }
+                return Err(anyhow!("{} already exists", new_path.display()));
+            }
+
+            if let Some(parent) = new_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::rename(&old_path, &new_path)?;
+            Ok(vec![old_path.to_string_lossy().to_string(), new_path.to_string_lossy().to_string()])
+        },
+        ResourceOp::Delete(delete) => {
+            let path = uri_to_path(&delete.uri)?;
+            let options = delete.options.as_ref();
+
+            if !path.exists() {
+                if options.and_then(|o| o.ignore_if_not_exists).unwrap_or(false) {
+                    return Ok(Vec::new());
+                }
+                return Err(anyhow!("{} does not exist", path.display()));
+            }
+
+            if path.is_dir() {
+                if options.and_then(|o| o.recursive).unwrap_or(false) {
+                    fs::remove_dir_all(&path)?;
+                } else {
+                    fs::remove_dir(&path)?;
+                }
+            } else {
+                fs::remove_file(&path)?;
+            }
+            Ok(vec![path.to_string_lossy().to_string()])
+        },
+    }
+}
+
+/**
+ * Apply a workspace edit (e.g. the result of a rename) to the files on disk.
+ *
+ * Text edits are computed for every file before anything is written, so an
+ * invalid edit leaves the workspace untouched; each file is then written atomically.
+ * Resource operations (create/rename/delete) are applied in the order they were sent
+ */
+pub fn apply_workspace_edit(edit: WorkspaceEdit) -> WorkspaceEditResult {
+    let mut changed_files = Vec::new();
+
+    let result = (|| -> Result<()> {
+        let steps = collect_steps(edit)?;
+        let has_resource_ops = steps.iter().any(|step| matches!(step, EditStep::Resource(_)));
+
+        if !has_resource_ops {
+            // Only text edits: validate all of them up front, then write
+            let mut new_contents: HashMap<PathBuf, String> = HashMap::new();
+            for step in &steps {
+                if let EditStep::Text(path, edits) = step {
+                    let content = match new_contents.get(path) {
+                        Some(content) => content.clone(),
+                        None => fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?,
+                    };
+                    let updated = apply_text_edits(&content, edits)
+                        .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+                    new_contents.insert(path.clone(), updated);
+                }
+            }
+
+            for (path, content) in new_contents {
+                write_file_atomically(&path, content.as_bytes())?;
+                changed_files.push(path.to_string_lossy().to_string());
+            }
+
+            return Ok(());
+        }
+
+        // Later edits may target files created or renamed by earlier operations, so apply in order
+        for step in &steps {
+            match step {
+                EditStep::Text(path, edits) => {
+                    let content = fs::read_to_string(path)
+                        .map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+                    let updated = apply_text_edits(&content, edits)
+                        .map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+                    write_file_atomically(path, updated.as_bytes())?;
+                    changed_files.push(path.to_string_lossy().to_string());
+                },
+                EditStep::Resource(op) => {
+                    changed_files.extend(apply_resource_op(op)?);
+                },
+            }
+        }
+
+        Ok(())
+    })();
+
+    changed_files.sort();
+    changed_files.dedup();
+
+    match result {
+        Ok(()) => {
+            logger::info("WorkspaceEdit", &format!("Applied workspace edit to {} file(s)", changed_files.len()));
+            WorkspaceEditResult { applied: true, changed_files, failure_reason: None }
+        },
+        Err(e) => {
+            logger::error("WorkspaceEdit", &format!("Failed to apply workspace edit: {}", e));
+            WorkspaceEditResult { applied: false, changed_files, failure_reason: Some(e.to_string()) }
+        }
+    }
+}
@@ -0,0 +1,548 @@
+//! Media module reading basic metadata from the headers of image, audio and video files
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use serde::Serialize;
+use tauri::command;
+
+use crate::fs::{is_audio_file, is_image_file, is_video_file};
+
+/// Bytes read from the start of a file to parse its header
+const HEADER_BYTES: u64 = 64 * 1024;
+
+/// Largest MP4 `moov` box read, which holds the duration and dimensions
+const MAX_MOOV_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Basic metadata of a media file; fields the header doesn't provide cheaply are None
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MediaInfo {
+    /// "image", "audio" or "video"
+    pub kind: String,
+    /// Format detected from the file's magic bytes, e.g. "png", "wav", "mp4", "webm"
+    pub format: Option<String>,
+    pub size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub duration_seconds: Option<f64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+}
+
+fn be_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn be_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+fn be_u64(bytes: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(bytes.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+fn le_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn le_u24(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+fn le_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Identifies a media format from the first bytes of a file
+pub fn sniff_format(header: &[u8]) -> Option<&'static str> {
+    let riff_type = header.get(8..12).filter(|_| header.starts_with(b"RIFF"));
+
+    if header.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if header.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpeg")
+    } else if header.starts_with(b"GIF87a") || header.starts_with(b"GIF89a") {
+        Some("gif")
+    } else if header.starts_with(b"BM") {
+        Some("bmp")
+    } else if riff_type == Some(b"WEBP") {
+        Some("webp")
+    } else if riff_type == Some(b"WAVE") {
+        Some("wav")
+    } else if riff_type == Some(b"AVI ") {
+        Some("avi")
+    } else if header.starts_with(b"fLaC") {
+        Some("flac")
+    } else if header.starts_with(b"OggS") {
+        Some("ogg")
+    } else if header.starts_with(b"ID3") || (header.len() > 1 && header[0] == 0xFF && header[1] & 0xE0 == 0xE0) {
+        Some("mp3")
+    } else if header.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        // The EBML header names the document type near the start
+        match header.windows(4).take(64).any(|window| window == b"webm") {
+            true => Some("webm"),
+            false => Some("matroska"),
+        }
+    } else if matches!(header.get(4..8), Some(b"ftyp" | b"moov" | b"mdat" | b"wide" | b"free")) {
+        Some("mp4")
+    } else {
+        None
+    }
+}
+
+/// Reads up to `HEADER_BYTES` from the start of a file
+pub fn read_header(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut header = Vec::new();
+    File::open(path)?.take(HEADER_BYTES).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+fn read_image_dimensions(format: &str, header: &[u8]) -> Option<(u32, u32)> {
+    match format {
+        "png" if header.get(12..16) == Some(b"IHDR") => Some((be_u32(header, 16)?, be_u32(header, 20)?)),
+        "gif" => Some((le_u16(header, 6)? as u32, le_u16(header, 8)? as u32)),
+        // Bottom-up bitmaps store a negative height
+        "bmp" => Some((le_u32(header, 18)?, (le_u32(header, 22)? as i32).unsigned_abs())),
+        "webp" => match header.get(12..16)? {
+            b"VP8 " => Some(((le_u16(header, 26)? & 0x3FFF) as u32, (le_u16(header, 28)? & 0x3FFF) as u32)),
+            b"VP8L" => {
+                let bits = le_u32(header, 21)?;
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            },
+            b"VP8X" => Some((le_u24(header, 24)? + 1, le_u24(header, 27)? + 1)),
+            _ => None,
+        },
+        "jpeg" => {
+            let mut offset = 2;
+            loop {
+                if *header.get(offset)? != 0xFF {
+                    return None;
+                }
+                let marker = *header.get(offset + 1)?;
+                match marker {
+                    // Padding and markers without a length
+                    0xFF => offset += 1,
+                    0x01 | 0xD0..=0xD8 => offset += 2,
+                    // Start of frame, except DHT, JPG and DAC which share the range
+                    0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                        return Some((be_u16(header, offset + 7)? as u32, be_u16(header, offset + 5)? as u32));
+                    },
+                    _ => offset += 2 + be_u16(header, offset + 2)? as usize,
+                }
+            }
+        },
+        _ => None,
+    }
+}
+
+fn read_wav_info(header: &[u8], info: &mut MediaInfo) {
+    let mut offset = 12;
+    let mut byte_rate = None;
+
+    while let (Some(id), Some(size)) = (header.get(offset..offset + 4), le_u32(header, offset + 4)) {
+        let data = offset + 8;
+        match id {
+            b"fmt " => {
+                info.channels = le_u16(header, data + 2);
+                info.sample_rate = le_u32(header, data + 4);
+                byte_rate = le_u32(header, data + 8).filter(|rate| *rate > 0);
+            },
+            b"data" => {
+                info.duration_seconds = byte_rate.map(|rate| size as f64 / rate as f64);
+                return;
+            },
+            _ => {},
+        }
+        // Chunks are padded to an even size
+        offset = data + size as usize + (size as usize & 1);
+    }
+}
+
+fn read_flac_info(header: &[u8], info: &mut MediaInfo) {
+    // STREAMINFO is always the first metadata block; its sample rate starts 10 bytes in
+    let Some(streaminfo) = header.get(18..26) else {
+        return;
+    };
+    let sample_rate = (streaminfo[0] as u32) << 12 | (streaminfo[1] as u32) << 4 | (streaminfo[2] as u32) >> 4;
+    let total_samples = ((streaminfo[3] & 0x0F) as u64) << 32 | be_u32(streaminfo, 4).unwrap_or(0) as u64;
+
+    info.sample_rate = Some(sample_rate).filter(|rate| *rate > 0);
+    info.channels = Some(((streaminfo[2] >> 1) & 0x07) as u16 + 1);
+    info.duration_seconds = info.sample_rate
+        .filter(|_| total_samples > 0)
+        .map(|rate| total_samples as f64 / rate as f64);
+}
+
+fn read_ogg_info(header: &[u8], info: &mut MediaInfo) {
+    // The first page carries the codec's identification header right after a one-segment table
+    if header.get(28..35) == Some(b"\x01vorbis") {
+        info.channels = header.get(39).map(|channels| *channels as u16);
+        info.sample_rate = le_u32(header, 40);
+    } else if header.get(28..36) == Some(b"OpusHead") {
+        info.channels = header.get(37).map(|channels| *channels as u16);
+        info.sample_rate = le_u32(header, 40);
+    }
+}
+
+fn read_avi_info(header: &[u8], info: &mut MediaInfo) {
+    if header.get(24..28) != Some(b"avih") {
+        return;
+    }
+    let micros_per_frame = le_u32(header, 32).unwrap_or(0);
+    let total_frames = le_u32(header, 48).unwrap_or(0);
+
+    info.duration_seconds = Some(micros_per_frame as f64 * total_frames as f64 / 1_000_000.0)
+        .filter(|duration| *duration > 0.0);
+    info.width = le_u32(header, 64);
+    info.height = le_u32(header, 68);
+}
+
+/// Reads the `moov` box of an MP4/QuickTime file, which may sit after the media data
+fn read_moov_box(file: &mut File) -> std::io::Result<Option<Vec<u8>>> {
+    let file_size = file.metadata()?.len();
+    let mut offset: u64 = 0;
+
+    while offset.saturating_add(8) <= file_size {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut box_header = [0u8; 16];
+        let read = file.read(&mut box_header)?;
+        if read < 8 {
+            break;
+        }
+
+        let (size, header_size) = match be_u32(&box_header, 0).unwrap_or(0) {
+            0 => (file_size - offset, 8),
+            1 if read >= 16 => (be_u64(&box_header, 8).unwrap_or(0), 16),
+            size => (size as u64, 8),
+        };
+        if size < header_size {
+            break;
+        }
+
+        if &box_header[4..8] == b"moov" {
+            let mut moov = Vec::new();
+            file.seek(SeekFrom::Start(offset + header_size))?;
+            file.take((size - header_size).min(MAX_MOOV_BYTES)).read_to_end(&mut moov)?;
+            return Ok(Some(moov));
+        }
+
+        // A corrupt 64-bit size can point past any real file
+        match offset.checked_add(size) {
+            Some(next) => offset = next,
+            None => break,
+        }
+    }
+
+    Ok(None)
+}
+
+fn read_mp4_boxes(data: &[u8], info: &mut MediaInfo) {
+    let mut offset = 0;
+
+    while let (Some(size), Some(kind)) = (be_u32(data, offset), data.get(offset + 4..offset + 8)) {
+        let size = size as usize;
+        let Some(end) = offset.checked_add(size).filter(|end| size >= 8 && *end <= data.len()) else {
+            break;
+        };
+        let content = &data[offset + 8..end];
+
+        match kind {
+            b"trak" | b"mdia" | b"minf" => read_mp4_boxes(content, info),
+            b"mvhd" => {
+                let (timescale, duration) = match content.first() {
+                    Some(1) => (be_u32(content, 20), be_u64(content, 24)),
+                    _ => (be_u32(content, 12), be_u32(content, 16).map(u64::from)),
+                };
+                if let (Some(timescale), Some(duration)) = (timescale.filter(|t| *t > 0), duration) {
+                    info.duration_seconds = Some(duration as f64 / timescale as f64);
+                }
+            },
+            // Audio tracks have a zero size, so the first track with dimensions is the video
+            b"tkhd" if info.width.is_none() => {
+                let dimensions_offset = if content.first() == Some(&1) { 88 } else { 76 };
+                let width = be_u32(content, dimensions_offset).map(|width| width >> 16).filter(|w| *w > 0);
+                let height = be_u32(content, dimensions_offset + 4).map(|height| height >> 16).filter(|h| *h > 0);
+                if let (Some(width), Some(height)) = (width, height) {
+                    info.width = Some(width);
+                    info.height = Some(height);
+                }
+            },
+            _ => {},
+        }
+
+        offset = end;
+    }
+}
+
+/// Reads an EBML variable-length integer, returning its value and length.
+/// IDs keep their length marker bits, sizes don't
+fn read_ebml_vint(data: &[u8], offset: usize, keep_marker: bool) -> Option<(u64, usize)> {
+    let first = *data.get(offset)?;
+    let length = first.leading_zeros() as usize + 1;
+    if length > 8 {
+        return None;
+    }
+
+    let mut value = if keep_marker { first as u64 } else { first as u64 & (0xFF >> length) };
+    for byte in data.get(offset + 1..offset + length)? {
+        value = value << 8 | *byte as u64;
+    }
+    Some((value, length))
+}
+
+fn read_ebml_uint(data: &[u8]) -> u64 {
+    data.iter().take(8).fold(0, |value, byte| value << 8 | *byte as u64)
+}
+
+fn read_ebml_float(data: &[u8]) -> Option<f64> {
+    match data.len() {
+        4 => Some(f32::from_be_bytes(data.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(data.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+/// Walks the Matroska/WebM elements found in the header for the duration, video size and audio format
+fn read_ebml_elements(data: &[u8], info: &mut MediaInfo, timecode_scale: &mut u64, duration: &mut Option<f64>) {
+    const SEGMENT: u64 = 0x18538067;
+    const INFO: u64 = 0x1549A966;
+    const TRACKS: u64 = 0x1654AE6B;
+    const TRACK_ENTRY: u64 = 0xAE;
+    const VIDEO: u64 = 0xE0;
+    const AUDIO: u64 = 0xE1;
+    const CLUSTER: u64 = 0x1F43B675;
+
+    let mut offset = 0;
+    while let Some((id, id_length)) = read_ebml_vint(data, offset, true) {
+        let Some((size, size_length)) = read_ebml_vint(data, offset + id_length, false) else {
+            return;
+        };
+        let start = offset + id_length + size_length;
+        // Unknown-size elements (all ones) and elements cut off by the header extend to the end
+        let end = start.saturating_add(size as usize).min(data.len());
+
+        match id {
+            SEGMENT | INFO | TRACKS | TRACK_ENTRY | VIDEO | AUDIO => {
+                read_ebml_elements(&data[start..end], info, timecode_scale, duration);
+            },
+            // Media data follows the metadata; nothing useful comes after it in the header
+            CLUSTER => return,
+            0x2AD7B1 => *timecode_scale = read_ebml_uint(&data[start..end]),
+            0x4489 => *duration = read_ebml_float(&data[start..end]),
+            0xB0 if info.width.is_none() => info.width = Some(read_ebml_uint(&data[start..end]) as u32),
+            0xBA if info.height.is_none() => info.height = Some(read_ebml_uint(&data[start..end]) as u32),
+            0xB5 if info.sample_rate.is_none() => info.sample_rate = read_ebml_float(&data[start..end]).map(|rate| rate as u32),
+            0x9F if info.channels.is_none() => info.channels = Some(read_ebml_uint(&data[start..end]) as u16),
+            _ => {},
+        }
+
+        offset = end;
+    }
+}
+
+/// Read basic metadata of an image, audio or video file from its header
+///
+/// # Arguments
+/// * `path` - The path of the media file
+///
+/// # Returns
+/// The file's kind and whatever of dimensions, duration, sample rate and channel
+/// count its format provides without decoding, or an error if it isn't a media file
+#[command]
+pub fn get_media_info(path: String) -> Result<MediaInfo, String> {
+    let kind = if is_image_file(path.clone()) {
+        "image"
+    } else if is_audio_file(path.clone()) {
+        "audio"
+    } else if is_video_file(path.clone(), None) {
+        "video"
+    } else {
+        return Err(format!("Not a media file: {}", path));
+    };
+
+    let mut file = File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let size = file.metadata().map_err(|e| format!("Failed to read metadata: {}", e))?.len();
+    let mut header = Vec::new();
+    (&mut file).take(HEADER_BYTES).read_to_end(&mut header)
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let format = sniff_format(&header);
+    let mut info = MediaInfo {
+        kind: kind.to_string(),
+        format: format.map(str::to_string),
+        size,
+        ..Default::default()
+    };
+
+    match format {
+        Some(image @ ("png" | "jpeg" | "gif" | "bmp" | "webp")) => {
+            if let Some((width, height)) = read_image_dimensions(image, &header) {
+                info.width = Some(width);
+                info.height = Some(height);
+            }
+        },
+        Some("wav") => read_wav_info(&header, &mut info),
+        Some("flac") => read_flac_info(&header, &mut info),
+        Some("ogg") => read_ogg_info(&header, &mut info),
+        Some("avi") => read_avi_info(&header, &mut info),
+        Some("mp4") => {
+            if let Ok(Some(moov)) = read_moov_box(&mut file) {
+                read_mp4_boxes(&moov, &mut info);
+            }
+        },
+        Some("webm" | "matroska") => {
+            // Durations are in units of the timecode scale, in nanoseconds (1ms by default)
+            let mut timecode_scale = 1_000_000;
+            let mut duration = None;
+            read_ebml_elements(&header, &mut info, &mut timecode_scale, &mut duration);
+            info.duration_seconds = duration.map(|duration| duration * timecode_scale as f64 / 1_000_000_000.0);
+        },
+        _ => {},
+    }
+
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn mp4_box(kind: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut bytes = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        bytes.extend(kind);
+        bytes.extend(body);
+        bytes
+    }
+
+    fn temp_file(bytes: &[u8]) -> File {
+        let mut file = tempfile::tempfile().unwrap();
+        file.write_all(bytes).unwrap();
+        file
+    }
+
+    #[test]
+    fn wav_duration_comes_from_the_data_chunk() {
+        let mut wav = b"RIFF\0\0\0\0WAVEfmt ".to_vec();
+        wav.extend(16u32.to_le_bytes());
+        wav.extend(1u16.to_le_bytes());
+        wav.extend(2u16.to_le_bytes());
+        wav.extend(8000u32.to_le_bytes());
+        wav.extend(32000u32.to_le_bytes());
+        wav.extend([4, 0, 16, 0]);
+        wav.extend(b"data");
+        wav.extend(64000u32.to_le_bytes());
+
+        let mut info = MediaInfo::default();
+        read_wav_info(&wav, &mut info);
+        assert_eq!(info.channels, Some(2));
+        assert_eq!(info.sample_rate, Some(8000));
+        assert_eq!(info.duration_seconds, Some(2.0));
+    }
+
+    #[test]
+    fn wav_with_oversized_chunk_stops_parsing() {
+        let mut wav = b"RIFF\0\0\0\0WAVELIST".to_vec();
+        wav.extend(u32::MAX.to_le_bytes());
+
+        let mut info = MediaInfo::default();
+        read_wav_info(&wav, &mut info);
+        assert_eq!(info.duration_seconds, None);
+    }
+
+    #[test]
+    fn flac_streaminfo_is_read() {
+        let mut flac = b"fLaC\0\0\0\x22".to_vec();
+        flac.extend([0; 10]);
+        // 44100 Hz, 2 channels, 88200 samples
+        flac.extend([0x0A, 0xC4, 0x42, 0xF0]);
+        flac.extend(88200u32.to_be_bytes());
+
+        let mut info = MediaInfo::default();
+        read_flac_info(&flac, &mut info);
+        assert_eq!(info.sample_rate, Some(44100));
+        assert_eq!(info.channels, Some(2));
+        assert_eq!(info.duration_seconds, Some(2.0));
+    }
+
+    #[test]
+    fn mp4_boxes_give_duration_and_video_size() {
+        let mut mvhd = vec![0u8; 100];
+        mvhd[12..16].copy_from_slice(&600u32.to_be_bytes());
+        mvhd[16..20].copy_from_slice(&1800u32.to_be_bytes());
+        let audio_tkhd = vec![0u8; 84];
+        let mut video_tkhd = vec![0u8; 84];
+        video_tkhd[76..80].copy_from_slice(&(1280u32 << 16).to_be_bytes());
+        video_tkhd[80..84].copy_from_slice(&(720u32 << 16).to_be_bytes());
+
+        let mut moov = mp4_box(b"mvhd", &mvhd);
+        moov.extend(mp4_box(b"trak", &mp4_box(b"tkhd", &audio_tkhd)));
+        moov.extend(mp4_box(b"trak", &mp4_box(b"tkhd", &video_tkhd)));
+
+        let mut info = MediaInfo::default();
+        read_mp4_boxes(&moov, &mut info);
+        assert_eq!(info.duration_seconds, Some(3.0));
+        assert_eq!((info.width, info.height), (Some(1280), Some(720)));
+    }
+
+    #[test]
+    fn mp4_box_running_past_the_data_stops_parsing() {
+        let mut data = mp4_box(b"free", &[0; 4]);
+        data.extend(u32::MAX.to_be_bytes());
+        data.extend(b"mvhd");
+
+        let mut info = MediaInfo::default();
+        read_mp4_boxes(&data, &mut info);
+        assert_eq!(info.duration_seconds, None);
+    }
+
+    #[test]
+    fn moov_box_is_found_after_media_data() {
+        let mut bytes = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        bytes.extend(mp4_box(b"mdat", &[7; 1000]));
+        bytes.extend(mp4_box(b"moov", b"payload"));
+
+        let moov = read_moov_box(&mut temp_file(&bytes)).unwrap();
+        assert_eq!(moov.as_deref(), Some(&b"payload"[..]));
+    }
+
+    #[test]
+    fn moov_search_stops_on_overflowing_box_size() {
+        let mut bytes = mp4_box(b"ftyp", b"isom\0\0\0\0");
+        bytes.extend(1u32.to_be_bytes());
+        bytes.extend(b"mdat");
+        bytes.extend((u64::MAX - 4).to_be_bytes());
+        bytes.extend(mp4_box(b"moov", b"payload"));
+
+        assert_eq!(read_moov_box(&mut temp_file(&bytes)).unwrap(), None);
+    }
+
+    #[test]
+    fn webm_elements_give_duration_and_video_size() {
+        // Segment > Info (Duration 2500.0) and Tracks > TrackEntry > Video (640x360)
+        let mut info_element = vec![0x15, 0x49, 0xA9, 0x66, 0x87, 0x44, 0x89, 0x84];
+        info_element.extend(2500.0f32.to_be_bytes());
+        let video = [0xE0, 0x88, 0xB0, 0x82, 0x02, 0x80, 0xBA, 0x82, 0x01, 0x68];
+        let mut segment_body = info_element;
+        segment_body.extend([0x16, 0x54, 0xAE, 0x6B, 0x80 | (video.len() as u8 + 2), 0xAE, 0x80 | video.len() as u8]);
+        segment_body.extend(video);
+        let mut webm = vec![0x18, 0x53, 0x80, 0x67, 0x80 | segment_body.len() as u8];
+        webm.extend(segment_body);
+
+        let mut info = MediaInfo::default();
+        let mut timecode_scale = 1_000_000;
+        let mut duration = None;
+        read_ebml_elements(&webm, &mut info, &mut timecode_scale, &mut duration);
+        assert_eq!(duration, Some(2500.0));
+        assert_eq!((info.width, info.height), (Some(640), Some(360)));
+    }
+
+    #[test]
+    fn formats_are_sniffed_from_magic_bytes() {
+        assert_eq!(sniff_format(b"\x89PNG\r\n\x1a\n"), Some("png"));
+        assert_eq!(sniff_format(b"RIFF\0\0\0\0WAVE"), Some("wav"));
+        assert_eq!(sniff_format(b"\0\0\0\x18ftypisom"), Some("mp4"));
+        assert_eq!(sniff_format(b"\x1a\x45\xdf\xa3\x9f\x42\x82\x84webm"), Some("webm"));
+        assert_eq!(sniff_format(b"plain text"), None);
+    }
+}
@@ -69,6 +69,11 @@ impl ProcessTracker {
         let process_names = self.process_names.lock().unwrap();
         process_names.get(terminal_id).cloned()
     }
+
+    pub fn get_tracked_pid(&self, terminal_id: &str) -> Option<Pid> {
+        let tracked_processes = self.tracked_processes.lock().unwrap();
+        tracked_processes.get(terminal_id).copied()
+    }
 }
 
 pub fn find_child_process(system: &System, parent_pid: Pid) -> Option<&Process> {
@@ -80,6 +85,24 @@ pub fn find_child_process(system: &System, parent_pid: Pid) -> Option<&Process>
         })
 }
 
+/// Collects every descendant of `root_pid` (excluding the root itself), deepest first
+pub fn find_descendant_processes(system: &System, root_pid: Pid) -> Vec<Pid> {
+    let mut descendants = Vec::new();
+    let mut frontier = vec![root_pid];
+
+    while let Some(parent_pid) = frontier.pop() {
+        for (pid, process) in system.processes() {
+            if process.parent() == Some(parent_pid) && !descendants.contains(pid) {
+                descendants.push(*pid);
+                frontier.push(*pid);
+            }
+        }
+    }
+
+    descendants.reverse();
+    descendants
+}
+
 impl Default for ProcessTracker {
     fn default() -> Self {
         Self::new()
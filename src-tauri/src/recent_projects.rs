@@ -0,0 +1,145 @@
+//! Recent projects module backing the "open recent" menu
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+
+/// Maximum number of projects kept in the list
+const MAX_RECENT_PROJECTS: usize = 20;
+
+/// A recently opened project root
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentProject {
+    pub path: String,
+    pub name: String,
+    /// Milliseconds since the Unix epoch
+    pub last_opened: u64,
+}
+
+/// Resolves the recent projects file inside the app data directory
+fn recent_projects_file(app: &AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app.path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+
+    Ok(app_dir.join("recent_projects.json"))
+}
+
+/// Canonical form of a project path, so the same folder reached through
+/// a symlink or a trailing slash is only listed once
+fn canonical_project_path(path: &str) -> String {
+    fs::canonicalize(path)
+        .unwrap_or_else(|_| PathBuf::from(path))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn read_recent_projects(file: &Path) -> Result<Vec<RecentProject>, String> {
+    if !file.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(file)
+        .map_err(|e| e.to_string())?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| e.to_string())
+}
+
+fn write_recent_projects(file: &Path, projects: &[RecentProject]) -> Result<(), String> {
+    if let Some(parent) = file.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let content = serde_json::to_string_pretty(projects)
+        .map_err(|e| e.to_string())?;
+
+    fs::write(file, content)
+        .map_err(|e| e.to_string())
+}
+
+/// Records a project as just opened, moving it to the top of the list
+///
+/// # Arguments
+/// * `path` - Root path of the project
+/// * `app` - The Tauri application handle
+///
+/// # Returns
+/// A Result containing the updated list, most recent first
+#[command]
+pub async fn add_recent_project(path: String, app: AppHandle) -> Result<Vec<RecentProject>, String> {
+    let file = recent_projects_file(&app)?;
+    let path = canonical_project_path(&path);
+
+    if !Path::new(&path).is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    // An unreadable file is replaced rather than blocking new entries
+    let mut projects = read_recent_projects(&file).unwrap_or_default();
+    projects.retain(|project| canonical_project_path(&project.path) != path);
+
+    let name = Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let last_opened = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+
+    projects.insert(0, RecentProject { path, name, last_opened });
+    projects.truncate(MAX_RECENT_PROJECTS);
+
+    write_recent_projects(&file, &projects)?;
+    Ok(projects)
+}
+
+/// Loads the recently opened projects
+///
+/// # Arguments
+/// * `app` - The Tauri application handle
+///
+/// # Returns
+/// A Result containing the projects whose directory still exists, most recent first.
+/// Dead entries are pruned from the stored file.
+#[command]
+pub async fn get_recent_projects(app: AppHandle) -> Result<Vec<RecentProject>, String> {
+    let file = recent_projects_file(&app)?;
+    let mut projects = read_recent_projects(&file)?;
+    let stored_count = projects.len();
+
+    projects.retain(|project| Path::new(&project.path).is_dir());
+    projects.sort_by_key(|project| std::cmp::Reverse(project.last_opened));
+
+    if projects.len() != stored_count {
+        write_recent_projects(&file, &projects)?;
+    }
+
+    Ok(projects)
+}
+
+/// Removes a project from the recent projects list
+///
+/// # Arguments
+/// * `path` - Root path of the project
+/// * `app` - The Tauri application handle
+///
+/// # Returns
+/// A Result containing the updated list, most recent first
+#[command]
+pub async fn remove_recent_project(path: String, app: AppHandle) -> Result<Vec<RecentProject>, String> {
+    let file = recent_projects_file(&app)?;
+    let path = canonical_project_path(&path);
+
+    let mut projects = read_recent_projects(&file)?;
+    let stored_count = projects.len();
+    projects.retain(|project| project.path != path && canonical_project_path(&project.path) != path);
+
+    if projects.len() != stored_count {
+        write_recent_projects(&file, &projects)?;
+    }
+
+    Ok(projects)
+}
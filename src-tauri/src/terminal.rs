@@ -2,25 +2,30 @@
 use std::sync::{Arc, Mutex};
 use tauri::{command, AppHandle, Window, State, Emitter, Manager};
 use tauri_plugin_shell::{ShellExt, process::{CommandEvent, CommandChild}};
-use std::collections::HashMap;
-use crate::process_tracker::{ProcessTracker, find_child_process};
+use std::collections::{HashMap, VecDeque};
+use crate::process_tracker::{ProcessTracker, find_child_process, find_descendant_processes};
 use sysinfo::Pid;
 use std::fs;
 use serde_json::{self, json};
 use regex::Regex;
+use crate::util::strip_ansi;
 
 /// State management for terminal sessions
 #[derive(Default)]
 pub struct TerminalState {
     processes: Arc<Mutex<HashMap<String, CommandChild>>>,
-    process_tracker: ProcessTracker
+    process_tracker: ProcessTracker,
+    directories: Arc<Mutex<HashMap<String, String>>>,
+    scrollback: Arc<Mutex<HashMap<String, ScrollbackBuffer>>>
 }
 
 /// Initializes a new terminal state with empty process tracking
 pub fn init_terminal_state() -> TerminalState {
     TerminalState {
         processes: Arc::new(Mutex::new(HashMap::new())),
-        process_tracker: ProcessTracker::new()
+        process_tracker: ProcessTracker::new(),
+        directories: Arc::new(Mutex::new(HashMap::new())),
+        scrollback: Arc::new(Mutex::new(HashMap::new()))
     }
 }
 
@@ -59,22 +64,214 @@ fn sanitize_terminal_output(text: &str) -> String {
     result
 }
 
-/// Sanitizes terminal output from raw bytes
+/// Payload of the `terminal://output` event, emitted for every chunk read from a session
+#[derive(Clone, serde::Serialize)]
+pub struct TerminalOutputEvent {
+    terminal_id: String,
+    /// Either "stdout" or "stderr"
+    stream: &'static str,
+    /// Per-session sequence number, so chunks can be applied strictly in order
+    seq: u64,
+    /// Raw output, including ANSI escape sequences
+    data: String,
+}
+
+/// Decodes as much of a byte stream as forms complete UTF-8
 /// 
 /// # Arguments
-/// * `bytes` - The terminal output bytes to sanitize
+/// * `pending` - Bytes of an incomplete character left over from the previous chunk
+/// * `bytes` - The newly read bytes
 /// 
 /// # Returns
-/// The sanitized text
-fn sanitize_terminal_bytes(bytes: &[u8]) -> String {
-    let text = String::from_utf8_lossy(bytes);
-    sanitize_terminal_output(&text)
+/// The decoded text; a trailing partial character is kept in `pending`
+fn decode_utf8_chunk(pending: &mut Vec<u8>, bytes: &[u8]) -> String {
+    pending.extend_from_slice(bytes);
+    
+    let complete_len = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        // error_len() is None only when the input ends in the middle of a character
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => pending.len(),
+    };
+    
+    let rest = pending.split_off(complete_len);
+    let text = String::from_utf8_lossy(pending).to_string();
+    *pending = rest;
+    text
+}
+
+/// Default number of lines kept per terminal session
+const DEFAULT_SCROLLBACK_LINES: usize = 5000;
+
+/// Default number of bytes of raw output kept per terminal session
+const DEFAULT_SCROLLBACK_BYTES: usize = 1024 * 1024;
+
+/// Minimum time between two `terminal://truncated` events of a session
+const TRUNCATION_EVENT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Bounded per-session history of raw output, discarding the oldest lines first
+pub struct ScrollbackBuffer {
+    lines: VecDeque<String>,
+    /// Output after the last newline, not yet a complete line
+    partial: String,
+    /// Absolute index of the first line still held
+    first_line: u64,
+    bytes: usize,
+    max_lines: usize,
+    max_bytes: usize,
+}
+
+impl ScrollbackBuffer {
+    fn new(max_lines: usize, max_bytes: usize) -> Self {
+        ScrollbackBuffer {
+            lines: VecDeque::new(),
+            partial: String::new(),
+            first_line: 0,
+            bytes: 0,
+            max_lines: max_lines.max(1),
+            max_bytes: max_bytes.max(1),
+        }
+    }
+    
+    /// Appends output, dropping the oldest lines past the limits
+    /// 
+    /// # Arguments
+    /// * `data` - The newly read output
+    /// 
+    /// # Returns
+    /// The number of lines that were discarded
+    fn push(&mut self, data: &str) -> u64 {
+        // The first segment continues the pending partial line; every later one starts a new line
+        let mut segments = data.split('\n');
+        if let Some(first) = segments.next() {
+            self.partial.push_str(first);
+        }
+        for segment in segments {
+            let line = std::mem::replace(&mut self.partial, segment.to_string());
+            self.push_line(line);
+        }
+        
+        // Output without newlines (progress bars) must not grow without bound either
+        if self.partial.len() > self.max_bytes {
+            let line = std::mem::take(&mut self.partial);
+            self.push_line(line);
+        }
+        
+        let mut dropped = 0;
+        while self.lines.len() > self.max_lines || (self.bytes > self.max_bytes && !self.lines.is_empty()) {
+            if let Some(line) = self.lines.pop_front() {
+                self.bytes -= line.len();
+                self.first_line += 1;
+                dropped += 1;
+            }
+        }
+        
+        dropped
+    }
+    
+    fn push_line(&mut self, line: String) {
+        self.bytes += line.len();
+        self.lines.push_back(line);
+    }
+    
+    /// The whole history as a single string
+    fn text(&self) -> String {
+        let mut text = String::with_capacity(self.bytes + self.lines.len() + self.partial.len());
+        for line in &self.lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.push_str(&self.partial);
+        text
+    }
+}
+
+/// Payload of the `terminal://truncated` event, emitted when old output is discarded
+#[derive(Clone, serde::Serialize)]
+pub struct TerminalTruncatedEvent {
+    terminal_id: String,
+    /// Absolute index of the oldest line still available
+    first_line: u64,
+}
+
+/// A page of scrollback returned to the frontend
+#[derive(serde::Serialize)]
+pub struct ScrollbackChunk {
+    /// Absolute index of the first returned line
+    first_line: u64,
+    /// Raw lines, including ANSI escape sequences; the last one may be incomplete
+    lines: Vec<String>,
+    /// Whether lines before `first_line` that were asked for have been discarded
+    truncated: bool,
+}
+
+/// Parses the contents of a `.env` file
+/// 
+/// Supports `KEY=value` and `export KEY=value` lines, `#` comments, single-quoted
+/// (literal) and double-quoted (with `\n`, `\t`, `\"` and `\\` escapes) values.
+/// Variable references like `$HOME` are not expanded.
+/// 
+/// # Arguments
+/// * `content` - The file contents
+/// 
+/// # Returns
+/// The variables, later definitions overriding earlier ones
+fn parse_dotenv(content: &str) -> HashMap<String, String> {
+    let mut vars = HashMap::new();
+    
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            continue;
+        }
+        
+        let value = value.trim();
+        let value = if let Some(quoted) = value.strip_prefix('"').and_then(|v| v.rfind('"').map(|end| &v[..end])) {
+            let mut unescaped = String::with_capacity(quoted.len());
+            let mut chars = quoted.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    unescaped.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('n') => unescaped.push('\n'),
+                    Some('t') => unescaped.push('\t'),
+                    Some(other) => unescaped.push(other),
+                    None => unescaped.push('\\'),
+                }
+            }
+            unescaped
+        } else if let Some(quoted) = value.strip_prefix('\'').and_then(|v| v.rfind('\'').map(|end| &v[..end])) {
+            quoted.to_string()
+        } else {
+            // Unquoted values end at an inline comment
+            value.split(" #").next().unwrap_or("").trim_end().to_string()
+        };
+        
+        vars.insert(key.to_string(), value);
+    }
+    
+    vars
 }
 
 /// Creates a new terminal session with the specified working directory
 /// 
 /// # Arguments
 /// * `working_dir` - The directory where the terminal session should start
+/// * `scrollback_lines` - Lines of history to keep (defaults to 5000)
+/// * `scrollback_bytes` - Bytes of history to keep (defaults to 1 MiB)
+/// * `env` - Variables set in the shell on top of the inherited environment
+/// * `load_dotenv` - Whether to also load a `.env` file from `working_dir` (overridden by `env`)
 /// * `state` - The terminal state manager
 /// * `app` - The Tauri application handle
 /// * `window` - The window where the terminal should be displayed
@@ -82,8 +279,13 @@ fn sanitize_terminal_bytes(bytes: &[u8]) -> String {
 /// # Returns
 /// A Result containing the session ID if successful, or an error message
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn create_terminal_session(
     working_dir: String,
+    scrollback_lines: Option<usize>,
+    scrollback_bytes: Option<usize>,
+    env: Option<HashMap<String, String>>,
+    load_dotenv: Option<bool>,
     state: State<'_, TerminalState>,
     app: AppHandle,
     window: Window
@@ -104,10 +306,27 @@ pub async fn create_terminal_session(
     #[cfg(target_os = "linux")]
     let (cmd, args): (&str, Vec<&str>) = ("bash", vec![]);
     
+    state.directories.lock().unwrap().insert(id.clone(), working_dir.clone());
+    state.scrollback.lock().unwrap().insert(id.clone(), ScrollbackBuffer::new(
+        scrollback_lines.unwrap_or(DEFAULT_SCROLLBACK_LINES),
+        scrollback_bytes.unwrap_or(DEFAULT_SCROLLBACK_BYTES),
+    ));
+    
+    let mut session_env = HashMap::new();
+    if load_dotenv.unwrap_or(false) {
+        // A missing or unreadable .env is not an error, the session just starts without it
+        if let Ok(content) = fs::read_to_string(std::path::Path::new(&working_dir).join(".env")) {
+            session_env.extend(parse_dotenv(&content));
+        }
+    }
+    session_env.extend(env.unwrap_or_default());
+    
     let shell = app.shell();
+    // Added on top of the inherited environment, which is kept
     let command = shell.command(cmd)
         .args(args)
-        .current_dir(working_dir);
+        .current_dir(working_dir)
+        .envs(session_env);
     
     let (mut rx, child) = command.spawn()
         .map_err(|e| format!("Failed to spawn process: {}", e))?;
@@ -122,31 +341,50 @@ pub async fn create_terminal_session(
     }
     
     let window_clone = window.clone();
+    let scrollback = state.scrollback.clone();
     tauri::async_runtime::spawn(async move {
+        // Events of a session are handled by this single task, so they are emitted in read order
+        let mut seq: u64 = 0;
+        let mut pending_stdout = Vec::new();
+        let mut pending_stderr = Vec::new();
+        let mut last_truncation_event: Option<std::time::Instant> = None;
+        
         while let Some(event) = rx.recv().await {
-            match event {
-                CommandEvent::Stdout(bytes) => {
-                    match String::from_utf8(bytes.clone()) {
-                        Ok(text) => {
-                            // Sanitize the output before sending to the frontend
-                            let sanitized_text = sanitize_terminal_output(&text);
-                            let _ = window_clone.emit(&format!("terminal_output_{}", id_clone), sanitized_text);
-                        },
-                        Err(_) => {
-                            // W przypadku nieprawidłowego UTF-8, używamy nowej funkcji sanityzującej bajty
-                            let sanitized_text = sanitize_terminal_bytes(&bytes);
-                            let _ = window_clone.emit(
-                                &format!("terminal_output_{}", id_clone), 
-                                sanitized_text
-                            );
+            let chunk = match &event {
+                CommandEvent::Stdout(bytes) => Some(("stdout", decode_utf8_chunk(&mut pending_stdout, bytes))),
+                CommandEvent::Stderr(bytes) => Some(("stderr", decode_utf8_chunk(&mut pending_stderr, bytes))),
+                _ => None,
+            };
+            
+            if let Some((stream, data)) = chunk {
+                if !data.is_empty() {
+                    let truncated = scrollback.lock().unwrap()
+                        .get_mut(&id_clone)
+                        .and_then(|buffer| (buffer.push(&data) > 0).then_some(buffer.first_line));
+                    
+                    // Throttled, so a flood of output doesn't flood the frontend with markers too
+                    if let Some(first_line) = truncated {
+                        if last_truncation_event.is_none_or(|at| at.elapsed() >= TRUNCATION_EVENT_INTERVAL) {
+                            last_truncation_event = Some(std::time::Instant::now());
+                            let _ = window_clone.emit("terminal://truncated", TerminalTruncatedEvent {
+                                terminal_id: id_clone.clone(),
+                                first_line,
+                            });
                         }
                     }
+                    
+                    let _ = window_clone.emit("terminal://output", TerminalOutputEvent {
+                        terminal_id: id_clone.clone(),
+                        stream,
+                        seq,
+                        data,
+                    });
+                    seq += 1;
                 }
-                CommandEvent::Stderr(line) => {
-                    // Sanitize error output too
-                    let sanitized_line = sanitize_terminal_bytes(&line);
-                    let _ = window_clone.emit(&format!("terminal_error_{}", id_clone), sanitized_line);
-                }
+            }
+            
+            // Output chunks went out as `terminal://output` above
+            match event {
                 CommandEvent::Error(err) => {
                     let error_message = format!("Error: {}", err);
                     let sanitized_error = sanitize_terminal_output(&error_message);
@@ -213,6 +451,9 @@ pub async fn terminate_terminal_session(
     let mut processes = state.processes.lock().unwrap();
     
     if let Some(process) = processes.remove(&id) {
+        state.directories.lock().unwrap().remove(&id);
+        state.scrollback.lock().unwrap().remove(&id);
+        
         process.kill()
             .map_err(|e| format!("Failed to kill process: {}", e))?;
         
@@ -244,6 +485,8 @@ pub async fn update_terminal_directory(
         process.write(command.as_bytes())
             .map_err(|e| format!("Failed to update directory: {}", e))?;
         
+        state.directories.lock().unwrap().insert(id, directory);
+        
         Ok(())
     } else {
         Err(format!("No terminal session with id: {}", id))
@@ -263,30 +506,100 @@ pub async fn get_terminal_process_name(
     id: String,
     state: State<'_, TerminalState>
 ) -> Result<String, String> {
-    if let Some(process_name) = state.process_tracker.get_process_name(&id) {
-        Ok(process_name)
+    Ok(state.process_tracker.get_process_name(&id).unwrap_or_else(default_shell_name))
+}
+
+/// Name reported for a session the process tracker hasn't inspected yet
+fn default_shell_name() -> String {
+    if cfg!(target_os = "windows") {
+        "cmd".to_string()
+    } else if cfg!(target_os = "macos") {
+        "zsh".to_string()
     } else {
-        #[cfg(target_os = "windows")]
-        return Ok("cmd".to_string());
+        "bash".to_string()
+    }
+}
 
-        #[cfg(target_os = "macos")]
-        return Ok("zsh".to_string());
+/// Key of the global history bucket used for commands run outside any project
+const GLOBAL_HISTORY_KEY: &str = "global";
 
-        #[cfg(target_os = "linux")]
-        return Ok("bash".to_string());
+/// Resolves the history bucket for a terminal session
+/// 
+/// Commands are grouped by the project (nearest ancestor containing `.git`)
+/// of the session's working directory, falling back to the global bucket.
+/// 
+/// # Arguments
+/// * `id` - The ID of the terminal session, if any
+/// * `state` - The terminal state manager
+/// 
+/// # Returns
+/// The project root path or the global key
+fn history_key(id: Option<&str>, state: &TerminalState) -> String {
+    let directory = id.and_then(|id| state.directories.lock().unwrap().get(id).cloned());
+    
+    directory
+        .and_then(|directory| {
+            std::path::Path::new(&directory)
+                .ancestors()
+                .find(|ancestor| ancestor.join(".git").exists())
+                .map(|root| root.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| GLOBAL_HISTORY_KEY.to_string())
+}
+
+/// Reads all history buckets, migrating the old flat format into the global bucket
+/// 
+/// # Arguments
+/// * `history_file` - Path of the history JSON file
+/// 
+/// # Returns
+/// A map from history key to commands
+fn read_history_buckets(history_file: &std::path::Path) -> Result<HashMap<String, Vec<String>>, String> {
+    if !history_file.exists() {
+        return Ok(HashMap::new());
+    }
+    
+    let content = fs::read_to_string(history_file)
+        .map_err(|e| e.to_string())?;
+    
+    let history: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| e.to_string())?;
+    
+    let to_commands = |value: &serde_json::Value| -> Vec<String> {
+        value.as_array()
+            .map(|commands| commands.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default()
+    };
+    
+    if let Some(directories) = history["directories"].as_object() {
+        Ok(directories.iter().map(|(key, commands)| (key.clone(), to_commands(commands))).collect())
+    } else if history["commands"].is_array() {
+        // Histories saved before per-directory keys were a single flat list
+        Ok(HashMap::from([(GLOBAL_HISTORY_KEY.to_string(), to_commands(&history["commands"]))]))
+    } else {
+        Err("Invalid history format".to_string())
     }
 }
 
-/// Saves the command history to a JSON file
+/// Saves the command history of a terminal session's project to a JSON file.
+/// A history file that can't be parsed is kept as `history.<timestamp>.json.bak`
+/// instead of being overwritten
 /// 
 /// # Arguments
 /// * `history` - Vector of commands to save
+/// * `id` - The ID of the terminal session whose working directory keys the history
+/// * `state` - The terminal state manager
 /// * `app` - The Tauri application handle
 /// 
 /// # Returns
 /// A Result indicating success or failure
 #[tauri::command]
-pub async fn save_command_history(history: Vec<String>, app: AppHandle) -> Result<(), String> {
+pub async fn save_command_history(
+    history: Vec<String>,
+    id: Option<String>,
+    state: State<'_, TerminalState>,
+    app: AppHandle
+) -> Result<(), String> {
     let app_dir = app.path()
         .app_data_dir()
         .map_err(|e| e.to_string())?;
@@ -295,8 +608,22 @@ pub async fn save_command_history(history: Vec<String>, app: AppHandle) -> Resul
     fs::create_dir_all(&history_dir).map_err(|e| e.to_string())?;
     
     let history_file = history_dir.join("history.json");
+    
+    // An unreadable file is moved aside rather than blocking new history, and never overwritten
+    let mut buckets = match read_history_buckets(&history_file) {
+        Ok(buckets) => buckets,
+        Err(e) => {
+            let backup = history_dir.join(format!("history.{}.json.bak", chrono::Local::now().format("%Y%m%d%H%M%S")));
+            fs::rename(&history_file, &backup)
+                .map_err(|rename_error| format!("History file is unreadable ({}) and could not be backed up: {}", e, rename_error))?;
+            HashMap::new()
+        }
+    };
+    buckets.insert(history_key(id.as_deref(), &state), history);
+    
     let history_json = json!({
-        "commands": history,
+        "version": 2,
+        "directories": buckets,
         "timestamp": chrono::Local::now().to_rfc3339()
     });
     
@@ -304,39 +631,30 @@ pub async fn save_command_history(history: Vec<String>, app: AppHandle) -> Resul
         .map_err(|e| e.to_string())
 }
 
-/// Loads the command history from a JSON file
+/// Loads the command history of a terminal session's project from a JSON file
 /// 
 /// # Arguments
+/// * `id` - The ID of the terminal session whose working directory keys the history
+/// * `state` - The terminal state manager
 /// * `app` - The Tauri application handle
 /// 
 /// # Returns
 /// A Result containing the command history or an empty vector if no history exists
 #[tauri::command]
-pub async fn load_command_history(app: AppHandle) -> Result<Vec<String>, String> {
+pub async fn load_command_history(
+    id: Option<String>,
+    state: State<'_, TerminalState>,
+    app: AppHandle
+) -> Result<Vec<String>, String> {
     let app_dir = app.path()
         .app_data_dir()
         .map_err(|e| e.to_string())?;
     
     let history_file = app_dir.join("terminal_history").join("history.json");
     
-    if !history_file.exists() {
-        return Ok(Vec::new());
-    }
-    
-    let content = fs::read_to_string(history_file)
-        .map_err(|e| e.to_string())?;
-    
-    let history: serde_json::Value = serde_json::from_str(&content)
-        .map_err(|e| e.to_string())?;
-    
-    let commands = history["commands"]
-        .as_array()
-        .ok_or_else(|| "Invalid history format".to_string())?
-        .iter()
-        .filter_map(|v| v.as_str().map(|s| s.to_string()))
-        .collect();
+    let mut buckets = read_history_buckets(&history_file)?;
     
-    Ok(commands)
+    Ok(buckets.remove(&history_key(id.as_deref(), &state)).unwrap_or_default())
 }
 
 /// Sends a signal to a terminal session
@@ -487,6 +805,240 @@ pub async fn has_child_process(
     }
 }
 
+/// Signals every process below a terminal's shell. SIGTERM is escalated to SIGKILL
+/// for processes that are still running after a grace period; other signals
+/// (e.g. SIGINT for a REPL) are sent once
+/// 
+/// # Arguments
+/// * `id` - The ID of the terminal session
+/// * `signal` - The signal to send first (defaults to "SIGTERM")
+/// * `state` - The terminal state manager
+/// 
+/// # Returns
+/// A Result containing the number of processes that were signaled
+#[command]
+pub async fn kill_terminal_children(
+    id: String,
+    signal: Option<String>,
+    state: State<'_, TerminalState>
+) -> Result<usize, String> {
+    use sysinfo::{System, Signal};
+    
+    let shell_pid = state.process_tracker.get_tracked_pid(&id)
+        .ok_or_else(|| format!("No terminal session with id: {}", id))?;
+    
+    let signal = signal.unwrap_or_else(|| "SIGTERM".to_string());
+    let sig = match signal.as_str() {
+        "SIGTERM" => Signal::Term,
+        "SIGINT" => Signal::Interrupt,
+        "SIGHUP" => Signal::Hangup,
+        "SIGQUIT" => Signal::Quit,
+        "SIGKILL" => Signal::Kill,
+        _ => return Err(format!("Unsupported signal: {}", signal)),
+    };
+    
+    let mut system = System::new();
+    system.refresh_processes();
+    
+    // Deepest processes first so parents can't respawn children mid-way
+    let descendants = find_descendant_processes(&system, shell_pid);
+    // Start times identify the signaled processes in case their pids get reused
+    let mut signaled = Vec::new();
+    
+    for pid in &descendants {
+        if let Some(process) = system.process(*pid) {
+            // Platforms without POSIX signals only support a hard kill
+            let sent = process.kill_with(sig).unwrap_or_else(|| process.kill());
+            if sent {
+                signaled.push((*pid, process.start_time()));
+            }
+        }
+    }
+    
+    if sig == Signal::Term && !signaled.is_empty() {
+        // Grace period before escalating to SIGKILL for anything still alive
+        tokio::time::sleep(std::time::Duration::from_millis(2000)).await;
+        
+        system.refresh_processes();
+        let still_below_shell = find_descendant_processes(&system, shell_pid);
+        for (pid, start_time) in &signaled {
+            if !still_below_shell.contains(pid) {
+                continue;
+            }
+            if let Some(process) = system.process(*pid) {
+                if process.start_time() == *start_time {
+                    process.kill();
+                }
+            }
+        }
+    }
+    
+    Ok(signaled.len())
+}
+
+/// Gets the actual working directory of a terminal session
+/// 
+/// # Arguments
+/// * `id` - The ID of the terminal session
+/// * `state` - The terminal state manager
+/// 
+/// # Returns
+/// The cwd of the session's leaf process as reported by the OS, or the last
+/// directory set via `update_terminal_directory` when it can't be queried
+#[command]
+pub async fn get_terminal_cwd(
+    id: String,
+    state: State<'_, TerminalState>
+) -> Result<String, String> {
+    use sysinfo::{ProcessRefreshKind, System, UpdateKind};
+    
+    let known_directory = state.directories.lock().unwrap().get(&id).cloned();
+    
+    let shell_pid = match state.process_tracker.get_tracked_pid(&id) {
+        Some(pid) => pid,
+        None => return known_directory.ok_or_else(|| format!("No terminal session with id: {}", id)),
+    };
+    
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessRefreshKind::new().with_cwd(UpdateKind::Always));
+    
+    process_tree_cwd(&system, shell_pid)
+        .or(known_directory)
+        .ok_or_else(|| "Unable to determine the terminal working directory".to_string())
+}
+
+/// Gets the OS-reported working directory below a terminal's shell
+/// 
+/// # Arguments
+/// * `system` - Process snapshot refreshed with cwd information
+/// * `shell_pid` - The PID of the session's shell
+/// 
+/// # Returns
+/// The cwd of the leaf process, falling back to the shell's own
+fn process_tree_cwd(system: &sysinfo::System, shell_pid: Pid) -> Option<String> {
+    // The most recently discovered descendant is the leaf; the shell itself is the last resort
+    find_descendant_processes(system, shell_pid)
+        .into_iter()
+        .chain(std::iter::once(shell_pid))
+        .filter_map(|pid| system.process(pid).and_then(|process| process.cwd()))
+        .find(|cwd| !cwd.as_os_str().is_empty())
+        .map(|cwd| cwd.to_string_lossy().to_string())
+}
+
+/// Summary of a live terminal session
+#[derive(serde::Serialize)]
+pub struct TerminalSessionInfo {
+    id: String,
+    /// Working directory as reported by the OS, or the last one set by the frontend
+    cwd: Option<String>,
+    /// Same value as `get_terminal_process_name`
+    process_name: String,
+    has_child_process: bool,
+}
+
+/// Lists every live terminal session, so the frontend can rebuild its tabs
+/// 
+/// # Arguments
+/// * `state` - The terminal state manager
+/// 
+/// # Returns
+/// The sessions, ordered by ID
+#[command]
+pub async fn list_terminal_sessions(
+    state: State<'_, TerminalState>
+) -> Result<Vec<TerminalSessionInfo>, String> {
+    use sysinfo::{ProcessRefreshKind, System, UpdateKind};
+    
+    let mut ids: Vec<String> = state.processes.lock().unwrap().keys().cloned().collect();
+    ids.sort();
+    
+    let mut system = System::new();
+    system.refresh_processes_specifics(ProcessRefreshKind::new().with_cwd(UpdateKind::Always));
+    
+    let directories = state.directories.lock().unwrap();
+    
+    let sessions = ids.into_iter()
+        .map(|id| {
+            let shell_pid = state.process_tracker.get_tracked_pid(&id);
+            let cwd = shell_pid
+                .and_then(|pid| process_tree_cwd(&system, pid))
+                .or_else(|| directories.get(&id).cloned());
+            let has_child_process = shell_pid
+                .is_some_and(|pid| find_child_process(&system, pid).is_some());
+            
+            TerminalSessionInfo {
+                process_name: state.process_tracker.get_process_name(&id).unwrap_or_else(default_shell_name),
+                cwd,
+                has_child_process,
+                id,
+            }
+        })
+        .collect();
+    
+    Ok(sessions)
+}
+
+/// Gets the captured output of a terminal session as plain text
+/// 
+/// # Arguments
+/// * `id` - The ID of the terminal session
+/// * `raw` - Whether to keep ANSI escape sequences (for a real terminal renderer)
+/// * `state` - The terminal state manager
+/// 
+/// # Returns
+/// The session's scrollback, with ANSI escape sequences removed unless `raw` is set
+#[command]
+pub async fn get_terminal_plain_output(
+    id: String,
+    raw: Option<bool>,
+    state: State<'_, TerminalState>
+) -> Result<String, String> {
+    let scrollback = state.scrollback.lock().unwrap();
+    let output = scrollback.get(&id)
+        .ok_or_else(|| format!("No terminal session with id: {}", id))?;
+    
+    if raw.unwrap_or(false) {
+        Ok(output.text())
+    } else {
+        Ok(strip_ansi(&output.text()))
+    }
+}
+
+/// Gets the retained history of a terminal session, starting at a line
+/// 
+/// # Arguments
+/// * `terminal_id` - The ID of the terminal session
+/// * `from_line` - Absolute index of the first line wanted (defaults to the oldest available)
+/// * `state` - The terminal state manager
+/// 
+/// # Returns
+/// The lines still held from `from_line` on, and whether older ones were discarded
+#[command]
+pub async fn get_terminal_scrollback(
+    terminal_id: String,
+    from_line: Option<u64>,
+    state: State<'_, TerminalState>
+) -> Result<ScrollbackChunk, String> {
+    let scrollback = state.scrollback.lock().unwrap();
+    let buffer = scrollback.get(&terminal_id)
+        .ok_or_else(|| format!("No terminal session with id: {}", terminal_id))?;
+    
+    let from_line = from_line.unwrap_or(buffer.first_line);
+    let start = from_line.max(buffer.first_line);
+    let skip = (start - buffer.first_line) as usize;
+    
+    let mut lines: Vec<String> = buffer.lines.iter().skip(skip).cloned().collect();
+    if !buffer.partial.is_empty() {
+        lines.push(buffer.partial.clone());
+    }
+    
+    Ok(ScrollbackChunk {
+        first_line: start,
+        lines,
+        truncated: from_line < buffer.first_line,
+    })
+}
+
 /// Detects URLs in the given text
 /// 
 /// # Arguments
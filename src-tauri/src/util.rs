@@ -0,0 +1,65 @@
+//! Shared helpers used by the terminal and LSP modules
+
+/// Removes ANSI escape sequences from captured output
+///
+/// Handles CSI sequences (including SGR colors and cursor movement), OSC
+/// sequences such as hyperlinks and window titles, and the remaining
+/// two-character escapes, so the text can be shown in a plain widget.
+///
+/// # Arguments
+/// * `input` - The text that may contain escape sequences
+///
+/// # Returns
+/// The text with all escape sequences removed
+pub fn strip_ansi(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x1b' => match chars.next() {
+                // CSI: parameter and intermediate bytes, then a single final byte
+                Some('[') => {
+                    for c in chars.by_ref() {
+                        if ('\x40'..='\x7e').contains(&c) {
+                            break;
+                        }
+                    }
+                }
+                // OSC, DCS and friends: terminated by BEL or ST (ESC \)
+                Some(']') | Some('P') | Some('X') | Some('^') | Some('_') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\x07' {
+                            break;
+                        }
+                        if c == '\x1b' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                // Character set selection and similar: intermediate bytes, then a final byte
+                Some(c) if ('\x20'..='\x2f').contains(&c) => {
+                    while let Some(&next) = chars.peek() {
+                        chars.next();
+                        if !('\x20'..='\x2f').contains(&next) {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            },
+            // 8-bit CSI
+            '\u{9b}' => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
@@ -37,6 +37,18 @@ interface TerminalInstance {
   processName: string;
 }
 
+interface TerminalOutputEvent {
+  terminal_id: string;
+  stream: 'stdout' | 'stderr';
+  seq: number;
+  data: string;
+}
+
+// Output events carry raw escape sequences; the plain-text view drops them
+const ANSI_PATTERN = /\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b\[[0-?]*[ -/]*[@-~]|\x1b[()][0-9A-Za-z]|\x1b[^[\]]/g;
+
+const stripAnsi = (text: string): string => text.replace(ANSI_PATTERN, '');
+
 const detectUrls = (text: string): { text: string; isUrl: boolean; url: string }[] => {
   const urlRegex = /(https?:\/\/[^\s]+)/g;
   const parts: { text: string; isUrl: boolean; url: string }[] = [];
@@ -84,19 +96,19 @@ const Terminal: React.FC<TerminalProps> = ({
 
   useEffect(() => {
     const loadHistory = async () => {
-      try {
-        const history = await invoke<string[]>('load_command_history');
-        if (history && history.length > 0) {
-          setInstances(prev => prev.map(instance => ({
-            ...instance,
-            state: {
-              ...instance.state,
-              commandHistory: history
-            }
-          })));
+      for (const instance of instances) {
+        try {
+          const history = await invoke<string[]>('load_command_history', { id: instance.id });
+          if (history && history.length > 0) {
+            setInstances(prev => prev.map(i => 
+              i.id === instance.id 
+                ? { ...i, state: { ...i.state, commandHistory: history } }
+                : i
+            ));
+          }
+        } catch (error) {
+          console.error('Failed to load command history:', error);
         }
-      } catch (error) {
-        console.error('Failed to load command history:', error);
       }
     };
 
@@ -131,7 +143,8 @@ const Terminal: React.FC<TerminalProps> = ({
       if (activeInstance && activeInstance.state.commandHistory.length > 0) {
         try {
           await invoke('save_command_history', { 
-            history: activeInstance.state.commandHistory 
+            history: activeInstance.state.commandHistory,
+            id: activeInstance.id
           });
         } catch (error) {
           console.error('Failed to save command history:', error);
@@ -149,6 +162,10 @@ const Terminal: React.FC<TerminalProps> = ({
       });
 
       const processName = await invoke<string>('get_terminal_process_name', { id });
+      const commandHistory = await invoke<string[]>('load_command_history', { id }).catch((error) => {
+        console.error('Failed to load command history:', error);
+        return [];
+      });
 
       const newInstance: TerminalInstance = {
         id,
@@ -157,8 +174,8 @@ const Terminal: React.FC<TerminalProps> = ({
           output: [],
           currentInput: '',
           sessionId: id,
-          commandHistory: [],
-          historyIndex: 0,
+          commandHistory,
+          historyIndex: commandHistory.length,
           isLocked: false
         },
         workingDirectory: currentDirectory || workingDirectory || '.',
@@ -168,15 +185,12 @@ const Terminal: React.FC<TerminalProps> = ({
       setInstances(prev => [...prev, newInstance]);
       setActiveInstanceId(id);
 
-      const stdoutUnlisten = await listen<string>(`terminal_output_${id}`, (event) => {
-        let output = '';
-        if (typeof event.payload === 'string') {
-          output = event.payload;
-        } else if (Array.isArray(event.payload)) {
-          output = String.fromCharCode(...(event.payload as number[]));
-        } else {
-          output = String(event.payload);
+      const outputUnlisten = await listen<TerminalOutputEvent>('terminal://output', (event) => {
+        if (event.payload.terminal_id !== id) {
+          return;
         }
+        const text = stripAnsi(event.payload.data);
+        const output = event.payload.stream === 'stderr' ? `Error: ${text}` : text;
         setInstances(prev => prev.map(instance => 
           instance.id === id 
             ? { ...instance, state: { ...instance.state, output: [...instance.state.output, output] } }
@@ -184,18 +198,10 @@ const Terminal: React.FC<TerminalProps> = ({
         ));
       });
 
-      const stderrUnlisten = await listen<string>(`terminal_error_${id}`, (event) => {
-        let error = '';
-        if (typeof event.payload === 'string') {
-          error = event.payload;
-        } else if (Array.isArray(event.payload)) {
-          error = String.fromCharCode(...(event.payload as number[]));
-        } else {
-          error = String(event.payload);
-        }
+      const errorUnlisten = await listen<string>(`terminal_error_${id}`, (event) => {
         setInstances(prev => prev.map(instance => 
           instance.id === id 
-            ? { ...instance, state: { ...instance.state, output: [...instance.state.output, `Error: ${error}`] } }
+            ? { ...instance, state: { ...instance.state, output: [...instance.state.output, event.payload] } }
             : instance
         ));
       });
@@ -209,8 +215,8 @@ const Terminal: React.FC<TerminalProps> = ({
       });
 
       return () => {
-        stdoutUnlisten();
-        stderrUnlisten();
+        outputUnlisten();
+        errorUnlisten();
         exitUnlisten();
       };
     } catch (error) {
@@ -270,7 +270,7 @@ export class FileService {
         if (fileInfo.content.length === 0) {
           try {
             console.log(`File content appears empty, trying direct read...`);
-            const directContent = await nativeFs.readFile(filePath);
+            const { content: directContent } = await nativeFs.readFile(filePath);
             console.log(`Direct read result, content length: ${directContent.length}, preview: ${directContent.substring(0, 50)}...`);
             
             if (directContent.length > 0) {
@@ -24,6 +24,15 @@ export interface FileInfo {
   is_unsaved: boolean;
 }
 
+/**
+ * File content with the version it was read at
+ */
+export interface FileContent {
+  content: string;
+  mtime: number;
+  hash: string;
+}
+
 /**
  * Directory item structure
  */
@@ -71,9 +80,9 @@ export async function createFile(path: string, content: string): Promise<void> {
 /**
  * Read the content of a file
  * @param path - Path of the file to read
- * @returns Promise that resolves to the file content
+ * @returns Promise that resolves to the file content, its mtime and its sha256 hash
  */
-export async function readFile(path: string): Promise<string> {
+export async function readFile(path: string): Promise<FileContent> {
   return invoke('read_file', { path });
 }
 
@@ -62,7 +62,7 @@ export const createClipboardSlice: StateCreator<
         window.alert("Directory paste operations are not fully implemented yet.");
         return;
       } else {
-        const fileContent = await nativeFs.readFile(clipboard.path);
+        const { content: fileContent } = await nativeFs.readFile(clipboard.path);
         
         await nativeFs.createFile(destinationPath, fileContent);
         